@@ -0,0 +1,438 @@
+//! Headless subcommands for scripts: `gity log` and `gity search` print
+//! commits without starting GTK or needing a display.
+//!
+//! Both walk history with the same `git::CommitWalker` as the commit list,
+//! and `search` goes through `SearchHandler`, so results (and their order)
+//! match what the window shows for the same ref, path and query.
+
+use chrono::{DateTime, FixedOffset};
+use git2::{Oid, Repository};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::git;
+use crate::logger::Logger;
+use crate::search::SearchHandler;
+
+pub const USAGE: &str = "\
+Usage:
+  gity [--ref <ref>] [--commit <sha>] [--file <path>] [<repository>]
+  gity x-gity://open?repo=<path>[&ref=<ref>][&commit=<sha>][&file=<path>]
+  gity log [<ref>] [--path <path>] [--author <text>] [--committer <text>]
+           [--grep <text>] [-n <count>] [-C <repository>] [--json]
+  gity search <query> [--ref <ref>] [--path <path>] [-n <count>]
+           [-C <repository>] [--json]
+
+<ref> defaults to the branch GitY opens first (the checked-out branch).
+<repository> defaults to the repository containing the current directory.
+--path limits the history to a file or folder, following renames of a file.
+search uses GitY's query syntax; a 7-40 digit hex query matches SHA prefixes
+first and only falls back to a text search when no commit SHA matches.
+Unknown options of search are query terms, so `-author:bob` negates a term;
+put the query after `--` if a term looks like one of the options above.";
+
+/// Exit status for a bad command line.
+const EXIT_USAGE: u8 = 2;
+
+/// Options shared by the subcommands.
+#[derive(Debug, Default, PartialEq)]
+struct CommonArgs {
+    repo: Option<PathBuf>,
+    reference: Option<String>,
+    path: Option<String>,
+    max_count: Option<usize>,
+    json: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Log {
+        common: CommonArgs,
+        author: Option<String>,
+        committer: Option<String>,
+        grep: Option<String>,
+    },
+    Search {
+        common: CommonArgs,
+        query: String,
+    },
+    Help,
+}
+
+/// One commit in the output.
+#[derive(Debug, Serialize)]
+struct CommitRecord {
+    /// Position in the (path-filtered) history of the ref, as in the commit list.
+    index: usize,
+    sha: String,
+    parents: Vec<String>,
+    author: String,
+    author_email: String,
+    /// Author date, RFC 3339 with the author's offset.
+    date: String,
+    summary: String,
+    message: String,
+}
+
+/// Whether `args` (without the program name) start with a subcommand.
+/// Anything else is left to GTK as a repository path or GTK option.
+pub fn is_subcommand(args: &[String]) -> bool {
+    matches!(args.first().map(String::as_str), Some("log" | "search"))
+}
+
+/// Run the subcommand in `args` and return the process exit status.
+pub fn run(args: &[String]) -> u8 {
+    // Keep stdout clean for the output scripts parse.
+    Logger::log_to_stderr();
+
+    let command = match parse_args(args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("gity: {message}\n\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+    let mut out = std::io::stdout().lock();
+    match execute(command, &mut out) {
+        Ok(()) => 0,
+        Err(message) => {
+            eprintln!("gity: {message}");
+            1
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (subcommand, rest) = args.split_first().ok_or("missing subcommand")?;
+    let mut common = CommonArgs::default();
+    let mut author = None;
+    let mut committer = None;
+    let mut grep = None;
+    let mut positional: Vec<String> = Vec::new();
+
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{name} needs a value"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--json" => common.json = true,
+            "-C" | "--repo" => common.repo = Some(PathBuf::from(value(arg)?)),
+            "--path" => common.path = Some(value(arg)?),
+            "-n" | "--max-count" => {
+                let count = value(arg)?;
+                let count = count
+                    .parse()
+                    .map_err(|_| format!("{arg}: not a number: {count}"))?;
+                common.max_count = Some(count);
+            }
+            "--ref" if subcommand == "search" => common.reference = Some(value(arg)?),
+            "--author" if subcommand == "log" => author = Some(value(arg)?),
+            "--committer" if subcommand == "log" => committer = Some(value(arg)?),
+            "--grep" if subcommand == "log" => grep = Some(value(arg)?),
+            "--" => positional.extend(iter.by_ref().cloned()),
+            // Search terms can be negated (`-author:bob`), so only `log`
+            // rejects unknown options.
+            flag if flag.starts_with('-') && flag.len() > 1 && subcommand != "search" => {
+                return Err(format!("unknown option for {subcommand}: {flag}"));
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    match subcommand.as_str() {
+        "log" => {
+            if positional.len() > 1 {
+                return Err(format!("log takes one ref, got: {}", positional.join(" ")));
+            }
+            common.reference = positional.pop();
+            Ok(Command::Log {
+                common,
+                author,
+                committer,
+                grep,
+            })
+        }
+        "search" => {
+            // Unquoted words form one query, as typed into the search bar.
+            let query = positional.join(" ");
+            if query.trim().is_empty() {
+                return Err("search needs a query".to_string());
+            }
+            Ok(Command::Search { common, query })
+        }
+        other => Err(format!("unknown subcommand: {other}")),
+    }
+}
+
+fn execute(command: Command, out: &mut impl Write) -> Result<(), String> {
+    match command {
+        Command::Help => writeln!(out, "{USAGE}").map_err(|e| e.to_string()),
+        Command::Log {
+            common,
+            author,
+            committer,
+            grep,
+        } => {
+            let repo_path = resolve_repo(common.repo.as_deref())?;
+            let reference = resolve_ref(&repo_path, common.reference.as_deref());
+            let mut opts = query_options(&reference, common.path.as_deref());
+            opts.author_contains = author;
+            opts.committer_contains = committer;
+            opts.message_contains = grep;
+            let records = log_records(&repo_path, opts, common.max_count)?;
+            print_records(&records, common.json, out)
+        }
+        Command::Search { common, query } => {
+            let repo_path = resolve_repo(common.repo.as_deref())?;
+            let reference = resolve_ref(&repo_path, common.reference.as_deref());
+            let records = search_records(
+                &repo_path,
+                &reference,
+                common.path.as_deref(),
+                &query,
+                common.max_count,
+            )?;
+            print_records(&records, common.json, out)
+        }
+    }
+}
+
+/// The repository containing `path` (the current directory by default),
+/// opened at its root like the window does.
+fn resolve_repo(path: Option<&Path>) -> Result<PathBuf, String> {
+    let start = match path {
+        Some(path) => path.to_path_buf(),
+        None => std::env::current_dir().map_err(|e| e.to_string())?,
+    };
+    git::discover_repository_root(&start)
+        .ok_or_else(|| format!("not a git repository: {}", start.display()))
+}
+
+fn resolve_ref(repo_path: &Path, reference: Option<&str>) -> String {
+    reference
+        .map(str::to_string)
+        .unwrap_or_else(|| git::default_branch_ref(repo_path))
+}
+
+/// Walker options the commit list uses for `reference`, limited to `path`.
+fn query_options(reference: &str, path: Option<&str>) -> git::CommitQueryOptions {
+    let mut opts = git::CommitQueryOptions::for_branch(reference);
+    if let Some(path) = path {
+        opts.pathspecs = vec![path.to_string()];
+        opts.follow_renames = true;
+    }
+    opts
+}
+
+fn log_records(
+    repo_path: &Path,
+    opts: git::CommitQueryOptions,
+    max_count: Option<usize>,
+) -> Result<Vec<CommitRecord>, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let mut walker = git::CommitWalker::new(&repo, opts).map_err(|e| e.message().to_string())?;
+    let mut records = Vec::new();
+    let mut index = 0;
+    while max_count.is_none_or(|max| records.len() < max) {
+        let Some(commit) = walker.next(None) else {
+            break;
+        };
+        let commit = commit.map_err(|e| e.message().to_string())?;
+        records.push(commit_record(&repo, index, &commit.id)?);
+        index += 1;
+    }
+    Ok(records)
+}
+
+fn search_records(
+    repo_path: &Path,
+    reference: &str,
+    path: Option<&str>,
+    query: &str,
+    max_count: Option<usize>,
+) -> Result<Vec<CommitRecord>, String> {
+    let handler = SearchHandler::with_path_filter(Arc::new(Mutex::new(path.map(str::to_string))));
+    let mut indices =
+        handler.find_matching_indices_in_repo(&repo_path.to_path_buf(), reference, query)?;
+    indices.sort_unstable();
+    if let Some(max) = max_count {
+        indices.truncate(max);
+    }
+    let Some(&last) = indices.last() else {
+        return Ok(Vec::new());
+    };
+
+    // Indices are positions in the walk the commit list does; repeat it to
+    // find the commits.
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let mut walker = git::CommitWalker::new(&repo, query_options(reference, path))
+        .map_err(|e| e.message().to_string())?;
+    let mut records = Vec::with_capacity(indices.len());
+    let mut wanted = indices.iter().peekable();
+    for index in 0..=last as usize {
+        let Some(commit) = walker.next(None) else {
+            break;
+        };
+        let commit = commit.map_err(|e| e.message().to_string())?;
+        if wanted.next_if(|&&i| i as usize == index).is_some() {
+            records.push(commit_record(&repo, index, &commit.id)?);
+        }
+    }
+    Ok(records)
+}
+
+fn commit_record(repo: &Repository, index: usize, sha: &str) -> Result<CommitRecord, String> {
+    let oid = Oid::from_str(sha).map_err(|e| e.message().to_string())?;
+    let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+    let author = commit.author();
+    let when = author.when();
+    let date = FixedOffset::east_opt(when.offset_minutes() * 60)
+        .and_then(|offset| {
+            DateTime::from_timestamp(when.seconds(), 0).map(|d| d.with_timezone(&offset))
+        })
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default();
+    let message = String::from_utf8_lossy(commit.message_bytes()).into_owned();
+    Ok(CommitRecord {
+        index,
+        sha: sha.to_string(),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        author: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+        author_email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+        date,
+        summary: message.lines().next().unwrap_or("").to_string(),
+        message,
+    })
+}
+
+/// A JSON array of the records, or one `sha date author summary` line each.
+fn print_records(records: &[CommitRecord], json: bool, out: &mut impl Write) -> Result<(), String> {
+    let result = if json {
+        serde_json::to_writer(&mut *out, records)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writeln!(out))
+    } else {
+        records.iter().try_for_each(|r| {
+            writeln!(
+                out,
+                "{}  {}  {}  {}",
+                &r.sha[..7],
+                r.date.get(..10).unwrap_or(&r.date),
+                r.author,
+                r.summary
+            )
+        })
+    };
+    result.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn run_json(command: &str) -> Vec<serde_json::Value> {
+        let mut out = Vec::new();
+        execute(parse_args(&args(command)).unwrap(), &mut out).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn args_are_parsed_per_subcommand() {
+        assert!(is_subcommand(&args("log main")));
+        assert!(!is_subcommand(&args("/path/to/repo")));
+        assert_eq!(
+            parse_args(&args("search fix login --ref dev -n 3 --json")).unwrap(),
+            Command::Search {
+                common: CommonArgs {
+                    reference: Some("dev".to_string()),
+                    max_count: Some(3),
+                    json: true,
+                    ..CommonArgs::default()
+                },
+                query: "fix login".to_string(),
+            }
+        );
+        assert!(parse_args(&args("log --ref dev")).is_err());
+        assert!(parse_args(&args("log main dev")).is_err());
+        assert!(parse_args(&args("search --json")).is_err());
+        assert!(parse_args(&args("log --author")).is_err());
+        assert!(parse_args(&args("log -author:bob")).is_err());
+    }
+
+    #[test]
+    fn negated_search_terms_reach_the_query() {
+        let parsed = |command: &str| match parse_args(&args(command)).unwrap() {
+            Command::Search { query, .. } => query,
+            other => panic!("expected search, got {other:?}"),
+        };
+        assert_eq!(parsed("search -author:bob"), "-author:bob");
+        assert_eq!(
+            parsed("search parser -path:src/ui --json"),
+            "parser -path:src/ui"
+        );
+        assert_eq!(parsed("search -- -n"), "-n");
+
+        let mut tr = TestRepo::new();
+        tr.commit_by("main", "by alice", "Alice", "alice@example.com");
+        tr.commit_by("main", "by bob", "Bob", "bob@example.com");
+        let repo = tr.path().display().to_string();
+        let found = run_json(&format!("search -author:bob --ref main -C {repo} --json"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["summary"], "by alice");
+    }
+
+    #[test]
+    fn log_filters_by_author_and_path() {
+        let mut tr = TestRepo::new();
+        let first = tr.commit_file("main", "a.txt", "1", "first");
+        tr.commit_file("main", "b.txt", "1", "second");
+        tr.commit_file("main", "a.txt", "2", "third");
+        let repo = tr.path().display().to_string();
+
+        let log = run_json(&format!("log main -C {repo} --path a.txt --json"));
+        let summaries: Vec<&str> = log.iter().map(|c| c["summary"].as_str().unwrap()).collect();
+        assert_eq!(summaries, vec!["third", "first"]);
+        assert_eq!(log[0]["index"], 0);
+        assert_eq!(log[1]["sha"], first.to_string());
+        assert_eq!(log[1]["index"], 1);
+
+        assert!(run_json(&format!("log -C {repo} --author nobody --json")).is_empty());
+        assert_eq!(run_json(&format!("log -C {repo} -n 1 --json")).len(), 1);
+    }
+
+    #[test]
+    fn search_prefers_sha_prefix_matches_over_text() {
+        let mut tr = TestRepo::new();
+        let target = tr.commit_file("main", "a.txt", "1", "target");
+        let prefix = target.to_string()[..8].to_string();
+        tr.commit_file("main", "a.txt", "2", &format!("mentions {prefix}"));
+        tr.commit_file("main", "a.txt", "3", "mentions nothing");
+        let repo = tr.path().display().to_string();
+
+        let found = run_json(&format!("search {prefix} --ref main -C {repo} --json"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["sha"], target.to_string());
+        assert_eq!(found[0]["index"], 2);
+
+        let found = run_json(&format!("search mentions --ref main -C {repo} --json"));
+        let summaries: Vec<&str> = found
+            .iter()
+            .map(|c| c["summary"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            summaries,
+            vec!["mentions nothing", format!("mentions {prefix}").as_str()]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use chrono::prelude::*;
 use git2::{
     Commit, Delta, Diff, DiffDelta, DiffFindOptions, DiffFormat, DiffLineType, DiffOptions, Error,
-    ObjectType, Pathspec, Repository, Signature, Time,
+    ObjectType, Pathspec, Repository, Signature, Status, StatusOptions, Time,
 };
 use std::fmt;
 use std::path::Path;
@@ -36,6 +36,30 @@ pub struct FileChange {
     pub new_path: Option<String>,
     /// Content / hunk lines only (no `diff --git` / rename headers).
     pub patch: String,
+    /// Whether libgit2 treats either side as binary (no hunks in `patch`).
+    pub binary: bool,
+}
+
+impl FileChange {
+    /// `(insertions, deletions)`: the added and removed lines in `patch`.
+    pub fn line_counts(&self) -> (usize, usize) {
+        // Headers are stripped, so every line starting with `+` / `-` is content.
+        self.patch
+            .lines()
+            .fold((0, 0), |(ins, del), line| match line.as_bytes().first() {
+                Some(b'+') => (ins + 1, del),
+                Some(b'-') => (ins, del + 1),
+                _ => (ins, del),
+            })
+    }
+}
+
+/// Size of a commit's change against its first parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DiffStats {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
 }
 
 /// Structured diff for a single commit or a commit range.
@@ -44,6 +68,9 @@ pub struct CommitDiff {
     /// Optional notice shown above the file list (e.g. merge-commit note).
     pub preamble: Option<String>,
     pub files: Vec<FileChange>,
+    /// Headings that split `files` into groups, each shown before the file at
+    /// the given index (e.g. staged vs unstaged changes).
+    pub group_headings: Vec<(usize, String)>,
 }
 
 fn file_change_kind(status: Delta) -> FileChangeKind {
@@ -73,6 +100,7 @@ fn file_change_from_delta(delta: &DiffDelta<'_>) -> FileChange {
         old_path: diff_path(delta.old_file().path()),
         new_path: diff_path(delta.new_file().path()),
         patch: String::new(),
+        binary: delta.flags().is_binary(),
     }
 }
 
@@ -123,6 +151,7 @@ fn commit_diff_from_trees(
     Ok(CommitDiff {
         preamble,
         files: collect_file_changes(&mut diff)?,
+        group_headings: Vec::new(),
     })
 }
 
@@ -142,6 +171,8 @@ fn collect_file_changes(diff: &mut Diff<'_>) -> Result<Vec<FileChange>, Error> {
                 return true;
             }
         }
+        // Binary detection needs the content, which is only loaded while printing.
+        files[file_idx].binary |= delta.flags().is_binary();
         append_patch_line(&mut files[file_idx].patch, &line);
         true
     })?;
@@ -188,6 +219,8 @@ pub struct GitCommit {
     pub author: String,
     pub message: String,
     pub date: String,
+    /// Full SHAs of the parent commits, in parent order.
+    pub parents: Vec<String>,
 }
 
 impl fmt::Debug for GitCommit {
@@ -197,10 +230,32 @@ impl fmt::Debug for GitCommit {
             .field("message", &self.message)
             .field("author", &self.author)
             .field("date", &self.date)
+            .field("parents", &self.parents)
             .finish()
     }
 }
 
+/// Id of the pseudo commit that stands for uncommitted changes in the commit
+/// list. Never a valid SHA.
+pub const WORKING_TREE_ID: &str = "";
+
+impl GitCommit {
+    /// Pseudo commit for the uncommitted changes on top of `head`.
+    pub fn working_tree(status: &WorkingTreeStatus, head: &str) -> Self {
+        Self {
+            id: WORKING_TREE_ID.to_string(),
+            author: String::new(),
+            message: status.summary(),
+            date: String::new(),
+            parents: vec![head.to_string()],
+        }
+    }
+
+    pub fn is_working_tree(&self) -> bool {
+        self.id == WORKING_TREE_ID
+    }
+}
+
 /// Commit revwalk ordering.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CommitSort {
@@ -220,8 +275,13 @@ pub struct CommitQueryOptions {
     pub revspecs: Vec<String>,
     /// Optional pathspec filters (only include commits touching these paths).
     pub pathspecs: Vec<String>,
+    /// With a single file pathspec, keep following the file across renames
+    /// (like `git log --follow`).
+    pub follow_renames: bool,
     pub sort: CommitSort,
     pub reverse: bool,
+    /// Substring of the author's name or email, or of a `Co-authored-by`
+    /// trailer.
     pub author_contains: Option<String>,
     pub committer_contains: Option<String>,
     pub message_contains: Option<String>,
@@ -237,6 +297,7 @@ impl CommitQueryOptions {
         Self {
             revspecs: vec![branch_ref.to_string()],
             pathspecs: Vec::new(),
+            follow_renames: false,
             sort: CommitSort::None,
             reverse: false,
             author_contains: None,
@@ -305,17 +366,7 @@ impl<'repo> CommitWalker<'repo> {
             revwalk.push_head()?;
         }
 
-        let mut diffopts = DiffOptions::new();
-        if !opts.pathspecs.is_empty() {
-            for spec in &opts.pathspecs {
-                diffopts.pathspec(spec);
-            }
-        }
-        let pathspec = if opts.pathspecs.is_empty() {
-            None
-        } else {
-            Some(Pathspec::new(opts.pathspecs.iter())?)
-        };
+        let (pathspec, diffopts) = Self::build_path_filter(&opts.pathspecs)?;
 
         Ok(Self {
             repo,
@@ -326,6 +377,61 @@ impl<'repo> CommitWalker<'repo> {
         })
     }
 
+    fn build_path_filter(pathspecs: &[String]) -> Result<(Option<Pathspec>, DiffOptions), Error> {
+        let mut diffopts = DiffOptions::new();
+        for spec in pathspecs {
+            diffopts.pathspec(spec);
+        }
+        let pathspec = if pathspecs.is_empty() {
+            None
+        } else {
+            Some(Pathspec::new(pathspecs.iter())?)
+        };
+        Ok((pathspec, diffopts))
+    }
+
+    /// With `follow_renames`, detect whether `commit` created the followed
+    /// file by renaming another one; if so, older commits are matched against
+    /// the previous name.
+    fn follow_rename(&mut self, commit: &Commit) -> Result<(), Error> {
+        if !self.opts.follow_renames || self.opts.pathspecs.len() != 1 || commit.parent_count() != 1
+        {
+            return Ok(());
+        }
+        let current = Path::new(&self.opts.pathspecs[0]);
+        let parent_tree = commit.parent(0)?.tree()?;
+        let tree = commit.tree()?;
+
+        // Cheap check first: only a commit that adds the path can be a rename onto it.
+        if parent_tree.get_path(current).is_ok() || tree.get_path(current).is_err() {
+            return Ok(());
+        }
+
+        let mut diff = self
+            .repo
+            .diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)?;
+        let mut find_opts = DiffFindOptions::new();
+        find_opts.renames(true).rename_threshold(50);
+        diff.find_similar(Some(&mut find_opts))?;
+
+        let old_path = diff
+            .deltas()
+            .find(|d| d.status() == Delta::Renamed && d.new_file().path() == Some(current))
+            .and_then(|d| {
+                d.old_file()
+                    .path()
+                    .and_then(|p| p.to_str())
+                    .map(str::to_string)
+            });
+        if let Some(old_path) = old_path {
+            let (pathspec, diffopts) = Self::build_path_filter(std::slice::from_ref(&old_path))?;
+            self.opts.pathspecs = vec![old_path];
+            self.pathspec = pathspec;
+            self.diffopts = diffopts;
+        }
+        Ok(())
+    }
+
     fn commit_passes_filters(&mut self, commit: &Commit) -> Result<bool, Error> {
         let parents = commit.parents().len();
         if parents < self.opts.min_parents {
@@ -356,9 +462,13 @@ impl<'repo> CommitWalker<'repo> {
                     }
                 }
             }
+            self.follow_rename(commit)?;
         }
 
-        if !sig_matches(&commit.author(), self.opts.author_contains.as_deref()) {
+        let author = self.opts.author_contains.as_deref();
+        if !sig_matches(&commit.author(), author)
+            && !author.is_some_and(|s| co_author_matches(commit.message(), s))
+        {
             return Ok(false);
         }
         if !sig_matches(&commit.committer(), self.opts.committer_contains.as_deref()) {
@@ -377,6 +487,7 @@ impl<'repo> CommitWalker<'repo> {
             author: commit.author().name().unwrap_or("").to_string(),
             date: format_datetime(&commit.author().when()),
             id: commit.id().to_string(),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
         }
     }
 
@@ -425,6 +536,16 @@ impl<'repo> CommitWalker<'repo> {
         }
         Ok((out, false))
     }
+
+    /// Return at most `limit` commits plus whether more exist. Looks one
+    /// commit ahead, so exactly `limit` commits don't count as cut off (as
+    /// they would with `next_page`'s `done` flag).
+    pub fn first_commits(&mut self, limit: usize) -> Result<(Vec<GitCommit>, bool), Error> {
+        let (mut commits, _) = self.next_page(limit + 1, None)?;
+        let truncated = commits.len() > limit;
+        commits.truncate(limit);
+        Ok((commits, truncated))
+    }
 }
 
 fn sig_matches(sig: &Signature, contains: Option<&str>) -> bool {
@@ -433,6 +554,13 @@ fn sig_matches(sig: &Signature, contains: Option<&str>) -> bool {
         || sig.email().map(|n| n.contains(s)).unwrap_or(false)
 }
 
+/// Whether a `Co-authored-by` trailer of `message` contains `contains`.
+fn co_author_matches(message: Option<&str>, contains: &str) -> bool {
+    parse_trailers(message.unwrap_or(""))
+        .iter()
+        .any(|t| t.is(CO_AUTHORED_BY) && t.value.contains(contains))
+}
+
 fn log_message_matches(msg: Option<&str>, contains: Option<&str>) -> bool {
     match (contains, msg) {
         (None, _) => true,
@@ -458,6 +586,12 @@ fn format_datetime(time: &Time) -> String {
     dt.format("%b %d, %Y %H:%M").to_string()
 }
 
+/// Date-only variant of `format_datetime`, for compact per-line annotations.
+fn format_date(time: &Time) -> String {
+    let dt = Local.timestamp_opt(time.seconds(), 0).unwrap();
+    dt.format("%Y-%m-%d").to_string()
+}
+
 fn git_time_to_utc(time: Time) -> DateTime<Utc> {
     Utc.timestamp_opt(time.seconds(), 0).unwrap()
 }
@@ -590,12 +724,271 @@ pub fn get_tag_list(path: &Path) -> Result<Vec<TagInfo>, Error> {
     Ok(tag_infos)
 }
 
+/// One reflog entry: a ref moving from `old_sha` to `new_sha`.
+#[derive(Debug, Clone)]
+pub struct ReflogEntry {
+    pub old_sha: String,
+    pub new_sha: String,
+    pub message: String,
+    pub committer: String,
+    pub date: String,
+    /// Summary of the `new_sha` commit, or `None` if it is no longer in the
+    /// object database (e.g. garbage collected).
+    pub summary: Option<String>,
+}
+
+/// Reflog of `name`, newest first. `name` is `HEAD`, a full ref name or a
+/// local branch name.
+pub fn get_reflog(path: &Path, name: &str) -> Result<Vec<ReflogEntry>, Error> {
+    let repo = Repository::open(path)?;
+    let ref_name = if name == "HEAD" || name.starts_with("refs/") {
+        name.to_string()
+    } else {
+        format!("refs/heads/{name}")
+    };
+    let reflog = repo.reflog(&ref_name)?;
+    Ok(reflog
+        .iter()
+        .map(|entry| {
+            let committer = entry.committer();
+            ReflogEntry {
+                old_sha: entry.id_old().to_string(),
+                new_sha: entry.id_new().to_string(),
+                message: entry.message().unwrap_or("").to_string(),
+                committer: committer.name().unwrap_or("").to_string(),
+                date: format_datetime(&committer.when()),
+                summary: repo
+                    .find_commit(entry.id_new())
+                    .ok()
+                    .map(|c| c.summary().unwrap_or("").to_string()),
+            }
+        })
+        .collect())
+}
+
+/// One commit formatted like `git format-patch` output.
+#[derive(Debug, Clone)]
+pub struct FormattedPatch {
+    /// File name `git format-patch` would use, e.g. `0001-Fix-typo.patch`.
+    pub file_name: String,
+    /// Mail with `From`/`Date`/`Subject` headers, the message, the diffstat
+    /// and the patch. Concatenated patches form an mbox.
+    pub text: String,
+}
+
+/// Longest subject part of a patch file name, like `git format-patch`.
+const PATCH_NAME_MAX: usize = 52;
+
+/// `NNNN-Subject-words.patch`: the subject reduced to letters, digits, `.`
+/// and `_`, with other runs of characters replaced by a single `-`.
+fn patch_file_name(number: usize, summary: &str) -> String {
+    let mut name = String::new();
+    for c in summary.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let mut name: String = name.chars().take(PATCH_NAME_MAX).collect();
+    while name.ends_with(['-', '.']) {
+        name.pop();
+    }
+    format!("{number:04}-{name}.patch")
+}
+
+/// Format `shas` (oldest first) as a numbered patch series, each against its
+/// parent. Merge commits are skipped, as `git format-patch` does.
+pub fn format_patches(path: &Path, shas: &[String]) -> Result<Vec<FormattedPatch>, Error> {
+    let repo = Repository::open(path)?;
+    let mut commits = Vec::with_capacity(shas.len());
+    for sha in shas {
+        let commit = repo.find_commit(git2::Oid::from_str(sha)?)?;
+        if commit.parent_count() <= 1 {
+            commits.push(commit);
+        }
+    }
+
+    let total = commits.len();
+    let mut patches = Vec::with_capacity(total);
+    for (idx, commit) in commits.iter().enumerate() {
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let mut find_opts = DiffFindOptions::new();
+        find_opts.renames(true).rename_threshold(50);
+        diff.find_similar(Some(&mut find_opts))?;
+
+        let summary = commit.summary().unwrap_or("");
+        let body = commit.body().unwrap_or("");
+        let email = git2::Email::from_diff(
+            &diff,
+            idx + 1,
+            total,
+            &commit.id(),
+            summary,
+            body,
+            &commit.author(),
+            &mut git2::EmailCreateOptions::new(),
+        )?;
+        patches.push(FormattedPatch {
+            file_name: patch_file_name(idx + 1, summary),
+            text: String::from_utf8_lossy(email.as_slice()).into_owned(),
+        });
+    }
+    Ok(patches)
+}
+
+/// One entry of the stash (`refs/stash` and its reflog).
+#[derive(Debug, Clone)]
+pub struct StashInfo {
+    /// Position in the stash list, as in `stash@{index}`.
+    pub index: usize,
+    pub message: String,
+    /// SHA of the stash commit.
+    pub sha: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Returns the stash entries of the repository, newest first.
+pub fn get_stashes(path: &Path) -> Result<Vec<StashInfo>, Error> {
+    let mut repo = Repository::open(path)?;
+    let mut entries = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        entries.push((index, message.to_string(), *oid));
+        true
+    })?;
+
+    let mut stashes = Vec::new();
+    for (index, message, oid) in entries {
+        let commit = repo.find_commit(oid)?;
+        stashes.push(StashInfo {
+            index,
+            message,
+            sha: oid.to_string(),
+            time: git_time_to_utc(commit.time()),
+        });
+    }
+    Ok(stashes)
+}
+
+/// Changes saved in a stash commit, grouped like the working tree diff:
+/// staged changes (base to the stashed index), unstaged changes (stashed
+/// index to stashed working tree) and, when the stash has a third parent,
+/// the untracked files it saved.
+pub fn get_stash_diff(path: &str, stash_sha: &str) -> Result<CommitDiff, Error> {
+    let repo = Repository::open(path)?;
+    let stash = repo.find_commit(git2::Oid::from_str(stash_sha)?)?;
+    let base_tree = stash.parent(0)?.tree()?;
+    let index_tree = stash.parent(1)?.tree()?;
+    let untracked_tree = if stash.parent_count() > 2 {
+        Some(stash.parent(2)?.tree()?)
+    } else {
+        None
+    };
+
+    let staged = commit_diff_from_trees(&repo, Some(&base_tree), &index_tree, None)?.files;
+    let unstaged = commit_diff_from_trees(&repo, Some(&index_tree), &stash.tree()?, None)?.files;
+    let untracked = match untracked_tree {
+        Some(tree) => commit_diff_from_trees(&repo, None, &tree, None)?.files,
+        None => Vec::new(),
+    };
+
+    let mut files = Vec::new();
+    let mut group_headings = Vec::new();
+    for (heading, group) in [
+        ("Staged changes", staged),
+        ("Unstaged changes", unstaged),
+        ("Untracked files", untracked),
+    ] {
+        if !group.is_empty() {
+            group_headings.push((files.len(), heading.to_string()));
+            files.extend(group);
+        }
+    }
+
+    Ok(CommitDiff {
+        preamble: None,
+        files,
+        group_headings,
+    })
+}
+
 pub struct CommitMetadata {
     pub author_name: String,
     pub author_email: String,
     pub date_time: String,
     pub commit_message: String,
     pub git_sha: String,
+    /// Trailers at the end of `commit_message`, in order.
+    pub trailers: Vec<Trailer>,
+}
+
+pub const CO_AUTHORED_BY: &str = "Co-authored-by";
+pub const REVIEWED_BY: &str = "Reviewed-by";
+
+/// A `Key: value` trailer at the end of a commit message, such as
+/// `Signed-off-by: Jane Doe <jane@example.com>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trailer {
+    pub key: String,
+    /// Value with continuation lines joined by single spaces.
+    pub value: String,
+}
+
+impl Trailer {
+    /// Whether the key is `key`, ignoring case as git does.
+    pub fn is(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+
+    /// Name and email of a `Name <email>` value. Without `<...>` the whole
+    /// value is the name.
+    pub fn identity(&self) -> (&str, Option<&str>) {
+        match self.value.rsplit_once('<') {
+            Some((name, rest)) if rest.ends_with('>') => {
+                (name.trim(), Some(&rest[..rest.len() - 1]))
+            }
+            _ => (self.value.as_str(), None),
+        }
+    }
+}
+
+/// Trailers of `message`: its last paragraph, if that is not the subject
+/// and every line is a `Key: value` trailer or an indented continuation.
+/// `(cherry picked from commit ...)` lines are allowed in between, as in
+/// `git interpret-trailers`.
+pub fn parse_trailers(message: &str) -> Vec<Trailer> {
+    let Some((_, block)) = message.trim_end().rsplit_once("\n\n") else {
+        return Vec::new();
+    };
+    let mut trailers: Vec<Trailer> = Vec::new();
+    for line in block.lines() {
+        if line.starts_with([' ', '\t']) {
+            let Some(last) = trailers.last_mut() else {
+                return Vec::new();
+            };
+            last.value.push(' ');
+            last.value.push_str(line.trim());
+            continue;
+        }
+        if line.starts_with("(cherry picked from commit ") {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Vec::new();
+        };
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Vec::new();
+        }
+        trailers.push(Trailer {
+            key: key.to_string(),
+            value: value.trim().to_string(),
+        });
+    }
+    trailers
 }
 
 pub fn get_commit_metadata(path: &str, commit_sha: &str) -> Result<CommitMetadata, Error> {
@@ -609,6 +1002,7 @@ pub fn get_commit_metadata(path: &str, commit_sha: &str) -> Result<CommitMetadat
     let date_time = format_datetime(&author.when());
     let commit_message = String::from_utf8_lossy(commit.message_bytes()).to_string();
     let git_sha = commit_sha.to_string();
+    let trailers = parse_trailers(&commit_message);
 
     Ok(CommitMetadata {
         author_name,
@@ -616,147 +1010,1436 @@ pub fn get_commit_metadata(path: &str, commit_sha: &str) -> Result<CommitMetadat
         date_time,
         commit_message,
         git_sha,
+        trailers,
     })
 }
 
-pub fn get_commit_diff(path: &str, commit_sha: &str) -> Result<CommitDiff, Error> {
-    let repo = Repository::open(path)?;
-    let commit_oid = git2::Oid::from_str(commit_sha)?;
-    let commit = repo.find_commit(commit_oid)?;
+/// Kind of signature on a commit or tag, from its armor header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureKind {
+    Gpg,
+    Ssh,
+    X509,
+    Other,
+}
 
-    let preamble = if commit.parents().len() > 1 {
-        Some("Merge commit - showing diff against first parent".to_string())
-    } else {
-        None
-    };
+/// Outcome of checking a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Good signature from a key in the keyring or allowed signers file.
+    Verified,
+    /// Bad signature, or one made by an expired or revoked key.
+    Unverified,
+    /// Could not be checked, e.g. the key is unknown or no verifier is set
+    /// up, or a good signature from a key not certified as trusted (git's
+    /// `U` status).
+    Unknown,
+}
 
-    let parent_tree = if commit.parents().len() >= 1 {
-        let parent = commit.parent(0)?;
-        Some(parent.tree()?)
-    } else {
-        None
-    };
+/// A checked signature of a commit or of an annotated tag pointing at it.
+#[derive(Debug, Clone)]
+pub struct SignatureCheck {
+    /// What was signed: "Commit" or "Tag <name>".
+    pub object: String,
+    pub kind: SignatureKind,
+    pub status: SignatureStatus,
+    /// Key owner or SSH principal, when the verifier names one.
+    pub signer: Option<String>,
+    /// One-line explanation from the verifier, or why it could not run.
+    pub detail: String,
+}
 
-    let commit_tree = commit.tree()?;
-    commit_diff_from_trees(&repo, parent_tree.as_ref(), &commit_tree, preamble)
+/// Where signatures are checked against. Unset fields fall back to the
+/// global git config (`gpg.ssh.allowedSignersFile`) or the verifier's
+/// defaults. The programs come from `gpg.program` and `gpg.ssh.program` in
+/// the global or system config; the repository's config is never read.
+#[derive(Debug, Clone, Default)]
+pub struct SignatureVerifier {
+    /// GnuPG home directory holding the keyring (`GNUPGHOME`).
+    pub gnupg_home: Option<PathBuf>,
+    /// SSH allowed signers file.
+    pub allowed_signers: Option<PathBuf>,
 }
 
-pub fn get_range_diff(path: &str, oldest_sha: &str, newest_sha: &str) -> Result<CommitDiff, Error> {
-    let repo = Repository::open(path)?;
+/// Armor headers that start a signature, as recognized by git.
+const SIGNATURE_HEADERS: &[(&str, SignatureKind)] = &[
+    ("-----BEGIN PGP SIGNATURE-----", SignatureKind::Gpg),
+    ("-----BEGIN PGP MESSAGE-----", SignatureKind::Gpg),
+    ("-----BEGIN SSH SIGNATURE-----", SignatureKind::Ssh),
+    ("-----BEGIN SIGNED MESSAGE-----", SignatureKind::X509),
+];
+
+fn signature_kind(signature: &[u8]) -> SignatureKind {
+    SIGNATURE_HEADERS
+        .iter()
+        .find(|(header, _)| signature.starts_with(header.as_bytes()))
+        .map_or(SignatureKind::Other, |(_, kind)| *kind)
+}
 
-    let oldest_oid = git2::Oid::from_str(oldest_sha)?;
-    let oldest_commit = repo.find_commit(oldest_oid)?;
+/// Split a raw tag object into the signed payload and its signature, which
+/// starts at the last line opening with a signature header.
+fn split_signed_tag(raw: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut start = None;
+    let mut pos = 0;
+    for line in raw.split_inclusive(|&b| b == b'\n') {
+        if SIGNATURE_HEADERS
+            .iter()
+            .any(|(header, _)| line.starts_with(header.as_bytes()))
+        {
+            start = Some(pos);
+        }
+        pos += line.len();
+    }
+    start.map(|idx| raw.split_at(idx))
+}
 
-    let newest_oid = git2::Oid::from_str(newest_sha)?;
-    let newest_commit = repo.find_commit(newest_oid)?;
+/// Signatures of the commit and of its annotated tags among `tag_names`
+/// (the commit's entry in the `get_tags` map, built once per refresh),
+/// checked with `verifier`. Unsigned objects are left out.
+pub fn get_signatures(
+    path: &str,
+    commit_sha: &str,
+    tag_names: &[String],
+    verifier: &SignatureVerifier,
+) -> Result<Vec<SignatureCheck>, Error> {
+    let repo = Repository::open(path)?;
+    let commit_oid = git2::Oid::from_str(commit_sha)?;
+    // Only global and system config: a repository's own `.git/config` must
+    // not choose the programs that are run or the signers that are trusted.
+    let config = git2::Config::open_default()?.snapshot()?;
+
+    let mut checks = Vec::new();
+    match repo.extract_signature(&commit_oid, None) {
+        Ok((signature, data)) => checks.push(check_signature(
+            "Commit".to_string(),
+            &signature,
+            &data,
+            verifier,
+            &config,
+        )),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+        Err(e) => return Err(e),
+    }
 
-    let base_tree = if oldest_commit.parents().len() >= 1 {
-        let parent = oldest_commit.parent(0)?;
-        Some(parent.tree()?)
-    } else {
-        None
-    };
+    let odb = repo.odb()?;
+    for name in tag_names {
+        let Some(tag_oid) = repo
+            .find_reference(&format!("refs/tags/{name}"))
+            .ok()
+            .and_then(|r| r.target())
+        else {
+            continue;
+        };
+        // Lightweight tags are not tag objects and cannot be signed.
+        let Ok(tag) = repo.find_tag(tag_oid) else {
+            continue;
+        };
+        if tag.target_id() != commit_oid {
+            continue;
+        }
+        let raw = odb.read(tag_oid)?;
+        if let Some((data, signature)) = split_signed_tag(raw.data()) {
+            checks.push(check_signature(
+                format!("Tag {name}"),
+                signature,
+                data,
+                verifier,
+                &config,
+            ));
+        }
+    }
+    Ok(checks)
+}
 
-    let newest_tree = newest_commit.tree()?;
-    commit_diff_from_trees(&repo, base_tree.as_ref(), &newest_tree, None)
+fn check_signature(
+    object: String,
+    signature: &[u8],
+    data: &[u8],
+    verifier: &SignatureVerifier,
+    config: &git2::Config,
+) -> SignatureCheck {
+    let kind = signature_kind(signature);
+    let (status, signer, detail) = match kind {
+        SignatureKind::Gpg => verify_gpg(signature, data, verifier, config),
+        SignatureKind::Ssh => verify_ssh(signature, data, verifier, config),
+        SignatureKind::X509 => (
+            SignatureStatus::Unknown,
+            None,
+            "X.509 signatures are not checked".to_string(),
+        ),
+        SignatureKind::Other => (
+            SignatureStatus::Unknown,
+            None,
+            "Unrecognized signature format".to_string(),
+        ),
+    };
+    SignatureCheck {
+        object,
+        kind,
+        status,
+        signer,
+        detail,
+    }
 }
 
-pub fn validate_repository(path: &Path) -> Result<(), git2::Error> {
-    Repository::open(path).map(|_| ())
+type VerifyResult = (SignatureStatus, Option<String>, String);
+
+/// A signature written to a private temporary file for an external
+/// verifier; removed on drop. The file is created exclusively with mode
+/// 0600, so a planted symlink or another user can't redirect the write.
+fn signature_file(signature: &[u8]) -> std::io::Result<tempfile::NamedTempFile> {
+    use std::io::Write;
+
+    let mut file = tempfile::Builder::new()
+        .prefix("gity-signature-")
+        .tempfile()?;
+    file.write_all(signature)?;
+    file.flush()?;
+    Ok(file)
 }
 
-/// If `path` is inside a git worktree (or is a bare repo), return the best path
-/// to open in the UI:
-/// - worktree root for normal repositories
-/// - repo directory for bare repositories
-pub fn discover_repository_root(path: &Path) -> Option<PathBuf> {
-    let repo = Repository::discover(path).ok()?;
-    if let Some(workdir) = repo.workdir() {
-        Some(workdir.to_path_buf())
-    } else {
-        // Bare repository
-        Some(repo.path().to_path_buf())
+/// Run `command` with `input` on stdin and collect its output.
+fn run_verifier(
+    command: &mut std::process::Command,
+    input: &[u8],
+) -> std::io::Result<std::process::Output> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A verifier that rejects the signature early may close stdin first.
+        let _ = stdin.write_all(input);
     }
+    child.wait_with_output()
 }
 
-pub fn branch_exists(path: &Path, branch_name: &str) -> bool {
-    let Ok(repo) = Repository::open(path) else {
-        return false;
-    };
-    repo.revparse_single(branch_name).is_ok()
+fn first_line(output: &[u8]) -> String {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("")
+        .to_string()
 }
 
-/// Returns the currently checked-out branch name for the repository at `path`.
-/// If HEAD is detached (or can't be read), returns `None`.
-pub fn checked_out_branch_name(path: &Path) -> Option<String> {
-    let repo = Repository::open(path).ok()?;
-    let head = repo.head().ok()?;
-    if !head.is_branch() {
-        return None;
+fn verify_gpg(
+    signature: &[u8],
+    data: &[u8],
+    verifier: &SignatureVerifier,
+    config: &git2::Config,
+) -> VerifyResult {
+    let program = config
+        .get_string("gpg.program")
+        .unwrap_or_else(|_| "gpg".to_string());
+    let file = match signature_file(signature) {
+        Ok(file) => file,
+        Err(e) => {
+            return (
+                SignatureStatus::Unknown,
+                None,
+                format!("Cannot write signature file: {e}"),
+            );
+        }
+    };
+    let mut command = std::process::Command::new(&program);
+    command
+        .args(["--status-fd=1", "--keyid-format=long", "--verify"])
+        .arg(file.path())
+        .arg("-");
+    if let Some(home) = &verifier.gnupg_home {
+        command.env("GNUPGHOME", home);
+    }
+    match run_verifier(&mut command, data) {
+        Ok(output) => parse_gpg_status(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => (
+            SignatureStatus::Unknown,
+            None,
+            format!("Cannot run {program}: {e}"),
+        ),
     }
-    head.shorthand().map(|s| s.to_string())
 }
 
-/// Returns the name and HEAD commit SHA of the repository's primary branch
-/// (`main` or `master`), but only when `current_ref` is a *different* branch.
-/// Used to show a "main" chip on the commit where the primary branch sits.
-pub fn get_main_branch_head(path: &Path, current_ref: &str) -> Option<(String, String)> {
-    let repo = Repository::open(path).ok()?;
-
-    for branch_name in &["main", "master"] {
-        if *branch_name == current_ref {
-            return None;
-        }
-        let ref_name = format!("refs/heads/{}", branch_name);
-        if let Ok(reference) = repo.find_reference(&ref_name) {
-            if let Ok(commit) = reference.peel_to_commit() {
-                return Some((branch_name.to_string(), commit.id().to_string()));
+/// Interpret gpg's `--status-fd` output.
+fn parse_gpg_status(status: &str) -> VerifyResult {
+    let mut good_signer = None;
+    let mut untrusted = false;
+    let mut missing_key = None;
+    for line in status.lines() {
+        let Some(rest) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let (keyword, args) = rest.split_once(' ').unwrap_or((rest, ""));
+        // Most signature lines are "<keyword> <key id> <user id>".
+        let (key_id, user_id) = args.split_once(' ').unwrap_or((args, ""));
+        let signer = (!user_id.is_empty()).then(|| user_id.to_string());
+        match keyword {
+            "GOODSIG" => good_signer = Some(signer),
+            "BADSIG" => {
+                return (
+                    SignatureStatus::Unverified,
+                    signer,
+                    "Bad signature".to_string(),
+                );
+            }
+            "EXPKEYSIG" => {
+                return (
+                    SignatureStatus::Unverified,
+                    signer,
+                    "Signed with an expired key".to_string(),
+                );
             }
+            "REVKEYSIG" => {
+                return (
+                    SignatureStatus::Unverified,
+                    signer,
+                    "Signed with a revoked key".to_string(),
+                );
+            }
+            "EXPSIG" => {
+                return (
+                    SignatureStatus::Unverified,
+                    signer,
+                    "The signature has expired".to_string(),
+                );
+            }
+            "ERRSIG" | "NO_PUBKEY" => missing_key = Some(key_id.to_string()),
+            "TRUST_UNDEFINED" | "TRUST_NEVER" => untrusted = true,
+            _ => {}
         }
     }
-    None
+    match (good_signer, missing_key) {
+        (Some(signer), _) if untrusted => (
+            SignatureStatus::Unknown,
+            signer,
+            "Good signature from a key that is not certified as trusted".to_string(),
+        ),
+        (Some(signer), _) => (
+            SignatureStatus::Verified,
+            signer,
+            "Good signature".to_string(),
+        ),
+        (None, Some(key_id)) => (
+            SignatureStatus::Unknown,
+            None,
+            format!("No public key {key_id} in the keyring"),
+        ),
+        (None, None) => (
+            SignatureStatus::Unknown,
+            None,
+            "gpg reported no signature status".to_string(),
+        ),
+    }
 }
 
-/// If the given branch has an upstream remote (e.g. `origin/main`), returns
-/// `Some((upstream_ref_name, commit_sha))` so the UI can show a chip on that commit.
-/// Returns `None` if the ref is not a local branch or has no upstream.
-pub fn get_branch_upstream(path: &Path, branch_ref: &str) -> Option<(String, String)> {
-    let repo = Repository::open(path).ok()?;
+fn verify_ssh(
+    signature: &[u8],
+    data: &[u8],
+    verifier: &SignatureVerifier,
+    config: &git2::Config,
+) -> VerifyResult {
+    let Some(allowed_signers) = verifier
+        .allowed_signers
+        .clone()
+        .or_else(|| config.get_path("gpg.ssh.allowedSignersFile").ok())
+    else {
+        return (
+            SignatureStatus::Unknown,
+            None,
+            "No allowed signers file is configured (gpg.ssh.allowedSignersFile)".to_string(),
+        );
+    };
+    let program = config
+        .get_string("gpg.ssh.program")
+        .unwrap_or_else(|_| "ssh-keygen".to_string());
+    let file = match signature_file(signature) {
+        Ok(file) => file,
+        Err(e) => {
+            return (
+                SignatureStatus::Unknown,
+                None,
+                format!("Cannot write signature file: {e}"),
+            );
+        }
+    };
 
-    // If viewing HEAD, resolve to branch name so we can look up upstream
-    let branch_name = if branch_ref == "HEAD" {
-        let head = repo.head().ok()?;
-        if !head.is_branch() {
-            return None;
+    let mut find = std::process::Command::new(&program);
+    find.args(["-Y", "find-principals", "-f"])
+        .arg(&allowed_signers)
+        .arg("-s")
+        .arg(file.path());
+    let principal = match run_verifier(&mut find, &[]) {
+        Ok(output) if output.status.success() => first_line(&output.stdout),
+        Ok(_) => {
+            // Not an allowed signer; still tell a good signature from a bad one.
+            let mut check = std::process::Command::new(&program);
+            check
+                .args(["-Y", "check-novalidate", "-n", "git", "-s"])
+                .arg(file.path());
+            return match run_verifier(&mut check, data) {
+                Ok(output) if output.status.success() => (
+                    SignatureStatus::Unknown,
+                    None,
+                    "Good signature, but the key is not in the allowed signers file".to_string(),
+                ),
+                Ok(output) => (
+                    SignatureStatus::Unverified,
+                    None,
+                    first_line(&output.stderr),
+                ),
+                Err(e) => (
+                    SignatureStatus::Unknown,
+                    None,
+                    format!("Cannot run {program}: {e}"),
+                ),
+            };
+        }
+        Err(e) => {
+            return (
+                SignatureStatus::Unknown,
+                None,
+                format!("Cannot run {program}: {e}"),
+            );
         }
-        head.shorthand().map(|s| s.to_string())?
-    } else {
-        branch_ref.to_string()
     };
 
-    let ref_name = format!("refs/heads/{}", branch_name);
-    let reference = repo.find_reference(&ref_name).ok()?;
-    let branch = git2::Branch::wrap(reference);
-    let upstream = branch.upstream().ok()?;
-    let upstream_name = upstream.name().ok().flatten()?.to_string();
-    let commit = upstream.get().peel_to_commit().ok()?;
-    let sha = commit.id().to_string();
+    let mut verify = std::process::Command::new(&program);
+    verify
+        .args(["-Y", "verify", "-f"])
+        .arg(&allowed_signers)
+        .args(["-I", &principal, "-n", "git", "-s"])
+        .arg(file.path());
+    match run_verifier(&mut verify, data) {
+        Ok(output) if output.status.success() => (
+            SignatureStatus::Verified,
+            Some(principal),
+            first_line(&output.stdout),
+        ),
+        Ok(output) => (
+            SignatureStatus::Unverified,
+            Some(principal),
+            first_line(&output.stderr),
+        ),
+        Err(e) => (
+            SignatureStatus::Unknown,
+            None,
+            format!("Cannot run {program}: {e}"),
+        ),
+    }
+}
 
-    Some((upstream_name, sha))
+/// How a merge commit is diffed. Other commits always diff against their
+/// only parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeDiffMode {
+    /// Against the parent at this index (0 is the first parent).
+    Parent(usize),
+    /// Only hunks that differ from every parent, like `git show --cc`.
+    Combined,
+    /// Against the tree an automatic merge of the two parents produces, so
+    /// conflict resolutions and evil-merge edits show up as changes.
+    Remerge,
 }
 
-/// Returns a mapping from commit SHA to tag names for all tags in the repository.
-///
-/// The returned HashMap maps full commit SHA strings to vectors of tag names
-/// that point to that commit (either directly or through tag objects).
-pub fn get_tags(path: &Path) -> Result<std::collections::HashMap<String, Vec<String>>, Error> {
-    use std::collections::HashMap;
+impl Default for MergeDiffMode {
+    fn default() -> Self {
+        MergeDiffMode::Parent(0)
+    }
+}
 
-    let repo = Repository::open(path)?;
-    let mut tag_map: HashMap<String, Vec<String>> = HashMap::new();
+impl MergeDiffMode {
+    /// Modes offered for a merge with `parent_count` parents. Remerging is
+    /// only supported for two parents.
+    pub fn available(parent_count: usize) -> Vec<MergeDiffMode> {
+        let mut modes: Vec<MergeDiffMode> = (0..parent_count).map(MergeDiffMode::Parent).collect();
+        modes.push(MergeDiffMode::Combined);
+        if parent_count == 2 {
+            modes.push(MergeDiffMode::Remerge);
+        }
+        modes
+    }
 
-    repo.tag_foreach(|oid, name_bytes| {
-        // Tag names come as "refs/tags/tagname" - extract just the tag name
+    pub fn label(&self) -> String {
+        match self {
+            MergeDiffMode::Parent(0) => "First Parent".to_string(),
+            MergeDiffMode::Parent(idx) => format!("Parent {}", idx + 1),
+            MergeDiffMode::Combined => "Combined".to_string(),
+            MergeDiffMode::Remerge => "Remerge".to_string(),
+        }
+    }
+}
+
+/// Number of parents of `commit_sha`.
+pub fn get_parent_count(path: &Path, commit_sha: &str) -> Result<usize, Error> {
+    let repo = Repository::open(path)?;
+    let commit = repo.find_commit(git2::Oid::from_str(commit_sha)?)?;
+    Ok(commit.parent_count())
+}
+
+/// Diff stats of `commit_sha` against its first parent (the empty tree for a
+/// root commit), with renames detected like in `get_commit_diff`. Cheaper
+/// than the full diff, as no patch text is built.
+pub fn get_commit_stats(path: &Path, commit_sha: &str) -> Result<DiffStats, Error> {
+    let repo = Repository::open(path)?;
+    let commit = repo.find_commit(git2::Oid::from_str(commit_sha)?)?;
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let mut find_opts = DiffFindOptions::new();
+    find_opts.renames(true).rename_threshold(50);
+    diff.find_similar(Some(&mut find_opts))?;
+
+    let stats = diff.stats()?;
+    Ok(DiffStats {
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+/// Diff of `commit_sha` against its parent. Merge commits are diffed as
+/// chosen by `merge_mode`; a mode the merge cannot use falls back to the
+/// first parent.
+pub fn get_commit_diff(
+    path: &str,
+    commit_sha: &str,
+    merge_mode: MergeDiffMode,
+) -> Result<CommitDiff, Error> {
+    let repo = Repository::open(path)?;
+    let commit_oid = git2::Oid::from_str(commit_sha)?;
+    let commit = repo.find_commit(commit_oid)?;
+    let commit_tree = commit.tree()?;
+    let parent_count = commit.parent_count();
+
+    if parent_count > 1 {
+        let merge_mode = if MergeDiffMode::available(parent_count).contains(&merge_mode) {
+            merge_mode
+        } else {
+            MergeDiffMode::default()
+        };
+        match merge_mode {
+            MergeDiffMode::Combined => {
+                return Ok(CommitDiff {
+                    preamble: Some(
+                        "Merge commit - combined diff: only changes that differ from every parent, shown against the first parent"
+                            .to_string(),
+                    ),
+                    files: combined_file_changes(&repo, &commit)?,
+                    group_headings: Vec::new(),
+                });
+            }
+            MergeDiffMode::Remerge => {
+                let remerged = remerge_tree(&repo, &commit)?;
+                let preamble = "Merge commit - showing diff against an automatic re-merge of its parents; conflict resolutions appear as edits to the conflict markers";
+                return commit_diff_from_trees(
+                    &repo,
+                    Some(&remerged),
+                    &commit_tree,
+                    Some(preamble.to_string()),
+                );
+            }
+            MergeDiffMode::Parent(idx) => {
+                let parent = commit.parent(idx)?;
+                let preamble = if idx == 0 {
+                    "Merge commit - showing diff against first parent".to_string()
+                } else {
+                    let parent_sha = parent.id().to_string();
+                    format!(
+                        "Merge commit - showing diff against parent {} ({})",
+                        idx + 1,
+                        &parent_sha[..7]
+                    )
+                };
+                return commit_diff_from_trees(
+                    &repo,
+                    Some(&parent.tree()?),
+                    &commit_tree,
+                    Some(preamble),
+                );
+            }
+        }
+    }
+
+    let parent_tree = if parent_count == 1 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    commit_diff_from_trees(&repo, parent_tree.as_ref(), &commit_tree, None)
+}
+
+/// Blob id and contents of `path` in `tree`, if it is a file there.
+fn tree_blob(
+    repo: &Repository,
+    tree: &git2::Tree<'_>,
+    path: &Path,
+) -> Result<Option<(git2::Oid, Vec<u8>)>, Error> {
+    let entry = match tree.get_path(path) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if entry.kind() != Some(ObjectType::Blob) {
+        return Ok(None);
+    }
+    let blob = repo.find_blob(entry.id())?;
+    Ok(Some((entry.id(), blob.content().to_vec())))
+}
+
+/// New-side line ranges (`start..end`) of the hunks between two file versions.
+/// A pure deletion covers the lines on either side of it.
+fn hunk_ranges(old: &[u8], new: &[u8]) -> Result<Vec<(u32, u32)>, Error> {
+    let mut opts = DiffOptions::new();
+    opts.context_lines(0);
+    let patch = git2::Patch::from_buffers(old, None, new, None, Some(&mut opts))?;
+    let mut ranges = Vec::new();
+    for idx in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(idx)?;
+        let start = hunk.new_start();
+        if hunk.new_lines() == 0 {
+            ranges.push((start, start + 2));
+        } else {
+            ranges.push((start, start + hunk.new_lines()));
+        }
+    }
+    Ok(ranges)
+}
+
+fn ranges_overlap(a: (u32, u32), b: (u32, u32)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+/// Files of a merge that differ from every parent. For files present on all
+/// sides, only the first-parent hunks that also changed against every other
+/// parent are kept.
+fn combined_file_changes(repo: &Repository, commit: &Commit<'_>) -> Result<Vec<FileChange>, Error> {
+    let commit_tree = commit.tree()?;
+    let parent_trees = commit
+        .parents()
+        .map(|p| p.tree())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.context_lines(3);
+    diff_opts.interhunk_lines(0);
+    let mut diff = repo.diff_tree_to_tree(
+        Some(&parent_trees[0]),
+        Some(&commit_tree),
+        Some(&mut diff_opts),
+    )?;
+    let first_parent_files = collect_file_changes(&mut diff)?;
+
+    let mut files = Vec::new();
+    for mut file in first_parent_files {
+        let Some(path) = file.new_path.clone().or_else(|| file.old_path.clone()) else {
+            continue;
+        };
+        let path = Path::new(&path);
+        let result = tree_blob(repo, &commit_tree, path)?;
+        let mut others = Vec::new();
+        for tree in &parent_trees[1..] {
+            others.push(tree_blob(repo, tree, path)?);
+        }
+        let result_id = result.as_ref().map(|(id, _)| *id);
+        if others
+            .iter()
+            .any(|other| other.as_ref().map(|(id, _)| *id) == result_id)
+        {
+            // Taken unchanged from another parent.
+            continue;
+        }
+
+        let first = tree_blob(repo, &parent_trees[0], path)?;
+        if let (Some((_, result)), Some((_, first))) = (&result, &first)
+            && others.iter().all(Option::is_some)
+        {
+            let other_ranges = others
+                .iter()
+                .flatten()
+                .map(|(_, other)| hunk_ranges(other, result))
+                .collect::<Result<Vec<_>, _>>()?;
+            file.patch = combined_patch(first, result, &other_ranges)?;
+            if file.patch.is_empty() {
+                continue;
+            }
+        }
+        files.push(file);
+    }
+    Ok(files)
+}
+
+/// Patch text of `first -> result` limited to the hunks that overlap a hunk
+/// in each of `other_ranges`.
+fn combined_patch(
+    first: &[u8],
+    result: &[u8],
+    other_ranges: &[Vec<(u32, u32)>],
+) -> Result<String, Error> {
+    let mut opts = DiffOptions::new();
+    opts.context_lines(3);
+    opts.interhunk_lines(0);
+    let patch = git2::Patch::from_buffers(first, None, result, None, Some(&mut opts))?;
+    let mut text = String::new();
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_idx)?;
+        // New-side lines touched by this hunk. A deletion sits between two
+        // new-side lines and touches both.
+        let mut changed = Vec::new();
+        let mut new_line = hunk.new_start();
+        for line_idx in 0..line_count {
+            match patch.line_in_hunk(hunk_idx, line_idx)?.origin_value() {
+                DiffLineType::Context => new_line += 1,
+                DiffLineType::Addition => {
+                    changed.push((new_line, new_line + 1));
+                    new_line += 1;
+                }
+                DiffLineType::Deletion => {
+                    changed.push((new_line.saturating_sub(1), new_line + 1));
+                }
+                _ => {}
+            }
+        }
+        let kept = other_ranges.iter().all(|ranges| {
+            ranges
+                .iter()
+                .any(|&r| changed.iter().any(|&c| ranges_overlap(c, r)))
+        });
+        if !kept {
+            continue;
+        }
+        if let Ok(header) = str::from_utf8(hunk.header()) {
+            text.push_str(header);
+        }
+        for line_idx in 0..line_count {
+            append_patch_line(&mut text, &patch.line_in_hunk(hunk_idx, line_idx)?);
+        }
+    }
+    Ok(text)
+}
+
+/// Tree of an automatic merge of the two parents of `commit`. Conflicted
+/// files keep their conflict markers, labelled with the parents' short SHAs.
+fn remerge_tree<'r>(repo: &'r Repository, commit: &Commit<'_>) -> Result<git2::Tree<'r>, Error> {
+    let ours = commit.parent(0)?;
+    let theirs = commit.parent(1)?;
+    let mut index = repo.merge_commits(&ours, &theirs, None)?;
+
+    if index.has_conflicts() {
+        let conflicts = index.conflicts()?.collect::<Result<Vec<_>, _>>()?;
+        let our_label = ours.id().to_string()[..7].to_string();
+        let their_label = theirs.id().to_string()[..7].to_string();
+        for conflict in conflicts {
+            let resolved = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+                (Some(ancestor), Some(our), Some(their)) => {
+                    let mut opts = git2::MergeFileOptions::new();
+                    opts.our_label(&our_label).their_label(&their_label);
+                    let merged =
+                        repo.merge_file_from_index(ancestor, our, their, Some(&mut opts))?;
+                    let content = merged.content();
+                    let mut entry = index_entry_copy(our);
+                    entry.id = repo.blob(content)?;
+                    entry.file_size = content.len() as u32;
+                    Some(entry)
+                }
+                // Added on both sides or deleted on one: keep what remains,
+                // preferring the first parent.
+                (_, Some(our), _) => Some(index_entry_copy(our)),
+                (_, None, Some(their)) => Some(index_entry_copy(their)),
+                _ => None,
+            };
+            let Some(path) = [&conflict.our, &conflict.their, &conflict.ancestor]
+                .into_iter()
+                .flatten()
+                .next()
+                .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+            else {
+                continue;
+            };
+            index.conflict_remove(Path::new(&path))?;
+            if let Some(entry) = resolved {
+                index.add(&entry)?;
+            }
+        }
+    }
+
+    let tree_oid = index.write_tree_to(repo)?;
+    repo.find_tree(tree_oid)
+}
+
+/// Stage bits of `IndexEntry::flags` (`GIT_INDEX_ENTRY_STAGEMASK`).
+const INDEX_ENTRY_STAGE_MASK: u16 = 0x3000;
+
+/// Copy of a conflict entry staged as a resolved (stage 0) entry.
+fn index_entry_copy(entry: &git2::IndexEntry) -> git2::IndexEntry {
+    git2::IndexEntry {
+        flags: entry.flags & !INDEX_ENTRY_STAGE_MASK,
+        path: entry.path.clone(),
+        ..*entry
+    }
+}
+
+pub fn get_range_diff(path: &str, oldest_sha: &str, newest_sha: &str) -> Result<CommitDiff, Error> {
+    let repo = Repository::open(path)?;
+
+    let oldest_oid = git2::Oid::from_str(oldest_sha)?;
+    let oldest_commit = repo.find_commit(oldest_oid)?;
+
+    let newest_oid = git2::Oid::from_str(newest_sha)?;
+    let newest_commit = repo.find_commit(newest_oid)?;
+
+    let base_tree = if oldest_commit.parents().len() >= 1 {
+        let parent = oldest_commit.parent(0)?;
+        Some(parent.tree()?)
+    } else {
+        None
+    };
+
+    let newest_tree = newest_commit.tree()?;
+    commit_diff_from_trees(&repo, base_tree.as_ref(), &newest_tree, None)
+}
+
+/// Most commits listed for each side of a comparison.
+pub const COMPARE_COMMIT_LIMIT: usize = 1000;
+
+/// Two commits compared with `compare_refs`.
+#[derive(Debug)]
+pub struct RefComparison {
+    pub base_sha: String,
+    pub head_sha: String,
+    /// `None` when the two histories are unrelated.
+    pub merge_base: Option<String>,
+    /// Commits reachable from head but not from base (`base..head`), newest
+    /// first, at most `COMPARE_COMMIT_LIMIT`.
+    pub only_in_head: Vec<GitCommit>,
+    /// Commits reachable from base but not from head (`head..base`).
+    pub only_in_base: Vec<GitCommit>,
+    /// Whether either commit list was cut off at the limit.
+    pub truncated: bool,
+    /// `git diff base..head`: the trees of the two commits.
+    pub two_dot: CommitDiff,
+    /// `git diff base...head`: what head changed since the merge base.
+    pub three_dot: CommitDiff,
+}
+
+/// Compare two revisions (branch, tag, remote branch or SHA).
+pub fn compare_refs(path: &str, base: &str, head: &str) -> Result<RefComparison, Error> {
+    let repo = Repository::open(path)?;
+    let base_commit = repo.revparse_single(base)?.peel_to_commit()?;
+    let head_commit = repo.revparse_single(head)?.peel_to_commit()?;
+    let base_oid = base_commit.id();
+    let head_oid = head_commit.id();
+
+    let unique_commits =
+        |from: git2::Oid, hide: git2::Oid| -> Result<(Vec<GitCommit>, bool), Error> {
+            let mut opts = CommitQueryOptions::for_branch(&from.to_string());
+            opts.revspecs.push(format!("^{hide}"));
+            opts.sort = CommitSort::Time;
+            CommitWalker::new(&repo, opts)?.first_commits(COMPARE_COMMIT_LIMIT)
+        };
+    let (only_in_head, head_truncated) = unique_commits(head_oid, base_oid)?;
+    let (only_in_base, base_truncated) = unique_commits(base_oid, head_oid)?;
+
+    let merge_base = repo.merge_base(base_oid, head_oid).ok();
+    let base_tree = base_commit.tree()?;
+    let head_tree = head_commit.tree()?;
+    let two_dot = commit_diff_from_trees(&repo, Some(&base_tree), &head_tree, None)?;
+    let three_dot = match merge_base {
+        Some(oid) => {
+            let merge_base_tree = repo.find_commit(oid)?.tree()?;
+            commit_diff_from_trees(&repo, Some(&merge_base_tree), &head_tree, None)?
+        }
+        None => commit_diff_from_trees(
+            &repo,
+            None,
+            &head_tree,
+            Some("No common ancestor - showing every file on the compared side".to_string()),
+        )?,
+    };
+
+    Ok(RefComparison {
+        base_sha: base_oid.to_string(),
+        head_sha: head_oid.to_string(),
+        merge_base: merge_base.map(|oid| oid.to_string()),
+        only_in_head,
+        only_in_base,
+        truncated: head_truncated || base_truncated,
+        two_dot,
+        three_dot,
+    })
+}
+
+/// Most commits listed per release; older history of a first release or a
+/// long-lived tag is cut off.
+pub const RELEASE_COMMIT_LIMIT: usize = 1000;
+
+/// A version tag and what went into it since the previous version.
+#[derive(Debug)]
+pub struct Release {
+    pub tag: String,
+    /// The tagged commit.
+    pub sha: String,
+    /// Tagger date of an annotated tag, else the date of the tagged commit.
+    pub time: DateTime<Utc>,
+    /// `Name <email>` of the tagger; `None` for lightweight tags.
+    pub tagger: Option<String>,
+    /// Message of an annotated tag, trimmed; `None` for lightweight tags.
+    pub message: Option<String>,
+    /// The next lower version tag, whose commits are excluded.
+    pub previous_tag: Option<String>,
+    /// Commits in `previous_tag..tag`, newest first, at most
+    /// `RELEASE_COMMIT_LIMIT`.
+    pub commits: Vec<GitCommit>,
+    /// Whether `commits` was cut off at the limit.
+    pub truncated: bool,
+}
+
+/// Releases for `tags` (tag names, highest version first), each with the
+/// commits since the next tag in the list. Tags that don't point to a commit
+/// are skipped.
+pub fn get_releases(path: &Path, tags: &[String]) -> Result<Vec<Release>, Error> {
+    let repo = Repository::open(path)?;
+    let mut resolved: Vec<(String, git2::Oid, Option<git2::Tag>)> = Vec::new();
+    for name in tags {
+        let Some(oid) = repo
+            .find_reference(&format!("refs/tags/{name}"))
+            .ok()
+            .and_then(|reference| reference.target())
+        else {
+            continue;
+        };
+        if let Ok(obj) = repo.find_object(oid, None)
+            && let Ok(commit) = obj.peel_to_commit()
+        {
+            resolved.push((name.clone(), commit.id(), obj.into_tag().ok()));
+        }
+    }
+    let tags = resolved;
+
+    let mut releases = Vec::with_capacity(tags.len());
+    for (idx, (name, commit_oid, tag)) in tags.iter().enumerate() {
+        let previous = tags.get(idx + 1);
+        let mut opts = CommitQueryOptions::for_branch(&commit_oid.to_string());
+        if let Some((_, previous_oid, _)) = previous {
+            opts.revspecs.push(format!("^{previous_oid}"));
+        }
+        opts.sort = CommitSort::Time;
+        let (commits, truncated) =
+            CommitWalker::new(&repo, opts)?.first_commits(RELEASE_COMMIT_LIMIT)?;
+
+        let tagger = tag.as_ref().and_then(|t| t.tagger());
+        let time = match &tagger {
+            Some(sig) => git_time_to_utc(sig.when()),
+            None => git_time_to_utc(repo.find_commit(*commit_oid)?.time()),
+        };
+        releases.push(Release {
+            tag: name.clone(),
+            sha: commit_oid.to_string(),
+            time,
+            tagger: tagger.map(|sig| sig.to_string()),
+            message: tag
+                .as_ref()
+                .and_then(|t| t.message())
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty()),
+            previous_tag: previous.map(|(name, _, _)| name.clone()),
+            commits,
+            truncated,
+        });
+    }
+    Ok(releases)
+}
+
+/// Number of files with each kind of uncommitted change. A file that is
+/// both staged and modified again in the working tree counts in both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkingTreeStatus {
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+}
+
+impl WorkingTreeStatus {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// One-line description, e.g. "Uncommitted changes (1 staged, 2 unstaged)".
+    pub fn summary(&self) -> String {
+        let parts: Vec<String> = [
+            (self.conflicted, "conflicted"),
+            (self.staged, "staged"),
+            (self.unstaged, "unstaged"),
+            (self.untracked, "untracked"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+
+        if parts.is_empty() {
+            "No uncommitted changes".to_string()
+        } else {
+            format!("Uncommitted changes ({})", parts.join(", "))
+        }
+    }
+}
+
+/// Whether untracked directories are listed file by file. Shared by the
+/// status counts and the diff so the row summary matches the files shown.
+const RECURSE_UNTRACKED_DIRS: bool = true;
+
+pub fn get_working_tree_status(repo: &Repository) -> Result<WorkingTreeStatus, Error> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(RECURSE_UNTRACKED_DIRS);
+
+    let staged_flags = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE;
+    let unstaged_flags =
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+
+    let mut status = WorkingTreeStatus::default();
+    for entry in repo.statuses(Some(&mut opts))?.iter() {
+        let flags = entry.status();
+        if flags.contains(Status::CONFLICTED) {
+            status.conflicted += 1;
+            continue;
+        }
+        if flags.intersects(staged_flags) {
+            status.staged += 1;
+        }
+        if flags.intersects(unstaged_flags) {
+            status.unstaged += 1;
+        }
+        if flags.contains(Status::WT_NEW) {
+            status.untracked += 1;
+        }
+    }
+    Ok(status)
+}
+
+/// Pseudo commit for the uncommitted changes when `branch_ref` is the
+/// checked-out branch (or `HEAD`) and the working tree is dirty. `None` for
+/// bare repositories, other refs (even tags at the HEAD commit) and clean
+/// trees.
+pub fn working_tree_commit(repo: &Repository, branch_ref: &str) -> Option<GitCommit> {
+    if repo.is_bare() {
+        return None;
+    }
+    let head_ref = repo.head().ok()?;
+    if branch_ref != "HEAD" {
+        let reference = repo.resolve_reference_from_short_name(branch_ref).ok()?;
+        if !head_ref.is_branch() || reference.name() != head_ref.name() {
+            return None;
+        }
+    }
+    let head = head_ref.peel_to_commit().ok()?.id();
+
+    let status = get_working_tree_status(repo).ok()?;
+    if status.is_clean() {
+        return None;
+    }
+    Some(GitCommit::working_tree(&status, &head.to_string()))
+}
+
+/// Uncommitted changes, grouped into staged changes (HEAD to index), unstaged
+/// changes (index to working tree, including untracked files) and, with
+/// `include_ignored`, ignored files. Conflicted paths appear in the unstaged
+/// group.
+pub fn get_working_tree_diff(path: &str, include_ignored: bool) -> Result<CommitDiff, Error> {
+    let repo = Repository::open(path)?;
+    let index = repo.index()?;
+    // An unborn HEAD has no tree; everything in the index is then staged.
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(_) => None,
+    };
+
+    let mut staged_opts = DiffOptions::new();
+    staged_opts.context_lines(3).interhunk_lines(0);
+    let mut staged =
+        repo.diff_tree_to_index(head_tree.as_ref(), Some(&index), Some(&mut staged_opts))?;
+    let mut find_opts = DiffFindOptions::new();
+    find_opts.renames(true).rename_threshold(50);
+    staged.find_similar(Some(&mut find_opts))?;
+
+    let mut workdir_opts = DiffOptions::new();
+    workdir_opts
+        .context_lines(3)
+        .interhunk_lines(0)
+        .include_untracked(true)
+        .recurse_untracked_dirs(RECURSE_UNTRACKED_DIRS)
+        .show_untracked_content(true)
+        .include_ignored(include_ignored)
+        .recurse_ignored_dirs(false);
+    let mut workdir = repo.diff_index_to_workdir(Some(&index), Some(&mut workdir_opts))?;
+
+    let staged_files = collect_file_changes(&mut staged)?;
+    let (mut ignored_files, unstaged_files): (Vec<_>, Vec<_>) = collect_file_changes(&mut workdir)?
+        .into_iter()
+        .partition(|f| f.kind == FileChangeKind::Ignored);
+    // Ignored entries are only listed, never expanded.
+    for file in &mut ignored_files {
+        file.patch.clear();
+    }
+
+    let mut files = Vec::new();
+    let mut group_headings = Vec::new();
+    for (heading, group) in [
+        ("Staged changes", staged_files),
+        ("Unstaged changes", unstaged_files),
+        ("Ignored files", ignored_files),
+    ] {
+        if !group.is_empty() {
+            group_headings.push((files.len(), heading.to_string()));
+            files.extend(group);
+        }
+    }
+
+    Ok(CommitDiff {
+        preamble: None,
+        files,
+        group_headings,
+    })
+}
+
+/// One line of a file annotated with the commit that last changed it.
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    pub content: String,
+    pub commit_id: String,
+    pub author: String,
+    pub date: String,
+}
+
+/// Blame `file_path` as it exists at `commit_sha`, returning one entry per line.
+///
+/// libgit2 cannot interrupt a blame in progress, so `cancel` is checked before
+/// and after the blame runs and while lines are assembled; a cancelled blame
+/// returns an error.
+pub fn get_file_blame(
+    path: &str,
+    commit_sha: &str,
+    file_path: &str,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Result<Vec<BlameLine>, Error> {
+    let is_cancelled = || cancel.is_some_and(|c| c.load(Ordering::Relaxed));
+    let cancelled = || Error::from_str("Blame cancelled");
+
+    let repo = Repository::open(path)?;
+    let commit_oid = git2::Oid::from_str(commit_sha)?;
+    let commit = repo.find_commit(commit_oid)?;
+    let entry = commit.tree()?.get_path(Path::new(file_path))?;
+    let blob = repo.find_blob(entry.id())?;
+    if blob.is_binary() {
+        return Err(Error::from_str("Cannot blame a binary file"));
+    }
+    let content = String::from_utf8_lossy(blob.content()).to_string();
+
+    if is_cancelled() {
+        return Err(cancelled());
+    }
+
+    let mut opts = git2::BlameOptions::new();
+    opts.newest_commit(commit_oid);
+    let blame = repo.blame_file(Path::new(file_path), Some(&mut opts))?;
+
+    let mut lines = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        if idx % 1000 == 0 && is_cancelled() {
+            return Err(cancelled());
+        }
+        let (commit_id, author, date) = match blame.get_line(idx + 1) {
+            Some(hunk) => {
+                let sig = hunk.final_signature();
+                (
+                    hunk.final_commit_id().to_string(),
+                    sig.name().unwrap_or("").to_string(),
+                    format_date(&sig.when()),
+                )
+            }
+            None => (String::new(), String::new(), String::new()),
+        };
+        lines.push(BlameLine {
+            content: line.to_string(),
+            commit_id,
+            author,
+            date,
+        });
+    }
+    Ok(lines)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeEntryKind {
+    Directory,
+    File,
+    Symlink,
+    Submodule,
+}
+
+/// One entry of a directory in a commit's file tree.
+#[derive(Debug, Clone)]
+pub struct TreeEntry {
+    pub name: String,
+    /// Path relative to the repository root.
+    pub path: String,
+    pub kind: TreeEntryKind,
+}
+
+/// List the directory `dir_path` (empty for the root) as it exists at
+/// `commit_sha`. Directories come first, then files, each sorted by name.
+pub fn get_tree_entries(
+    path: &str,
+    commit_sha: &str,
+    dir_path: &str,
+) -> Result<Vec<TreeEntry>, Error> {
+    let repo = Repository::open(path)?;
+    let commit = repo.find_commit(git2::Oid::from_str(commit_sha)?)?;
+    let root = commit.tree()?;
+    let tree = if dir_path.is_empty() {
+        root
+    } else {
+        root.get_path(Path::new(dir_path))?
+            .to_object(&repo)?
+            .peel_to_tree()?
+    };
+
+    let mut entries: Vec<TreeEntry> = tree
+        .iter()
+        .map(|entry| {
+            let name = String::from_utf8_lossy(entry.name_bytes()).into_owned();
+            let kind = match entry.kind() {
+                Some(ObjectType::Tree) => TreeEntryKind::Directory,
+                Some(ObjectType::Commit) => TreeEntryKind::Submodule,
+                _ if entry.filemode() == i32::from(git2::FileMode::Link) => TreeEntryKind::Symlink,
+                _ => TreeEntryKind::File,
+            };
+            let path = if dir_path.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", dir_path, name)
+            };
+            TreeEntry { name, path, kind }
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        let a_dir = a.kind == TreeEntryKind::Directory;
+        let b_dir = b.kind == TreeEntryKind::Directory;
+        b_dir.cmp(&a_dir).then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(entries)
+}
+
+/// Contents of a file at a commit. `text` is `None` for binary files.
+#[derive(Debug, Clone)]
+pub struct FileContent {
+    pub text: Option<String>,
+    pub size: usize,
+}
+
+/// Read `file_path` as it exists at `commit_sha`.
+pub fn get_file_content(
+    path: &str,
+    commit_sha: &str,
+    file_path: &str,
+) -> Result<FileContent, Error> {
+    let repo = Repository::open(path)?;
+    let commit = repo.find_commit(git2::Oid::from_str(commit_sha)?)?;
+    let entry = commit.tree()?.get_path(Path::new(file_path))?;
+    let blob = repo.find_blob(entry.id())?;
+    let text = (!blob.is_binary()).then(|| String::from_utf8_lossy(blob.content()).into_owned());
+    Ok(FileContent {
+        text,
+        size: blob.size(),
+    })
+}
+
+pub fn validate_repository(path: &Path) -> Result<(), git2::Error> {
+    Repository::open(path).map(|_| ())
+}
+
+/// The repository's git directory (holding `HEAD` and `index`) and its common
+/// directory (holding `refs/` and `packed-refs`). They differ for linked
+/// worktrees.
+pub fn git_dirs(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let repo = Repository::open(path).ok()?;
+    Some((repo.path().to_path_buf(), repo.commondir().to_path_buf()))
+}
+
+/// If `path` is inside a git worktree (or is a bare repo), return the best path
+/// to open in the UI:
+/// - worktree root for normal repositories
+/// - repo directory for bare repositories
+pub fn discover_repository_root(path: &Path) -> Option<PathBuf> {
+    let repo = Repository::discover(path).ok()?;
+    if let Some(workdir) = repo.workdir() {
+        Some(workdir.to_path_buf())
+    } else {
+        // Bare repository
+        Some(repo.path().to_path_buf())
+    }
+}
+
+/// Full SHA of the commit `spec` (a SHA, SHA prefix or any revspec) names.
+pub fn resolve_commit(path: &Path, spec: &str) -> Option<String> {
+    let repo = Repository::open(path).ok()?;
+    let commit = repo.revparse_single(spec).ok()?.peel_to_commit().ok()?;
+    Some(commit.id().to_string())
+}
+
+pub fn branch_exists(path: &Path, branch_name: &str) -> bool {
+    let Ok(repo) = Repository::open(path) else {
+        return false;
+    };
+    repo.revparse_single(branch_name).is_ok()
+}
+
+/// Returns the currently checked-out branch name for the repository at `path`.
+/// If HEAD is detached (or can't be read), returns `None`.
+pub fn checked_out_branch_name(path: &Path) -> Option<String> {
+    let repo = Repository::open(path).ok()?;
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    head.shorthand().map(|s| s.to_string())
+}
+
+/// Returns the name and HEAD commit SHA of the repository's primary branch
+/// (`main` or `master`), but only when `current_ref` is a *different* branch.
+/// Used to show a "main" chip on the commit where the primary branch sits.
+pub fn get_main_branch_head(path: &Path, current_ref: &str) -> Option<(String, String)> {
+    let repo = Repository::open(path).ok()?;
+
+    for branch_name in &["main", "master"] {
+        if *branch_name == current_ref {
+            return None;
+        }
+        let ref_name = format!("refs/heads/{}", branch_name);
+        if let Ok(reference) = repo.find_reference(&ref_name) {
+            if let Ok(commit) = reference.peel_to_commit() {
+                return Some((branch_name.to_string(), commit.id().to_string()));
+            }
+        }
+    }
+    None
+}
+
+/// If the given branch has an upstream remote (e.g. `origin/main`), returns
+/// `Some((upstream_ref_name, commit_sha))` so the UI can show a chip on that commit.
+/// Returns `None` if the ref is not a local branch or has no upstream.
+pub fn get_branch_upstream(path: &Path, branch_ref: &str) -> Option<(String, String)> {
+    let repo = Repository::open(path).ok()?;
+
+    // If viewing HEAD, resolve to branch name so we can look up upstream
+    let branch_name = if branch_ref == "HEAD" {
+        let head = repo.head().ok()?;
+        if !head.is_branch() {
+            return None;
+        }
+        head.shorthand().map(|s| s.to_string())?
+    } else {
+        branch_ref.to_string()
+    };
+
+    let ref_name = format!("refs/heads/{}", branch_name);
+    let reference = repo.find_reference(&ref_name).ok()?;
+    let branch = git2::Branch::wrap(reference);
+    let upstream = branch.upstream().ok()?;
+    let upstream_name = upstream.name().ok().flatten()?.to_string();
+    let commit = upstream.get().peel_to_commit().ok()?;
+    let sha = commit.id().to_string();
+
+    Some((upstream_name, sha))
+}
+
+/// Number of commits a branch has that another doesn't (`ahead`), and the
+/// reverse (`behind`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AheadBehind {
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+/// How far a local branch has moved from its upstream and from the primary
+/// branch. Each side holds the other branch's name with the counts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BranchDivergence {
+    pub upstream: Option<(String, AheadBehind)>,
+    /// `None` for the primary branch itself.
+    pub main: Option<(String, AheadBehind)>,
+}
+
+/// Ahead/behind counts of every local branch against its upstream and the
+/// primary branch (`main` or `master`), keyed by branch name.
+///
+/// Each count walks the history between the two tips, so this is meant for
+/// a background thread; `cancel` is checked between branches.
+pub fn get_branch_divergence(
+    path: &Path,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Result<std::collections::HashMap<String, BranchDivergence>, Error> {
+    let repo = Repository::open(path)?;
+    // No ref to exclude: every branch but the primary one is compared with it.
+    let main = get_main_branch_head(path, "")
+        .and_then(|(name, sha)| Some((name, git2::Oid::from_str(&sha).ok()?)));
+
+    let mut divergence = std::collections::HashMap::new();
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(Error::from_str("Cancelled"));
+        }
+        let (branch, _) = branch?;
+        let Some(name) = branch.name()?.map(str::to_string) else {
+            continue;
+        };
+        let Ok(tip) = branch.get().peel_to_commit().map(|c| c.id()) else {
+            continue;
+        };
+        let ahead_behind = |other: git2::Oid| {
+            repo.graph_ahead_behind(tip, other)
+                .ok()
+                .map(|(ahead, behind)| AheadBehind { ahead, behind })
+        };
+
+        let upstream = branch.upstream().ok().and_then(|upstream| {
+            let upstream_name = upstream.name().ok().flatten()?.to_string();
+            let upstream_tip = upstream.get().peel_to_commit().ok()?.id();
+            Some((upstream_name, ahead_behind(upstream_tip)?))
+        });
+        let main = main
+            .as_ref()
+            .filter(|(main_name, _)| *main_name != name)
+            .and_then(|(main_name, main_tip)| Some((main_name.clone(), ahead_behind(*main_tip)?)));
+
+        divergence.insert(name, BranchDivergence { upstream, main });
+    }
+    Ok(divergence)
+}
+
+/// Returns a mapping from commit SHA to tag names for all tags in the repository.
+///
+/// The returned HashMap maps full commit SHA strings to vectors of tag names
+/// that point to that commit (either directly or through tag objects).
+pub fn get_tags(path: &Path) -> Result<std::collections::HashMap<String, Vec<String>>, Error> {
+    use std::collections::HashMap;
+
+    let repo = Repository::open(path)?;
+    let mut tag_map: HashMap<String, Vec<String>> = HashMap::new();
+
+    repo.tag_foreach(|oid, name_bytes| {
+        // Tag names come as "refs/tags/tagname" - extract just the tag name
         let name = str::from_utf8(name_bytes)
             .ok()
             .and_then(|s| s.strip_prefix("refs/tags/"))
@@ -904,6 +2587,24 @@ mod tests {
         assert!(done3);
     }
 
+    #[test]
+    fn first_commits_only_reports_truncation_past_the_limit() {
+        let mut tr = TestRepo::new();
+        for i in 0..3 {
+            tr.commit(&format!("commit {i}"));
+        }
+        let walker =
+            || CommitWalker::new(tr.repo(), CommitQueryOptions::for_branch("main")).unwrap();
+
+        let (commits, truncated) = walker().first_commits(3).unwrap();
+        assert_eq!(commits.len(), 3);
+        assert!(!truncated);
+
+        let (commits, truncated) = walker().first_commits(2).unwrap();
+        assert_eq!(messages(&commits), vec!["commit 2", "commit 1"]);
+        assert!(truncated);
+    }
+
     #[test]
     fn commit_walker_filters_by_message() {
         let mut tr = TestRepo::new();
@@ -932,6 +2633,65 @@ mod tests {
         assert_eq!(messages(&commits), vec!["also alice", "by alice"]);
     }
 
+    #[test]
+    fn commit_walker_author_filter_matches_co_authors() {
+        let mut tr = TestRepo::new();
+        tr.commit_by("main", "solo", "Alice", "alice@example.com");
+        tr.commit_by(
+            "main",
+            "pairing\n\nCo-authored-by: Carol <carol@example.com>",
+            "Bob",
+            "bob@example.com",
+        );
+        tr.commit_by(
+            "main",
+            "mentions Carol\n\nReviewed-by: Carol <carol@example.com>",
+            "Bob",
+            "bob@example.com",
+        );
+
+        let mut opts = CommitQueryOptions::for_branch("main");
+        opts.author_contains = Some("carol@".to_string());
+        let commits = collect(tr.repo(), opts);
+        assert_eq!(
+            messages(&commits),
+            vec!["pairing\n\nCo-authored-by: Carol <carol@example.com>"]
+        );
+    }
+
+    #[test]
+    fn trailers_come_from_the_last_paragraph_only() {
+        let trailers = parse_trailers(
+            "Fix parser\n\nBody: not a trailer block\nbecause of this line\n\n\
+             Co-authored-by: Jane Doe <jane@example.com>\n\
+             reviewed-by: Joe\n\
+             (cherry picked from commit 0123abc)\n\
+             Signed-off-by: Long\n  Name <long@example.com>\n",
+        );
+        let pairs: Vec<(&str, &str)> = trailers
+            .iter()
+            .map(|t| (t.key.as_str(), t.value.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("Co-authored-by", "Jane Doe <jane@example.com>"),
+                ("reviewed-by", "Joe"),
+                ("Signed-off-by", "Long Name <long@example.com>"),
+            ]
+        );
+        assert!(trailers[1].is(REVIEWED_BY));
+        assert_eq!(
+            trailers[0].identity(),
+            ("Jane Doe", Some("jane@example.com"))
+        );
+        assert_eq!(trailers[1].identity(), ("Joe", None));
+
+        assert!(parse_trailers("Subject: looks like a trailer").is_empty());
+        assert!(parse_trailers("Subject\n\nSee https://example.com for details").is_empty());
+        assert!(parse_trailers("Subject\n\nAcked-by: A\nThanks!").is_empty());
+    }
+
     #[test]
     fn commit_walker_min_parents_selects_only_merge() {
         let mut tr = TestRepo::new();
@@ -1029,7 +2789,12 @@ mod tests {
             )
             .unwrap();
 
-        let diff = get_commit_diff(tr.path().to_str().unwrap(), &rename_oid.to_string()).unwrap();
+        let diff = get_commit_diff(
+            tr.path().to_str().unwrap(),
+            &rename_oid.to_string(),
+            MergeDiffMode::default(),
+        )
+        .unwrap();
         assert_eq!(
             diff.files.len(),
             1,
@@ -1050,4 +2815,682 @@ mod tests {
             "patch should not include file headers"
         );
     }
+
+    #[test]
+    fn commit_stats_match_the_line_counts_of_each_file() {
+        let mut tr = TestRepo::new();
+        let root = tr.commit_file("main", "a.txt", "one\ntwo\nthree\n", "add a");
+        let add_b = tr.commit_file("main", "b.txt", "-- not a header\n", "add b");
+        let tip = tr.commit_file("main", "a.txt", "one\n++two\nthree\nfour\n", "edit a");
+
+        let stats = get_commit_stats(tr.path(), &root.to_string()).unwrap();
+        assert_eq!(
+            stats,
+            DiffStats {
+                files_changed: 1,
+                insertions: 3,
+                deletions: 0
+            }
+        );
+
+        let stats = get_commit_stats(tr.path(), &tip.to_string()).unwrap();
+        assert_eq!(
+            stats,
+            DiffStats {
+                files_changed: 1,
+                insertions: 2,
+                deletions: 1
+            }
+        );
+        let diff = get_commit_diff(
+            tr.path().to_str().unwrap(),
+            &tip.to_string(),
+            MergeDiffMode::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.files[0].line_counts(), (2, 1));
+
+        let diff = get_commit_diff(
+            tr.path().to_str().unwrap(),
+            &add_b.to_string(),
+            MergeDiffMode::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.files[0].line_counts(), (1, 0));
+    }
+
+    #[test]
+    fn commit_diff_flags_binary_files_only() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "a.bin", "\0\x01", "add binary");
+        let sha = tr
+            .commit_file(
+                "main",
+                "notes.txt",
+                "Binary files a and b differ\n",
+                "add notes",
+            )
+            .to_string();
+        let path = tr.path().to_str().unwrap();
+
+        let root = tr
+            .repo()
+            .revparse_single("main~1")
+            .unwrap()
+            .id()
+            .to_string();
+        let diff = get_commit_diff(path, &root, MergeDiffMode::default()).unwrap();
+        assert!(diff.files[0].binary);
+        assert_eq!(diff.files[0].line_counts(), (0, 0));
+
+        let diff = get_commit_diff(path, &sha, MergeDiffMode::default()).unwrap();
+        assert!(!diff.files[0].binary);
+        assert_eq!(diff.files[0].line_counts(), (1, 0));
+    }
+
+    #[test]
+    fn format_patches_numbers_commits_and_skips_merges() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "a.txt", "one\n", "Add a");
+        tr.create_branch("topic", "main");
+        let first = tr.commit_file("topic", "a.txt", "one\ntwo\n", "Fix: the a/b \"case\"");
+        let second = tr.commit_file("topic", "b.txt", "x\n", "Add b\n\nLonger explanation.");
+        let merge = tr.merge_commit("main", "topic", "Merge topic");
+
+        let shas: Vec<String> = [first, second, merge]
+            .iter()
+            .map(|o| o.to_string())
+            .collect();
+        let patches = format_patches(tr.path(), &shas).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].file_name, "0001-Fix-the-a-b-case.patch");
+        assert_eq!(patches[1].file_name, "0002-Add-b.patch");
+
+        let text = &patches[0].text;
+        assert!(text.starts_with(&format!("From {first} ")));
+        assert!(text.contains("From: Tester <tester@example.com>\n"));
+        assert!(text.contains("\nDate: "));
+        assert!(text.contains("Subject: [PATCH 1/2] Fix: the a/b \"case\"\n"));
+        assert!(text.contains(" a.txt | 1 +\n"));
+        assert!(text.contains("+two\n"));
+        assert!(patches[1].text.contains("Subject: [PATCH 2/2] Add b\n"));
+        assert!(patches[1].text.contains("Longer explanation."));
+
+        assert_eq!(patch_file_name(3, &"x".repeat(80)).len(), 4 + 1 + 52 + 6);
+    }
+
+    #[test]
+    fn get_file_blame_attributes_lines_to_last_change() {
+        let mut tr = TestRepo::new();
+        let first = tr.commit_file("main", "notes.txt", "alpha\nbeta\n", "add notes");
+        let second = tr.commit_file("main", "notes.txt", "alpha\nbeta changed\ngamma\n", "edit");
+        tr.commit("unrelated");
+
+        let path = tr.path().to_str().unwrap();
+        let lines = get_file_blame(path, &second.to_string(), "notes.txt", None).unwrap();
+        let annotated: Vec<(&str, String)> = lines
+            .iter()
+            .map(|l| (l.content.as_str(), l.commit_id.clone()))
+            .collect();
+        assert_eq!(
+            annotated,
+            vec![
+                ("alpha", first.to_string()),
+                ("beta changed", second.to_string()),
+                ("gamma", second.to_string()),
+            ]
+        );
+        assert_eq!(lines[0].author, "Tester");
+
+        // Blaming at the older commit ignores the later edit.
+        let lines = get_file_blame(path, &first.to_string(), "notes.txt", None).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.commit_id == first.to_string()));
+    }
+
+    #[test]
+    fn get_file_blame_honors_cancellation() {
+        let mut tr = TestRepo::new();
+        let oid = tr.commit_file("main", "a.txt", "one\n", "add a");
+        let cancel = Arc::new(AtomicBool::new(true));
+        let result = get_file_blame(
+            tr.path().to_str().unwrap(),
+            &oid.to_string(),
+            "a.txt",
+            Some(&cancel),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn commit_walker_filters_by_pathspec() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "config.toml", "a = 1\n", "add config");
+        tr.commit("unrelated");
+        tr.commit_file("main", "config.toml", "a = 2\n", "bump config");
+
+        let mut opts = CommitQueryOptions::for_branch("main");
+        opts.pathspecs = vec!["config.toml".to_string()];
+        let commits = collect(tr.repo(), opts);
+        assert_eq!(messages(&commits), vec!["bump config", "add config"]);
+    }
+
+    #[test]
+    fn commit_walker_follows_renames() {
+        let mut tr = TestRepo::new();
+        let mut contents = String::new();
+        for i in 0..20 {
+            contents.push_str(&format!("setting_{i} = true\n"));
+        }
+        tr.commit_file("main", "old.conf", &contents, "add old.conf");
+        tr.commit_file(
+            "main",
+            "old.conf",
+            &format!("{contents}extra = 1\n"),
+            "edit old.conf",
+        );
+
+        tr.rename_file("main", "old.conf", "new.conf", "rename");
+        tr.commit_file(
+            "main",
+            "new.conf",
+            &format!("{contents}extra = 2\n"),
+            "edit new.conf",
+        );
+
+        let mut opts = CommitQueryOptions::for_branch("main");
+        opts.pathspecs = vec!["new.conf".to_string()];
+        assert_eq!(
+            messages(&collect(tr.repo(), opts.clone())),
+            vec!["edit new.conf", "rename"]
+        );
+
+        opts.follow_renames = true;
+        assert_eq!(
+            messages(&collect(tr.repo(), opts)),
+            vec!["edit new.conf", "rename", "edit old.conf", "add old.conf"]
+        );
+    }
+
+    #[test]
+    fn working_tree_commit_only_for_dirty_checked_out_branch() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "a.txt", "one\n", "add a");
+        tr.create_branch("older", "main");
+        tr.commit_file("main", "b.txt", "b\n", "add b");
+        tr.checkout("main");
+        assert!(working_tree_commit(tr.repo(), "main").is_none());
+
+        std::fs::write(tr.path().join("a.txt"), "two\n").unwrap();
+        std::fs::write(tr.path().join("new.txt"), "new\n").unwrap();
+        assert!(working_tree_commit(tr.repo(), "older").is_none());
+        tr.lightweight_tag("v1", tr.tip("main"));
+        assert!(working_tree_commit(tr.repo(), "v1").is_none());
+        assert!(working_tree_commit(tr.repo(), "HEAD").is_some());
+
+        let row = working_tree_commit(tr.repo(), "main").expect("dirty tree row");
+        assert!(row.is_working_tree());
+        assert_eq!(row.parents, vec![tr.tip("main").to_string()]);
+        assert_eq!(row.message, "Uncommitted changes (1 unstaged, 1 untracked)");
+    }
+
+    #[test]
+    fn working_tree_diff_groups_staged_unstaged_and_ignored() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", ".gitignore", "target/\n", "ignore target");
+        tr.commit_file("main", "a.txt", "one\n", "add a");
+        tr.checkout("main");
+
+        std::fs::write(tr.path().join("a.txt"), "two\n").unwrap();
+        let mut index = tr.repo().index().unwrap();
+        index.add_path(Path::new("a.txt")).unwrap();
+        index.write().unwrap();
+        std::fs::write(tr.path().join("a.txt"), "three\n").unwrap();
+        std::fs::write(tr.path().join("notes.txt"), "todo\n").unwrap();
+        std::fs::create_dir(tr.path().join("target")).unwrap();
+        std::fs::write(tr.path().join("target/out.bin"), "bin").unwrap();
+
+        let diff = get_working_tree_diff(tr.path().to_str().unwrap(), true).unwrap();
+        let summary: Vec<(FileChangeKind, &str)> = diff
+            .files
+            .iter()
+            .map(|f| (f.kind, f.new_path.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (FileChangeKind::Modified, "a.txt"),
+                (FileChangeKind::Modified, "a.txt"),
+                (FileChangeKind::Untracked, "notes.txt"),
+                (FileChangeKind::Ignored, "target/"),
+            ]
+        );
+        assert_eq!(
+            diff.group_headings,
+            vec![
+                (0, "Staged changes".to_string()),
+                (1, "Unstaged changes".to_string()),
+                (3, "Ignored files".to_string()),
+            ]
+        );
+        assert!(diff.files[0].patch.contains("-one\n+two\n"));
+        assert!(diff.files[1].patch.contains("-two\n+three\n"));
+        assert!(diff.files[2].patch.contains("+todo\n"));
+        assert!(diff.files[3].patch.is_empty());
+    }
+
+    #[test]
+    fn working_tree_diff_leaves_out_ignored_files_unless_asked() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", ".gitignore", "target/\n", "ignore target");
+        tr.checkout("main");
+        std::fs::create_dir(tr.path().join("target")).unwrap();
+        std::fs::write(tr.path().join("target/out.bin"), "bin").unwrap();
+        std::fs::create_dir_all(tr.path().join("docs/guide")).unwrap();
+        std::fs::write(tr.path().join("docs/a.md"), "a\n").unwrap();
+        std::fs::write(tr.path().join("docs/guide/b.md"), "b\n").unwrap();
+
+        let diff = get_working_tree_diff(tr.path().to_str().unwrap(), false).unwrap();
+        let paths: Vec<&str> = diff
+            .files
+            .iter()
+            .map(|f| f.new_path.as_deref().unwrap())
+            .collect();
+        assert_eq!(paths, vec!["docs/a.md", "docs/guide/b.md"]);
+
+        // The row summary counts the same untracked files the diff lists.
+        let status = get_working_tree_status(tr.repo()).unwrap();
+        assert_eq!(status.untracked, paths.len());
+    }
+
+    #[test]
+    fn tree_entries_list_directories_first_and_read_files() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "b.txt", "bee\n", "add b");
+        tr.commit_file("main", "src/main.rs", "fn main() {}\n", "add main");
+        let sha = tr
+            .commit_file("main", "a.bin", "\0\x01", "add binary")
+            .to_string();
+        let path = tr.path().to_str().unwrap();
+
+        let root: Vec<(String, TreeEntryKind)> = get_tree_entries(path, &sha, "")
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.path, e.kind))
+            .collect();
+        assert_eq!(
+            root,
+            vec![
+                ("src".to_string(), TreeEntryKind::Directory),
+                ("a.bin".to_string(), TreeEntryKind::File),
+                ("b.txt".to_string(), TreeEntryKind::File),
+            ]
+        );
+        let src = get_tree_entries(path, &sha, "src").unwrap();
+        assert_eq!(src[0].path, "src/main.rs");
+
+        let text = get_file_content(path, &sha, "src/main.rs").unwrap();
+        assert_eq!(text.text.as_deref(), Some("fn main() {}\n"));
+        let binary = get_file_content(path, &sha, "a.bin").unwrap();
+        assert!(binary.text.is_none());
+        assert_eq!(binary.size, 2);
+    }
+
+    #[test]
+    fn merge_diff_modes_show_parents_combined_changes_and_resolutions() {
+        let numbered = |edits: &[(usize, &str)]| {
+            (1..=20)
+                .map(|n| {
+                    edits
+                        .iter()
+                        .find(|(line, _)| *line == n)
+                        .map_or(format!("line {n}\n"), |(_, text)| format!("{text}\n"))
+                })
+                .collect::<String>()
+        };
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "a.txt", &numbered(&[]), "base a");
+        tr.commit_file("main", "c.txt", "c base\n", "base c");
+        tr.create_branch("feature", "main");
+        tr.commit_file("main", "a.txt", &numbered(&[(2, "main 2")]), "main a");
+        tr.commit_file("main", "c.txt", "c main\n", "main c");
+        tr.commit_file(
+            "feature",
+            "a.txt",
+            &numbered(&[(10, "feature 10"), (18, "feature 18")]),
+            "feature a",
+        );
+        tr.commit_file("feature", "c.txt", "c feature\n", "feature c");
+
+        // Resolve c.txt by hand and also edit line 10 in the merge itself.
+        let repo = tr.repo();
+        let main_tip = repo.find_commit(tr.tip("main")).unwrap();
+        let feature_tip = repo.find_commit(tr.tip("feature")).unwrap();
+        let merged_a = numbered(&[(2, "main 2"), (10, "resolved 10"), (18, "feature 18")]);
+        let mut builder = repo.treebuilder(Some(&main_tip.tree().unwrap())).unwrap();
+        let a_blob = repo.blob(merged_a.as_bytes()).unwrap();
+        let c_blob = repo.blob(b"c resolved\n").unwrap();
+        builder.insert("a.txt", a_blob, 0o100644).unwrap();
+        builder.insert("c.txt", c_blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let sig = Signature::now("Tester", "tester@example.com").unwrap();
+        let merge_oid = repo
+            .commit(
+                Some("refs/heads/main"),
+                &sig,
+                &sig,
+                "merge feature",
+                &tree,
+                &[&main_tip, &feature_tip],
+            )
+            .unwrap()
+            .to_string();
+        let path = tr.path().to_str().unwrap();
+        let file = |diff: &CommitDiff, name: &str| {
+            diff.files
+                .iter()
+                .find(|f| f.new_path.as_deref() == Some(name))
+                .map(|f| f.patch.clone())
+        };
+
+        assert_eq!(get_parent_count(tr.path(), &merge_oid).unwrap(), 2);
+        assert_eq!(
+            get_parent_count(tr.path(), &main_tip.id().to_string()).unwrap(),
+            1
+        );
+        assert_eq!(
+            MergeDiffMode::available(2),
+            vec![
+                MergeDiffMode::Parent(0),
+                MergeDiffMode::Parent(1),
+                MergeDiffMode::Combined,
+                MergeDiffMode::Remerge
+            ]
+        );
+
+        let second = get_commit_diff(path, &merge_oid, MergeDiffMode::Parent(1)).unwrap();
+        let a = file(&second, "a.txt").unwrap();
+        assert!(a.contains("+main 2") && a.contains("+resolved 10"));
+        assert!(!a.contains("feature 18"));
+
+        // Line 18 comes unchanged from the feature side and line 2 from main;
+        // only the line 10 edit differs from both parents.
+        let combined = get_commit_diff(path, &merge_oid, MergeDiffMode::Combined).unwrap();
+        let a = file(&combined, "a.txt").unwrap();
+        assert!(a.contains("+resolved 10"), "got:\n{a}");
+        assert!(!a.contains("+feature 18"), "got:\n{a}");
+        assert!(file(&combined, "c.txt").unwrap().contains("+c resolved"));
+
+        // The automatic merge takes lines 2 and 18 itself, so only the hand
+        // edits remain; the conflict shows up as its markers being replaced.
+        let remerge = get_commit_diff(path, &merge_oid, MergeDiffMode::Remerge).unwrap();
+        let a = file(&remerge, "a.txt").unwrap();
+        assert!(a.contains("-feature 10") && a.contains("+resolved 10"));
+        assert!(!a.contains("main 2") && !a.contains("feature 18"));
+        let c = file(&remerge, "c.txt").unwrap();
+        assert!(
+            c.contains("-<<<<<<< ") && c.contains("+c resolved"),
+            "got:\n{c}"
+        );
+    }
+
+    #[test]
+    fn stash_diff_groups_staged_unstaged_and_untracked_changes() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "a.txt", "one\n", "add a");
+        tr.checkout("main");
+        let workdir = tr.path().to_path_buf();
+        std::fs::write(workdir.join("a.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(workdir.join("b.txt"), "staged\n").unwrap();
+        std::fs::write(workdir.join("c.txt"), "untracked\n").unwrap();
+        let mut index = tr.repo().index().unwrap();
+        index.add_path(Path::new("b.txt")).unwrap();
+        index.write().unwrap();
+
+        let mut repo = Repository::open(tr.path()).unwrap();
+        let sig = Signature::now("Tester", "tester@example.com").unwrap();
+        let stash_oid = repo
+            .stash_save(
+                &sig,
+                "work in progress",
+                Some(git2::StashFlags::INCLUDE_UNTRACKED),
+            )
+            .unwrap();
+
+        let stashes = get_stashes(tr.path()).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].index, 0);
+        assert_eq!(stashes[0].sha, stash_oid.to_string());
+        assert!(stashes[0].message.contains("work in progress"));
+
+        let diff = get_stash_diff(tr.path().to_str().unwrap(), &stashes[0].sha).unwrap();
+        let paths: Vec<_> = diff
+            .files
+            .iter()
+            .map(|f| f.new_path.as_deref().unwrap())
+            .collect();
+        assert_eq!(paths, vec!["b.txt", "a.txt", "c.txt"]);
+        assert_eq!(
+            diff.group_headings,
+            vec![
+                (0, "Staged changes".to_string()),
+                (1, "Unstaged changes".to_string()),
+                (2, "Untracked files".to_string()),
+            ]
+        );
+        assert!(diff.files[1].patch.contains("+two"));
+    }
+
+    #[test]
+    fn reflog_keeps_commits_a_branch_no_longer_points_to() {
+        let mut tr = TestRepo::new();
+        let first = tr.commit_file("main", "a.txt", "one\n", "first");
+        let second = tr.commit_file("main", "a.txt", "two\n", "second");
+        tr.repo()
+            .find_reference("refs/heads/main")
+            .unwrap()
+            .set_target(first, "reset: moving to first")
+            .unwrap();
+
+        let reflog = get_reflog(tr.path(), "main").unwrap();
+        assert_eq!(reflog.len(), 3);
+        assert_eq!(reflog[0].message, "reset: moving to first");
+        assert_eq!(reflog[0].old_sha, second.to_string());
+        assert_eq!(reflog[0].new_sha, first.to_string());
+        assert_eq!(reflog[1].new_sha, second.to_string());
+        assert_eq!(reflog[1].summary.as_deref(), Some("second"));
+
+        // The lost commit still diffs even though no ref reaches it.
+        let diff = get_commit_diff(
+            tr.path().to_str().unwrap(),
+            &reflog[1].new_sha,
+            MergeDiffMode::default(),
+        )
+        .unwrap();
+        assert!(diff.files[0].patch.contains("+two"));
+    }
+
+    #[test]
+    fn gpg_status_and_signed_tags_are_parsed() {
+        let good = "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 0123456789ABCDEF Tester <tester@example.com>\n[GNUPG:] TRUST_ULTIMATE 0 pgp\n";
+        assert_eq!(
+            parse_gpg_status(good),
+            (
+                SignatureStatus::Verified,
+                Some("Tester <tester@example.com>".to_string()),
+                "Good signature".to_string()
+            )
+        );
+        let untrusted = "[GNUPG:] GOODSIG 0123456789ABCDEF Tester <tester@example.com>\n[GNUPG:] TRUST_UNDEFINED 0 pgp\n";
+        assert_eq!(parse_gpg_status(untrusted).0, SignatureStatus::Unknown);
+        let never = "[GNUPG:] GOODSIG 0123456789ABCDEF Tester <tester@example.com>\n[GNUPG:] TRUST_NEVER 0 pgp\n";
+        assert_eq!(parse_gpg_status(never).0, SignatureStatus::Unknown);
+        let missing = "[GNUPG:] ERRSIG 0123456789ABCDEF 22 8 00 1700000000 9 -\n[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n";
+        assert_eq!(parse_gpg_status(missing).0, SignatureStatus::Unknown);
+        let bad = "[GNUPG:] BADSIG 0123456789ABCDEF Tester <tester@example.com>\n";
+        assert_eq!(parse_gpg_status(bad).0, SignatureStatus::Unverified);
+
+        let tag = b"object 0123\ntype commit\ntag v1\n\nrelease\n-----BEGIN SSH SIGNATURE-----\nabc\n-----END SSH SIGNATURE-----\n";
+        let (data, signature) = split_signed_tag(tag).unwrap();
+        assert!(data.ends_with(b"release\n"));
+        assert_eq!(signature_kind(signature), SignatureKind::Ssh);
+        assert!(split_signed_tag(b"object 0123\n\nunsigned\n").is_none());
+    }
+
+    #[test]
+    fn ssh_signatures_are_checked_against_allowed_signers() {
+        let ssh_keygen = |args: &[&str]| {
+            std::process::Command::new("ssh-keygen")
+                .args(args)
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false)
+        };
+        let keys = tempfile::tempdir().unwrap();
+        let key = keys.path().join("key");
+        let key_path = key.to_str().unwrap();
+        // Needs OpenSSH's ssh-keygen; nothing to check without it.
+        if !ssh_keygen(&[
+            "-q", "-t", "ed25519", "-N", "", "-C", "tester", "-f", key_path,
+        ]) {
+            return;
+        }
+
+        let mut tr = TestRepo::new();
+        let unsigned = tr.commit_file("main", "a.txt", "one\n", "unsigned");
+        let repo = tr.repo();
+        let parent = repo.find_commit(unsigned).unwrap();
+        let sig = Signature::now("Tester", "tester@example.com").unwrap();
+        let buffer = repo
+            .commit_create_buffer(&sig, &sig, "signed", &parent.tree().unwrap(), &[&parent])
+            .unwrap();
+        let content = str::from_utf8(&buffer).unwrap().to_string();
+        let payload = keys.path().join("commit");
+        std::fs::write(&payload, &content).unwrap();
+        assert!(ssh_keygen(&[
+            "-Y",
+            "sign",
+            "-f",
+            key_path,
+            "-n",
+            "git",
+            payload.to_str().unwrap()
+        ]));
+        let signature = std::fs::read_to_string(keys.path().join("commit.sig")).unwrap();
+        let signed = repo
+            .commit_signed(&content, &signature, None)
+            .unwrap()
+            .to_string();
+
+        let public_key = std::fs::read_to_string(keys.path().join("key.pub")).unwrap();
+        let allowed = keys.path().join("allowed_signers");
+        std::fs::write(&allowed, format!("tester@example.com {public_key}")).unwrap();
+        let verifier = SignatureVerifier {
+            gnupg_home: None,
+            allowed_signers: Some(allowed.clone()),
+        };
+        let path = tr.path().to_str().unwrap();
+
+        let checks = get_signatures(path, &signed, &[], &verifier).unwrap();
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].object, "Commit");
+        assert_eq!(checks[0].kind, SignatureKind::Ssh);
+        assert_eq!(checks[0].status, SignatureStatus::Verified, "{checks:?}");
+        assert_eq!(checks[0].signer.as_deref(), Some("tester@example.com"));
+
+        // A good signature from a key that is not allowed can't be vouched for.
+        std::fs::write(&allowed, "").unwrap();
+        let checks = get_signatures(path, &signed, &[], &verifier).unwrap();
+        assert_eq!(checks[0].status, SignatureStatus::Unknown, "{checks:?}");
+
+        assert!(
+            get_signatures(path, &unsigned.to_string(), &[], &verifier)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn compare_refs_lists_both_sides_and_diffs_from_the_merge_base() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "shared.txt", "shared\n", "shared");
+        tr.create_branch("release", "main");
+        tr.commit_file("main", "main.txt", "main\n", "main only");
+        tr.commit_file("release", "fix.txt", "fix\n", "release fix");
+        tr.commit_file("release", "fix.txt", "fix 2\n", "release fix 2");
+
+        let cmp = compare_refs(tr.path().to_str().unwrap(), "main", "release").unwrap();
+        assert_eq!(
+            messages(&cmp.only_in_head),
+            vec!["release fix 2", "release fix"]
+        );
+        assert_eq!(messages(&cmp.only_in_base), vec!["main only"]);
+        assert!(!cmp.truncated);
+        assert_eq!(cmp.head_sha, tr.tip("release").to_string());
+
+        let paths = |diff: &CommitDiff| -> Vec<(FileChangeKind, String)> {
+            diff.files
+                .iter()
+                .map(|f| {
+                    let path = f.new_path.clone().or_else(|| f.old_path.clone());
+                    (f.kind, path.unwrap())
+                })
+                .collect()
+        };
+        assert_eq!(
+            paths(&cmp.two_dot),
+            vec![
+                (FileChangeKind::Added, "fix.txt".to_string()),
+                (FileChangeKind::Deleted, "main.txt".to_string()),
+            ]
+        );
+        assert_eq!(
+            paths(&cmp.three_dot),
+            vec![(FileChangeKind::Added, "fix.txt".to_string())]
+        );
+    }
+
+    #[test]
+    fn branch_divergence_counts_against_upstream_and_main() {
+        let mut tr = TestRepo::new();
+        tr.commit("base");
+        tr.create_branch("feature", "main");
+        let pushed = tr.commit_on("feature", "pushed");
+        tr.commit_on("feature", "local only");
+        tr.commit("main moves on");
+
+        tr.repo()
+            .remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        tr.create_remote_ref("origin/feature", pushed);
+        let mut feature = tr
+            .repo()
+            .find_branch("feature", git2::BranchType::Local)
+            .unwrap();
+        feature.set_upstream(Some("origin/feature")).unwrap();
+
+        let divergence = get_branch_divergence(tr.path(), None).unwrap();
+        let feature = &divergence["feature"];
+        assert_eq!(
+            feature.upstream,
+            Some((
+                "origin/feature".to_string(),
+                AheadBehind {
+                    ahead: 1,
+                    behind: 0
+                }
+            ))
+        );
+        assert_eq!(
+            feature.main,
+            Some((
+                "main".to_string(),
+                AheadBehind {
+                    ahead: 2,
+                    behind: 1
+                }
+            ))
+        );
+        assert_eq!(divergence["main"], BranchDivergence::default());
+    }
 }
@@ -0,0 +1,284 @@
+//! Deep links: what to show when GitY is launched for a repository.
+//!
+//! A launch target is a repository plus, optionally, the ref to view, the
+//! commit to select and a file to reveal in that commit's diff. It comes
+//! either from the command line:
+//!
+//! ```text
+//! gity [--ref <name>] [--commit <sha>] [--file <path>] <repository>
+//! ```
+//!
+//! or from an `x-gity://open?repo=<path>&ref=<name>&commit=<sha>&file=<path>`
+//! URI (query values percent-encoded). Command-line targets are converted to
+//! such a URI and handed to `GApplication` as a file to open, so a running
+//! instance receives them like any other link.
+
+use std::path::{Path, PathBuf};
+
+/// URI scheme of GitY deep links.
+pub const URI_SCHEME: &str = "x-gity";
+
+/// `GApplication` / GTK options that take their value as the next argument.
+/// They are passed on together with that value, so it isn't mistaken for
+/// the repository. Other options are passed on alone (`--opt=value` forms
+/// included).
+const PASSTHROUGH_OPTIONS_WITH_VALUE: &[&str] = &[
+    "--display",
+    "--gapplication-app-id",
+    "--gdk-debug",
+    "--gtk-debug",
+    "--gtk-module",
+];
+
+/// Repository, ref, commit and file to open.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchTarget {
+    pub repo: PathBuf,
+    /// Branch or tag to view; the default ref when `None`.
+    pub reference: Option<String>,
+    /// Commit (SHA or prefix) to select.
+    pub commit: Option<String>,
+    /// Path, relative to the repository root, of the file to reveal.
+    pub file: Option<String>,
+}
+
+/// Command-line arguments split into GitY's launch target and the rest,
+/// which are left to `GApplication` / GTK.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LaunchArgs {
+    pub target: Option<LaunchTarget>,
+    pub passthrough: Vec<String>,
+}
+
+impl LaunchTarget {
+    /// Parse an `x-gity://open?...` URI.
+    pub fn from_uri(uri: &str) -> Result<Self, String> {
+        let rest = uri
+            .strip_prefix(URI_SCHEME)
+            .and_then(|r| r.strip_prefix("://"))
+            .ok_or_else(|| format!("not a {URI_SCHEME} link: {uri}"))?;
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        if action.trim_end_matches('/') != "open" {
+            return Err(format!("unknown {URI_SCHEME} action: {action}"));
+        }
+
+        let mut target = LaunchTarget::default();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                "repo" => target.repo = PathBuf::from(value),
+                "ref" => target.reference = Some(value),
+                "commit" => target.commit = Some(value),
+                "file" => target.file = Some(value),
+                // Unknown keys are ignored so newer links still open.
+                _ => {}
+            }
+        }
+        if target.repo.as_os_str().is_empty() {
+            return Err(format!("{URI_SCHEME} link without a repository: {uri}"));
+        }
+        Ok(target)
+    }
+
+    /// The `x-gity://open?...` URI for this target.
+    pub fn to_uri(&self) -> String {
+        let mut uri = format!(
+            "{URI_SCHEME}://open?repo={}",
+            percent_encode(&self.repo.to_string_lossy())
+        );
+        for (key, value) in [
+            ("ref", &self.reference),
+            ("commit", &self.commit),
+            ("file", &self.file),
+        ] {
+            if let Some(value) = value {
+                uri.push_str(&format!("&{key}={}", percent_encode(value)));
+            }
+        }
+        uri
+    }
+
+    /// Whether the target asks for more than the repository itself.
+    pub fn has_selection(&self) -> bool {
+        self.reference.is_some() || self.commit.is_some() || self.file.is_some()
+    }
+}
+
+/// Split `args` (without the program name) into the launch target and the
+/// arguments for `GApplication`. The first non-option argument is the
+/// repository, relative to `cwd`; a link passed as that argument is parsed
+/// as well, with the options overriding its values.
+pub fn parse_args(args: &[String], cwd: &Path) -> Result<LaunchArgs, String> {
+    let mut parsed = LaunchArgs::default();
+    let mut target: Option<LaunchTarget> = None;
+    let mut reference = None;
+    let mut commit = None;
+    let mut file = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{name} needs a value"))
+        };
+        match arg.as_str() {
+            "--ref" => reference = Some(value(arg)?),
+            "--commit" => commit = Some(value(arg)?),
+            "--file" => file = Some(value(arg)?),
+            option if PASSTHROUGH_OPTIONS_WITH_VALUE.contains(&option) => {
+                let option_value = value(arg)?;
+                parsed.passthrough.push(option.to_string());
+                parsed.passthrough.push(option_value);
+            }
+            flag if flag.starts_with('-') => parsed.passthrough.push(flag.to_string()),
+            _ if target.is_some() => return Err(format!("unexpected argument: {arg}")),
+            uri if uri.starts_with(&format!("{URI_SCHEME}:")) => {
+                target = Some(LaunchTarget::from_uri(uri)?);
+            }
+            path => {
+                target = Some(LaunchTarget {
+                    repo: cwd.join(path),
+                    ..LaunchTarget::default()
+                });
+            }
+        }
+    }
+
+    if target.is_none() && (reference.is_some() || commit.is_some() || file.is_some()) {
+        return Err("--ref, --commit and --file need a repository".to_string());
+    }
+    parsed.target = target.map(|mut target| {
+        target.reference = reference.or(target.reference);
+        target.commit = commit.or(target.commit);
+        target.file = file.or(target.file);
+        target
+    });
+    Ok(parsed)
+}
+
+/// Encode everything but unreserved characters and `/`, so paths stay
+/// readable.
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| format!("bad escape in link: {value}"))?;
+                out.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| format!("link is not UTF-8: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn uris_round_trip_with_escaped_values() {
+        let target = LaunchTarget {
+            repo: PathBuf::from("/home/me/my repo"),
+            reference: Some("feature/a&b".to_string()),
+            commit: Some("0123abc".to_string()),
+            file: Some("src/ui/100%.rs".to_string()),
+        };
+        let uri = target.to_uri();
+        assert_eq!(
+            uri,
+            "x-gity://open?repo=/home/me/my%20repo&ref=feature/a%26b&commit=0123abc&file=src/ui/100%25.rs"
+        );
+        assert_eq!(LaunchTarget::from_uri(&uri).unwrap(), target);
+
+        let plain = LaunchTarget::from_uri("x-gity://open/?repo=/r&unknown=1").unwrap();
+        assert_eq!(plain.repo, PathBuf::from("/r"));
+        assert!(!plain.has_selection());
+
+        assert!(LaunchTarget::from_uri("x-gity://open?ref=main").is_err());
+        assert!(LaunchTarget::from_uri("x-gity://delete?repo=/r").is_err());
+        assert!(LaunchTarget::from_uri("x-gity://open?repo=%zz").is_err());
+    }
+
+    #[test]
+    fn args_give_the_target_and_keep_other_options() {
+        let cwd = Path::new("/work");
+        let parsed = parse_args(
+            &args("--ref main --gapplication-service --commit abc1234 --file a.rs repo"),
+            cwd,
+        )
+        .unwrap();
+        assert_eq!(parsed.passthrough, vec!["--gapplication-service"]);
+        assert_eq!(
+            parsed.target,
+            Some(LaunchTarget {
+                repo: PathBuf::from("/work/repo"),
+                reference: Some("main".to_string()),
+                commit: Some("abc1234".to_string()),
+                file: Some("a.rs".to_string()),
+            })
+        );
+
+        let parsed =
+            parse_args(&args("x-gity://open?repo=/r&commit=abc --file b.rs"), cwd).unwrap();
+        let target = parsed.target.unwrap();
+        assert_eq!(target.commit.as_deref(), Some("abc"));
+        assert_eq!(target.file.as_deref(), Some("b.rs"));
+
+        assert_eq!(parse_args(&[], cwd).unwrap(), LaunchArgs::default());
+        assert!(parse_args(&args("--commit abc"), cwd).is_err());
+        assert!(parse_args(&args("repo --file"), cwd).is_err());
+        assert!(parse_args(&args("one two"), cwd).is_err());
+    }
+
+    #[test]
+    fn options_with_values_keep_their_values() {
+        let cwd = Path::new("/work");
+        let parsed = parse_args(
+            &args("--display :1 repo --gapplication-app-id=org.example.Test"),
+            cwd,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.passthrough,
+            vec!["--display", ":1", "--gapplication-app-id=org.example.Test"]
+        );
+        assert_eq!(parsed.target.unwrap().repo, PathBuf::from("/work/repo"));
+
+        let parsed = parse_args(&args("--gtk-debug interactive"), cwd).unwrap();
+        assert_eq!(parsed.passthrough, vec!["--gtk-debug", "interactive"]);
+        assert_eq!(parsed.target, None);
+        assert!(parse_args(&args("repo --display"), cwd).is_err());
+    }
+}
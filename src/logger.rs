@@ -1,11 +1,31 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether debug/info messages go to stderr instead of stdout.
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
 /// Logger module that only prints in debug builds
 pub struct Logger;
 
 impl Logger {
+    /// Send all messages to stderr, keeping stdout for command output
+    /// (used by the headless subcommands).
+    pub fn log_to_stderr() {
+        TO_STDERR.store(true, Ordering::Relaxed);
+    }
+
+    #[cfg(debug_assertions)]
+    fn print(line: &str) {
+        if TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
     /// Log a debug  message (only prints in debug builds)
     #[cfg(debug_assertions)]
     pub fn debug(message: &str) {
-        println!("[DEBUG] {}", message);
+        Self::print(&format!("[DEBUG] {}", message));
     }
 
     /// Log a debug message (no-op in release builds)
@@ -17,7 +37,7 @@ impl Logger {
     /// Log an info message (only prints in debug builds)
     #[cfg(debug_assertions)]
     pub fn info(message: &str) {
-        println!("[INFO] {}", message);
+        Self::print(&format!("[INFO] {}", message));
     }
 
     /// Log an info message (no-op in release builds)
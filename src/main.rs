@@ -1,7 +1,13 @@
+mod cli;
 mod git;
+mod launch;
 mod logger;
+mod releases;
 mod search;
+mod search_index;
+mod search_query;
 mod ui;
+mod util;
 mod version;
 
 #[cfg(test)]
@@ -17,6 +23,13 @@ pub const APP_ID: &str = "com.markdeepwell.GitY";
 pub const DEVELOPER_NAME: &str = "Mark Deepwell";
 
 fn main() -> glib::ExitCode {
+    // Headless subcommands (`gity log`, `gity search`) never touch GTK, so
+    // they work without a display.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::is_subcommand(&args) {
+        return glib::ExitCode::from(cli::run(&args));
+    }
+
     // Handle simple CLI flags before initializing GTK / GIO.
     // We intentionally avoid full argument parsing so we don't interfere with
     // `gio::Application` / GTK's built-in `--gapplication-*` flags.
@@ -53,18 +66,27 @@ fn main() -> glib::ExitCode {
         }
     }
 
-    // Parse command-line arguments for repository path
-    let repo_arg: Option<std::path::PathBuf> = std::env::args_os().skip(1).find_map(|a| {
-        // Ignore flags; treat the first non-flag arg as a path
-        let s = a.to_string_lossy();
-        if s.starts_with('-') {
-            None
-        } else {
-            Some(std::path::PathBuf::from(a))
+    // A repository (optionally with --ref/--commit/--file) or an x-gity://
+    // link. It is passed on to GApplication as a link to open, so an already
+    // running instance opens it; other options are left to GApplication.
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let launch_args = match launch::parse_args(&args, &cwd) {
+        Ok(launch_args) => launch_args,
+        Err(message) => {
+            eprintln!("gity: {message}\n\n{}", cli::USAGE);
+            return glib::ExitCode::from(2);
         }
-    });
+    };
+    let mut gapplication_args: Vec<String> = std::env::args()
+        .take(1)
+        .chain(launch_args.passthrough)
+        .collect();
+    gapplication_args.extend(launch_args.target.map(|target| target.to_uri()));
 
-    let app = adw::Application::builder().application_id(APP_ID).build();
+    let app = adw::Application::builder()
+        .application_id(APP_ID)
+        .flags(gio::ApplicationFlags::HANDLES_OPEN)
+        .build();
     app.connect_startup(|app| {
         let display = gdk::Display::default().expect("Could not get default display");
 
@@ -85,19 +107,31 @@ fn main() -> glib::ExitCode {
         ui::setup_app_action(app);
     });
 
-    let repo_arg_for_activate = repo_arg.clone();
-    app.connect_activate(move |app| {
-        // Only load from command-line args on the first window (initial launch)
-        let is_first_window = app.windows().is_empty();
-        ui::build_ui(
-            app,
-            if is_first_window {
-                repo_arg_for_activate.as_ref()
+    app.connect_activate(|app| {
+        ui::build_ui(app, None);
+    });
+
+    // Links and repositories, from our own command line or forwarded from
+    // another `gity` invocation.
+    app.connect_open(|app, files, _hint| {
+        for file in files {
+            let uri = file.uri();
+            let target = if uri.starts_with(launch::URI_SCHEME) {
+                launch::LaunchTarget::from_uri(&uri)
+            } else if let Some(path) = file.path() {
+                Ok(launch::LaunchTarget {
+                    repo: path,
+                    ..Default::default()
+                })
             } else {
-                None
-            },
-        );
+                Err(format!("cannot open {uri}"))
+            };
+            match target {
+                Ok(target) => ui::open_launch_target(app, &target),
+                Err(message) => logger::Logger::error(&message),
+            }
+        }
     });
 
-    app.run()
+    app.run_with_args(&gapplication_args)
 }
@@ -0,0 +1,381 @@
+//! Releases: version tags in version order, and Markdown changelogs of the
+//! commits between consecutive versions, grouped by Conventional Commit type
+//! (`feat(ui): ...`, `fix!: ...`; see <https://www.conventionalcommits.org>).
+
+use std::cmp::Ordering;
+use std::path::Path;
+
+use crate::git::{self, GitCommit, Release};
+use crate::util::natural_compare;
+
+/// Number of SHA characters shown in changelog entries.
+const SHORT_SHA_LEN: usize = 7;
+
+/// Changelog sections by Conventional Commit type, in output order. Other
+/// types, and messages that aren't Conventional Commits, go under
+/// `OTHER_SECTION`.
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("Features", &["feat"]),
+    ("Bug Fixes", &["fix"]),
+    ("Performance", &["perf"]),
+    ("Reverts", &["revert"]),
+    ("Refactoring", &["refactor"]),
+    ("Documentation", &["docs"]),
+    ("Tests", &["test"]),
+    ("Build and CI", &["build", "ci"]),
+    ("Chores", &["chore", "style"]),
+];
+/// Section listed first, for commits marked `!` or `BREAKING CHANGE:`.
+const BREAKING_SECTION: &str = "Breaking Changes";
+const OTHER_SECTION: &str = "Other Changes";
+
+/// Version of a tag such as `v1.2.3` or `2.0.0-rc.1+build.5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    /// Numeric components: `major.minor[.patch[.fourth]]`.
+    pub core: Vec<u64>,
+    /// Pre-release after `-`, e.g. `rc.1`.
+    pub pre: Option<String>,
+}
+
+/// Parse a version tag: an optional `v`, two to four dot-separated numbers,
+/// an optional `-pre-release` and optional `+build` metadata (ignored).
+/// Other tags are not versions.
+pub fn parse_version(tag: &str) -> Option<Version> {
+    let rest = tag.strip_prefix(['v', 'V']).unwrap_or(tag);
+    let rest = rest.split_once('+').map_or(rest, |(version, _)| version);
+    let (core, pre) = match rest.split_once('-') {
+        Some((core, pre)) if !pre.is_empty() => (core, Some(pre.to_string())),
+        Some(_) => return None,
+        None => (rest, None),
+    };
+    let core: Vec<u64> = core
+        .split('.')
+        .map(|part| {
+            if !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) {
+                part.parse().ok()
+            } else {
+                None
+            }
+        })
+        .collect::<Option<_>>()?;
+    if !(2..=4).contains(&core.len()) {
+        return None;
+    }
+    Some(Version { core, pre })
+}
+
+impl Ord for Version {
+    /// Semver order: numeric components (missing ones count as 0), then a
+    /// pre-release before the release itself, pre-releases compared
+    /// naturally (`rc.2` < `rc.10`).
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.core.len().max(other.core.len());
+        let component = |v: &Version, i: usize| v.core.get(i).copied().unwrap_or(0);
+        (0..len)
+            .map(|i| component(self, i).cmp(&component(other, i)))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => natural_compare(a, b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Order tags by version, versions after other tags; ties (`v1.0` and
+/// `1.0.0`) and non-versions by name.
+pub fn compare_version_tags(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(va), Some(vb)) => va.cmp(&vb).then_with(|| natural_compare(a, b)),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => natural_compare(a, b),
+    }
+}
+
+/// Version tags of the repository at `path`, highest version first, each
+/// with the commits since the next lower version.
+pub fn load_releases(path: &Path) -> Result<Vec<Release>, git2::Error> {
+    let mut tags: Vec<String> = git::get_tag_list(path)?
+        .into_iter()
+        .map(|tag| tag.name)
+        .filter(|name| parse_version(name).is_some())
+        .collect();
+    tags.sort_by(|a, b| compare_version_tags(b, a));
+    git::get_releases(path, &tags)
+}
+
+/// A commit message parsed as a Conventional Commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalCommit<'a> {
+    /// Type, e.g. `feat` or `fix`, as written.
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    /// Marked with `!` or a `BREAKING CHANGE:` footer.
+    pub breaking: bool,
+    pub description: &'a str,
+}
+
+/// Parse `type(scope)!: description` from the first line of `message`.
+pub fn parse_conventional(message: &str) -> Option<ConventionalCommit<'_>> {
+    let summary = message.lines().next()?;
+    let (header, description) = summary.split_once(": ")?;
+    let description = description.trim();
+    let kind_len = header
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(header.len());
+    let (kind, rest) = header.split_at(kind_len);
+    let (rest, bang) = match rest.strip_suffix('!') {
+        Some(rest) => (rest, true),
+        None => (rest, false),
+    };
+    let scope = match rest {
+        "" => None,
+        _ => Some(
+            rest.strip_prefix('(')?
+                .strip_suffix(')')
+                .filter(|s| !s.is_empty())?,
+        ),
+    };
+    if kind.is_empty() || description.is_empty() {
+        return None;
+    }
+    let breaking = bang
+        || message.lines().skip(1).any(|line| {
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        });
+    Some(ConventionalCommit {
+        kind,
+        scope,
+        breaking,
+        description,
+    })
+}
+
+/// Changelog entries of `commits` by section, sections in output order and
+/// empty ones left out. Merge commits are skipped.
+pub fn group_commits(commits: &[GitCommit]) -> Vec<(&'static str, Vec<String>)> {
+    let mut sections: Vec<(&'static str, Vec<String>)> = std::iter::once(BREAKING_SECTION)
+        .chain(SECTIONS.iter().map(|(title, _)| *title))
+        .chain(std::iter::once(OTHER_SECTION))
+        .map(|title| (title, Vec::new()))
+        .collect();
+    for commit in commits.iter().filter(|c| c.parents.len() <= 1) {
+        let sha = commit.id.get(..SHORT_SHA_LEN).unwrap_or(&commit.id);
+        let (title, text) = match parse_conventional(&commit.message) {
+            Some(cc) => {
+                let title = if cc.breaking {
+                    BREAKING_SECTION
+                } else {
+                    SECTIONS
+                        .iter()
+                        .find(|(_, kinds)| kinds.iter().any(|k| k.eq_ignore_ascii_case(cc.kind)))
+                        .map_or(OTHER_SECTION, |(title, _)| *title)
+                };
+                let text = match cc.scope {
+                    Some(scope) => format!("**{scope}:** {}", cc.description),
+                    None => cc.description.to_string(),
+                };
+                (title, text)
+            }
+            None => (
+                OTHER_SECTION,
+                commit
+                    .message
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+            ),
+        };
+        if let Some((_, entries)) = sections.iter_mut().find(|(t, _)| *t == title) {
+            entries.push(format!("{text} ({sha})"));
+        }
+    }
+    sections.retain(|(_, entries)| !entries.is_empty());
+    sections
+}
+
+/// Markdown changelog of `releases`, in the given (highest first) order:
+/// per release its date, tag message and grouped commits.
+pub fn changelog_markdown(releases: &[Release]) -> String {
+    let mut out = String::from("# Changelog\n");
+    for release in releases {
+        out.push_str(&format!(
+            "\n## {} ({})\n",
+            release.tag,
+            release.time.format("%Y-%m-%d")
+        ));
+        if let Some(message) = &release.message {
+            out.push_str(&format!("\n{message}\n"));
+        }
+        for (title, entries) in group_commits(&release.commits) {
+            out.push_str(&format!("\n### {title}\n\n"));
+            for entry in entries {
+                out.push_str(&format!("- {entry}\n"));
+            }
+        }
+        if release.truncated {
+            out.push_str("\nOlder commits are not listed.\n");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+    use chrono::{TimeZone, Utc};
+
+    fn commit(id: &str, message: &str, parents: usize) -> GitCommit {
+        GitCommit {
+            id: id.to_string(),
+            author: "Tester".to_string(),
+            message: message.to_string(),
+            date: String::new(),
+            parents: vec!["p".to_string(); parents],
+        }
+    }
+
+    fn messages(commits: &[GitCommit]) -> Vec<String> {
+        commits
+            .iter()
+            .map(|c| c.message.trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn version_tags_sort_semver_aware() {
+        let mut tags = vec![
+            "v1.10.0",
+            "v1.2.0",
+            "v2.0.0",
+            "v2.0.0-rc.10",
+            "v2.0.0-rc.2",
+            "1.2",
+            "v1.2.0-beta",
+            "nightly",
+        ];
+        tags.sort_by(|a, b| compare_version_tags(a, b));
+        assert_eq!(
+            tags,
+            [
+                "nightly",
+                "v1.2.0-beta",
+                "1.2",
+                "v1.2.0",
+                "v1.10.0",
+                "v2.0.0-rc.2",
+                "v2.0.0-rc.10",
+                "v2.0.0",
+            ]
+        );
+
+        assert_eq!(
+            parse_version("v3.1.4+build.7"),
+            Some(Version {
+                core: vec![3, 1, 4],
+                pre: None
+            })
+        );
+        for not_a_version in ["nightly", "v1", "release-1.0", "1.0-", "1..2", "v1.x"] {
+            assert_eq!(parse_version(not_a_version), None, "{not_a_version}");
+        }
+    }
+
+    #[test]
+    fn releases_are_version_ordered_with_the_commits_since_the_previous_one() {
+        let mut tr = TestRepo::new();
+        let first = tr.commit("feat: first");
+        tr.lightweight_tag("v1.0.0", first);
+        tr.commit("fix: second");
+        let third = tr.commit("feat: third");
+        tr.annotated_tag("v1.10.0", third, "Big release\n");
+        let fourth = tr.commit("fix: fourth");
+        tr.lightweight_tag("v1.2.0", fourth);
+        tr.lightweight_tag("nightly", fourth);
+
+        let releases = load_releases(tr.path()).unwrap();
+        let tags: Vec<&str> = releases.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, vec!["v1.10.0", "v1.2.0", "v1.0.0"]);
+
+        let big = &releases[0];
+        assert_eq!(big.previous_tag.as_deref(), Some("v1.2.0"));
+        assert_eq!(big.message.as_deref(), Some("Big release"));
+        assert_eq!(big.tagger.as_deref(), Some("Tester <tester@example.com>"));
+        // v1.2.0 is newer in history but a lower version: nothing in between.
+        assert!(big.commits.is_empty());
+
+        let middle = &releases[1];
+        assert_eq!(middle.sha, fourth.to_string());
+        assert_eq!(middle.message, None);
+        assert_eq!(
+            messages(&middle.commits),
+            vec!["fix: fourth", "feat: third", "fix: second"]
+        );
+        assert_eq!(messages(&releases[2].commits), vec!["feat: first"]);
+        assert_eq!(releases[2].previous_tag, None);
+    }
+
+    #[test]
+    fn conventional_commits_give_type_scope_and_breaking() {
+        assert_eq!(
+            parse_conventional("feat(ui)!: New sidebar\n\nDetails"),
+            Some(ConventionalCommit {
+                kind: "feat",
+                scope: Some("ui"),
+                breaking: true,
+                description: "New sidebar",
+            })
+        );
+        let fix = parse_conventional("fix: Crash\n\nBREAKING CHANGE: config moved").unwrap();
+        assert_eq!((fix.kind, fix.scope, fix.breaking), ("fix", None, true));
+        assert_eq!(parse_conventional("Fix crash on start"), None);
+        assert_eq!(parse_conventional("Merge branch 'a': b"), None);
+        assert_eq!(parse_conventional("feat(): empty scope"), None);
+        assert_eq!(parse_conventional("see http://example.com"), None);
+    }
+
+    #[test]
+    fn changelog_groups_commits_by_type_per_release() {
+        let release = Release {
+            tag: "v1.1.0".to_string(),
+            sha: "f".repeat(40),
+            time: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            tagger: Some("Tester <tester@example.com>".to_string()),
+            message: Some("Spring release".to_string()),
+            previous_tag: Some("v1.0.0".to_string()),
+            commits: vec![
+                commit("aaaaaaaaaa", "Merge branch 'topic'", 2),
+                commit("bbbbbbbbbb", "fix(diff): Wrap long lines", 1),
+                commit("cccccccccc", "Update README", 1),
+                commit("dddddddddd", "feat!: Drop the old config", 1),
+                commit("eeeeeeeeee", "feat: Releases view", 1),
+                commit("ffffffffff", "ci: Cache cargo", 1),
+            ],
+            truncated: false,
+        };
+        assert_eq!(
+            changelog_markdown(&[release]),
+            "# Changelog\n\
+             \n## v1.1.0 (2024-05-01)\n\
+             \nSpring release\n\
+             \n### Breaking Changes\n\n- Drop the old config (ddddddd)\n\
+             \n### Features\n\n- Releases view (eeeeeee)\n\
+             \n### Bug Fixes\n\n- **diff:** Wrap long lines (bbbbbbb)\n\
+             \n### Build and CI\n\n- Cache cargo (fffffff)\n\
+             \n### Other Changes\n\n- Update README (ccccccc)\n"
+        );
+    }
+}
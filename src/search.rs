@@ -1,8 +1,12 @@
 use crate::git;
 use crate::logger::Logger;
+use crate::search_index::{self, CommitIndex, IndexedCommit};
+use crate::search_query::{QueryFilter, SearchQuery};
 use git2::{ObjectType, Oid, Repository};
 use gtk::{gio, glib, prelude::*};
-use std::path::PathBuf;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 use std::time::Duration;
@@ -14,6 +18,11 @@ pub type SearchState = Arc<Mutex<(String, Vec<u32>, usize)>>;
 struct OidIndexKey {
     repo_path: PathBuf,
     branch_ref: String,
+    /// Commit `branch_ref` resolved to, so the index follows a moved ref.
+    /// `None` when it isn't a single commit (e.g. a range).
+    tip: Option<Oid>,
+    /// History-mode path filter; the index then follows the filtered list.
+    path_filter: Option<String>,
 }
 
 #[derive(Debug)]
@@ -21,13 +30,18 @@ struct OidIndexCacheState {
     key: Option<OidIndexKey>,
     generation: u64,
     building: bool,
-    oids: Option<Arc<Vec<Oid>>>,
+    index: Option<Arc<CommitIndex>>,
+    /// Last unfiltered index, the starting point for incremental updates and
+    /// for path-filtered lists.
+    base: Option<Arc<CommitIndex>>,
 }
 
 #[derive(Debug)]
 struct OidIndexCache {
     state: Mutex<OidIndexCacheState>,
     ready: Condvar,
+    /// Where indices are persisted; `None` keeps them in memory only.
+    cache_dir: Option<PathBuf>,
 }
 
 impl OidIndexCache {
@@ -37,17 +51,36 @@ impl OidIndexCache {
                 key: None,
                 generation: 0,
                 building: false,
-                oids: None,
+                index: None,
+                base: None,
             }),
             ready: Condvar::new(),
+            // Tests must not write to the user's cache.
+            cache_dir: if cfg!(test) {
+                None
+            } else {
+                search_index::default_cache_dir()
+            },
         }
     }
 
+    /// The commit `branch_ref` points at, if it names a single commit.
+    fn resolve_tip(repo_path: &PathBuf, branch_ref: &str) -> Option<Oid> {
+        let repo = Repository::open(repo_path).ok()?;
+        let revspec = repo.revparse(branch_ref).ok()?;
+        if !revspec.mode().contains(git2::RevparseMode::SINGLE) {
+            return None;
+        }
+        Some(revspec.from()?.peel_to_commit().ok()?.id())
+    }
+
+    /// Revwalk for refs that don't resolve to a single tip; these are not
+    /// persisted.
     fn build_oids_in_revwalk_order(
+        repo: &Repository,
         key: &OidIndexKey,
         cancel: Option<&Arc<AtomicBool>>,
     ) -> Result<Vec<Oid>, git2::Error> {
-        let repo = Repository::open(&key.repo_path)?;
         let opts = git::CommitQueryOptions::for_branch(&key.branch_ref);
 
         let mut revwalk = repo.revwalk()?;
@@ -89,15 +122,84 @@ impl OidIndexCache {
         Ok(out)
     }
 
+    /// Index for `key`, together with the unfiltered index it was derived
+    /// from (if any).
+    fn build_index(
+        key: &OidIndexKey,
+        base: Option<Arc<CommitIndex>>,
+        cache_dir: Option<&Path>,
+        cancel: Option<&Arc<AtomicBool>>,
+    ) -> Result<(Arc<CommitIndex>, Option<Arc<CommitIndex>>), git2::Error> {
+        let repo = Repository::open(&key.repo_path)?;
+        let base = match key.tip {
+            Some(tip) => Some(CommitIndex::load_or_build(
+                &repo,
+                &key.repo_path,
+                &key.branch_ref,
+                tip,
+                base,
+                cache_dir,
+                cancel,
+            )?),
+            None => None,
+        };
+
+        let oids = match (&key.path_filter, &base) {
+            (None, Some(base)) => return Ok((base.clone(), Some(base.clone()))),
+            (None, None) => Self::build_oids_in_revwalk_order(&repo, key, cancel)?,
+            // A path filter needs per-commit diffs, so defer to the same walker the
+            // commit list uses to keep indices aligned with the filtered list.
+            (Some(path), _) => {
+                let mut opts = git::CommitQueryOptions::for_branch(&key.branch_ref);
+                opts.pathspecs = vec![path.clone()];
+                opts.follow_renames = true;
+                let mut walker = git::CommitWalker::new(&repo, opts)?;
+                let mut out: Vec<Oid> = Vec::new();
+                while let Some(commit) = walker.next(cancel) {
+                    out.push(Oid::from_str(&commit?.id)?);
+                }
+                out
+            }
+        };
+
+        // Reuse the unfiltered index's commit data where possible.
+        let positions: HashMap<Oid, usize> = base
+            .iter()
+            .flat_map(|b| b.oids.iter().enumerate().map(|(i, oid)| (*oid, i)))
+            .collect();
+        let mut commits = Vec::with_capacity(oids.len());
+        for oid in &oids {
+            if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return Err(git2::Error::from_str("Cancelled"));
+            }
+            commits.push(match (&base, positions.get(oid)) {
+                (Some(base), Some(&i)) => base.commits[i].clone(),
+                _ => IndexedCommit::from_commit(&repo.find_commit(*oid)?),
+            });
+        }
+
+        let index = CommitIndex {
+            repo_path: key.repo_path.clone(),
+            branch_ref: key.branch_ref.clone(),
+            tip: None,
+            oids,
+            commits,
+        };
+        Ok((Arc::new(index), base))
+    }
+
     fn get_or_build(
         &self,
         repo_path: &PathBuf,
         branch_ref: &str,
+        path_filter: Option<String>,
         cancel: Option<&Arc<AtomicBool>>,
-    ) -> Result<Arc<Vec<Oid>>, String> {
+    ) -> Result<Arc<CommitIndex>, String> {
         let wanted = OidIndexKey {
             repo_path: repo_path.clone(),
             branch_ref: branch_ref.to_string(),
+            tip: Self::resolve_tip(repo_path, branch_ref),
+            path_filter,
         };
 
         loop {
@@ -107,16 +209,16 @@ impl OidIndexCache {
 
             let mut st = self.state.lock().unwrap();
 
-            // Invalidate cache if repo/branch changed.
+            // Invalidate cache if repo/branch/tip changed.
             if st.key.as_ref() != Some(&wanted) {
                 st.key = Some(wanted.clone());
-                st.oids = None;
+                st.index = None;
                 st.building = false;
                 st.generation = st.generation.wrapping_add(1);
             }
 
-            if let Some(oids) = st.oids.clone() {
-                return Ok(oids);
+            if let Some(index) = st.index.clone() {
+                return Ok(index);
             }
 
             if st.building {
@@ -132,6 +234,7 @@ impl OidIndexCache {
             st.building = true;
             let build_gen = st.generation;
             let key = st.key.clone().unwrap();
+            let base = st.base.clone();
             drop(st);
 
             let started_at = std::time::Instant::now();
@@ -141,19 +244,22 @@ impl OidIndexCache {
                 key.branch_ref
             ));
 
-            let built = Self::build_oids_in_revwalk_order(&key, cancel);
+            let built = Self::build_index(&key, base, self.cache_dir.as_deref(), cancel);
 
             let elapsed_ms = started_at.elapsed().as_millis();
             let mut st = self.state.lock().unwrap();
             if st.generation == build_gen && st.key.as_ref() == Some(&key) {
                 match built {
-                    Ok(oids) => {
+                    Ok((index, base)) => {
                         Logger::info(&format!(
                             "Built commit OID index: {} commits - {}ms",
-                            oids.len(),
+                            index.oids.len(),
                             elapsed_ms
                         ));
-                        st.oids = Some(Arc::new(oids));
+                        st.index = Some(index);
+                        if base.is_some() {
+                            st.base = base;
+                        }
                     }
                     Err(e) => {
                         let msg = e.message().to_string();
@@ -176,8 +282,8 @@ impl OidIndexCache {
             st.building = false;
             self.ready.notify_all();
 
-            if let Some(oids) = st.oids.clone() {
-                return Ok(oids);
+            if let Some(index) = st.index.clone() {
+                return Ok(index);
             }
             // If the key/generation changed mid-build, loop and try again.
         }
@@ -199,13 +305,22 @@ pub struct SearchResult {
 pub struct SearchHandler {
     pub state: SearchState,
     oid_cache: Arc<OidIndexCache>,
+    /// Path filter of the commit list being searched (history mode), shared
+    /// with the window state so indices match the rows on screen.
+    path_filter: Arc<Mutex<Option<String>>>,
 }
 
 impl SearchHandler {
     pub fn new() -> Self {
+        Self::with_path_filter(Arc::new(Mutex::new(None)))
+    }
+
+    /// Create a handler that searches the list filtered by `path_filter`.
+    pub fn with_path_filter(path_filter: Arc<Mutex<Option<String>>>) -> Self {
         Self {
             state: Arc::new(Mutex::new((String::new(), Vec::new(), 0))),
             oid_cache: Arc::new(OidIndexCache::new()),
+            path_filter,
         }
     }
 
@@ -237,13 +352,20 @@ impl SearchHandler {
             return Ok(Vec::new());
         }
 
+        // Parse up front so syntax errors are reported before any history walk.
+        let parsed = SearchQuery::parse(query)?;
+        if parsed.is_empty() {
+            return Ok(Vec::new());
+        }
+
         if cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) {
             return Err("Cancelled".to_string());
         }
 
-        let oids = self
+        let path_filter = self.path_filter.lock().unwrap().clone();
+        let index = self
             .oid_cache
-            .get_or_build(path, branch_ref, cancel.as_ref())?;
+            .get_or_build(path, branch_ref, path_filter, cancel.as_ref())?;
 
         // If the query looks like a plausible SHA prefix (7–40 hex chars), try SHA matching
         // first. If we find *any* SHA matches, return only those indices and skip text search.
@@ -253,8 +375,12 @@ impl SearchHandler {
         };
         if should_try_sha {
             let started_at = std::time::Instant::now();
-            let sha_matches =
-                find_sha_prefix_matches(&oids, &query_lower, cancel.as_ref(), progress.as_ref())?;
+            let sha_matches = find_sha_prefix_matches(
+                &index.oids,
+                &query_lower,
+                cancel.as_ref(),
+                progress.as_ref(),
+            )?;
             if !sha_matches.is_empty() {
                 Logger::info(&format!(
                     "SHA search hit: prefix \"{}\" - {} matches - {}ms",
@@ -266,13 +392,12 @@ impl SearchHandler {
             }
         }
 
-        // Full-text search fallback (full commit message, case-insensitive).
+        // Query search fallback: message text, regexes, field and content filters.
         let started_at = std::time::Instant::now();
         let matches = find_text_matches_parallel(
             path,
-            oids,
-            query,
-            &query_lower,
+            index,
+            Arc::new(parsed),
             cancel.as_ref(),
             progress.as_ref(),
         )?;
@@ -451,9 +576,10 @@ impl SearchHandler {
         //
         // IMPORTANT: callers must ensure the target index is already present in the store.
         if let Some(&match_index) = matching_indices.get(0) {
-            if match_index < store.n_items() {
-                selection_model.select_item(match_index, true);
-                Self::scroll_to_item(&scrolled_window, &selection_model, match_index);
+            let row = crate::ui::commit_row(&store, match_index);
+            if row < store.n_items() {
+                selection_model.select_item(row, true);
+                Self::scroll_to_item(&scrolled_window, &selection_model, row);
             }
         }
 
@@ -598,41 +724,177 @@ fn find_sha_prefix_matches(
     Ok(out)
 }
 
-fn contains_ascii_case_insensitive(haystack: &[u8], needle_lower: &[u8]) -> bool {
-    if needle_lower.is_empty() {
-        return true;
+/// Whether `commit` changes `path` (a file or folder) relative to its first
+/// parent. Root commits match when they contain the path.
+fn commit_touches_path(commit: &git2::Commit, path: &str) -> Result<bool, git2::Error> {
+    let entry_id = |tree: &git2::Tree| tree.get_path(Path::new(path)).ok().map(|e| e.id());
+    let ours = entry_id(&commit.tree()?);
+    if commit.parent_count() == 0 {
+        return Ok(ours.is_some());
+    }
+    let theirs = entry_id(&commit.parent(0)?.tree()?);
+    Ok(ours != theirs)
+}
+
+/// Diff of `commit` against its first parent (or the empty tree for a root
+/// commit), without context lines.
+fn first_parent_diff<'r>(
+    repo: &'r Repository,
+    commit: &git2::Commit,
+) -> Result<git2::Diff<'r>, git2::Error> {
+    let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(0);
+    repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))
+}
+
+/// Non-overlapping occurrences of `needle` in `haystack`.
+fn count_occurrences(haystack: &[u8], needle: &[u8]) -> usize {
+    if needle.is_empty() {
+        return 0;
     }
-    if haystack.len() < needle_lower.len() {
-        return false;
+    let mut count = 0;
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if &haystack[i..i + needle.len()] == needle {
+            count += 1;
+            i += needle.len();
+        } else {
+            i += 1;
+        }
     }
+    count
+}
 
-    let first = needle_lower[0];
-    let first_upper = first.to_ascii_uppercase();
+/// Pickaxe check (`git log -S`): whether any text file in `diff` has a
+/// different number of occurrences of `needle` before and after.
+fn diff_changes_occurrences(
+    repo: &Repository,
+    diff: &git2::Diff,
+    needle: &str,
+) -> Result<bool, git2::Error> {
+    // `None` for files whose content can't be searched (binary, submodules).
+    let count = |file: git2::DiffFile| -> Result<Option<usize>, git2::Error> {
+        if file.id().is_zero() {
+            return Ok(Some(0));
+        }
+        if file.mode() == git2::FileMode::Commit {
+            return Ok(None);
+        }
+        let blob = repo.find_blob(file.id())?;
+        if blob.is_binary() {
+            return Ok(None);
+        }
+        Ok(Some(count_occurrences(blob.content(), needle.as_bytes())))
+    };
 
-    'outer: for i in 0..=(haystack.len() - needle_lower.len()) {
-        let b0 = haystack[i];
-        if b0 != first && b0 != first_upper {
-            continue;
+    for delta in diff.deltas() {
+        if let (Some(old), Some(new)) = (count(delta.old_file())?, count(delta.new_file())?)
+            && old != new
+        {
+            return Ok(true);
         }
-        for j in 1..needle_lower.len() {
-            if haystack[i + j].to_ascii_lowercase() != needle_lower[j] {
-                continue 'outer;
+    }
+    Ok(false)
+}
+
+/// Whether any added or removed line in `diff` matches `re` (`git log -G`).
+fn diff_has_line_matching(diff: &git2::Diff, re: &Regex) -> Result<bool, git2::Error> {
+    let mut found = false;
+    let result = diff.foreach(
+        &mut |_, _| true,
+        None,
+        None,
+        Some(&mut |_, _, line| {
+            if matches!(line.origin(), '+' | '-') {
+                let text = String::from_utf8_lossy(line.content());
+                if re.is_match(text.trim_end_matches('\n')) {
+                    found = true;
+                    // Stop iterating; `foreach` then reports a user abort.
+                    return false;
+                }
+            }
+            true
+        }),
+    );
+    match result {
+        Err(_) if found => Ok(true),
+        Err(e) => Err(e),
+        Ok(()) => Ok(found),
+    }
+}
+
+/// Fill `slot` on first use.
+fn get_or_load<T>(
+    slot: &mut Option<T>,
+    load: impl FnOnce() -> Result<T, git2::Error>,
+) -> Result<&T, git2::Error> {
+    if slot.is_none() {
+        *slot = Some(load()?);
+    }
+    Ok(slot.as_ref().unwrap())
+}
+
+/// Whether the commit `oid` satisfies every term of `query`.
+///
+/// Most terms are answered from the indexed data. Path and content terms
+/// read the commit from `repo`; they run after the cheap ones, and the diff
+/// is computed at most once per commit.
+fn commit_matches_query(
+    repo: &Repository,
+    oid: Oid,
+    indexed: &IndexedCommit,
+    query: &SearchQuery,
+) -> Result<bool, git2::Error> {
+    let mut commit: Option<git2::Commit> = None;
+    let mut diff: Option<git2::Diff> = None;
+
+    let cheap = query.terms.iter().filter(|t| !t.filter.needs_diff());
+    let costly = query.terms.iter().filter(|t| t.filter.needs_diff());
+    for term in cheap.chain(costly) {
+        let matched = match &term.filter {
+            QueryFilter::Text(needle) => indexed.message.contains(needle.as_str()),
+            QueryFilter::Regex(re) => re.is_match(&indexed.message),
+            QueryFilter::Author(needle) => indexed.author.contains(needle.as_str()),
+            QueryFilter::Committer(needle) => indexed.committer.contains(needle.as_str()),
+            QueryFilter::Before(t) => indexed.time < *t,
+            QueryFilter::After(t) => indexed.time >= *t,
+            QueryFilter::Merge => indexed.parent_count > 1,
+            QueryFilter::Path(path) => {
+                let commit = get_or_load(&mut commit, || repo.find_commit(oid))?;
+                commit_touches_path(commit, path)?
+            }
+            QueryFilter::Pickaxe(needle) => {
+                let commit = get_or_load(&mut commit, || repo.find_commit(oid))?;
+                let diff = get_or_load(&mut diff, || first_parent_diff(repo, commit))?;
+                diff_changes_occurrences(repo, diff, needle)?
             }
+            QueryFilter::DiffRegex(re) => {
+                let commit = get_or_load(&mut commit, || repo.find_commit(oid))?;
+                let diff = get_or_load(&mut diff, || first_parent_diff(repo, commit))?;
+                diff_has_line_matching(diff, re)?
+            }
+        };
+        if matched == term.negated {
+            return Ok(false);
         }
-        return true;
     }
-    false
+    Ok(true)
 }
 
 fn find_text_matches_parallel(
     repo_path: &PathBuf,
-    oids: Arc<Vec<Oid>>,
-    query: &str,
-    query_lower: &str,
+    index: Arc<CommitIndex>,
+    query: Arc<SearchQuery>,
     cancel: Option<&Arc<AtomicBool>>,
     progress: Option<&Arc<AtomicUsize>>,
 ) -> Result<Vec<u32>, String> {
-    let total = oids.len();
+    let total = index.oids.len();
     if total == 0 {
         return Ok(Vec::new());
     }
@@ -644,18 +906,6 @@ fn find_text_matches_parallel(
     let max_workers = (total + min_chunk - 1) / min_chunk;
     let workers = available.max(1).min(max_workers.max(1));
 
-    let needle_lower_ascii: Option<Vec<u8>> = if query.is_ascii() {
-        Some(
-            query
-                .as_bytes()
-                .iter()
-                .map(|b| b.to_ascii_lowercase())
-                .collect(),
-        )
-    } else {
-        None
-    };
-
     let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<u32>, String>>();
     let chunk_size = (total + workers - 1) / workers;
 
@@ -667,11 +917,10 @@ fn find_text_matches_parallel(
         let end = ((worker_idx + 1) * chunk_size).min(total);
         let tx = tx.clone();
         let repo_path = repo_path.clone();
-        let oids = oids.clone();
+        let index = index.clone();
         let cancel = cancel.cloned();
         let progress = progress.cloned();
-        let query_lower = query_lower.to_string();
-        let needle_lower_ascii = needle_lower_ascii.clone();
+        let query = query.clone();
 
         std::thread::spawn(move || {
             let repo = match Repository::open(&repo_path) {
@@ -688,22 +937,14 @@ fn find_text_matches_parallel(
                     let _ = tx.send(Err("Cancelled".to_string()));
                     return;
                 }
-                let oid = oids[i];
-                let commit = match repo.find_commit(oid) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        let _ = tx.send(Err(e.to_string()));
-                        return;
-                    }
-                };
-
-                let msg_bytes = commit.message_bytes();
-                let matched = if let Some(ref needle_lower) = needle_lower_ascii {
-                    contains_ascii_case_insensitive(msg_bytes, needle_lower)
-                } else {
-                    let msg = String::from_utf8_lossy(msg_bytes);
-                    msg.to_lowercase().contains(&query_lower)
-                };
+                let matched =
+                    match commit_matches_query(&repo, index.oids[i], &index.commits[i], &query) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            let _ = tx.send(Err(e.to_string()));
+                            return;
+                        }
+                    };
 
                 if matched {
                     out.push(i as u32);
@@ -734,22 +975,6 @@ mod tests {
     use crate::test_support::TestRepo;
     use git2::Oid;
 
-    #[test]
-    fn case_insensitive_contains_matches_regardless_of_case() {
-        assert!(contains_ascii_case_insensitive(b"Hello World", b"world"));
-        assert!(contains_ascii_case_insensitive(b"HELLO", b"hello"));
-        assert!(!contains_ascii_case_insensitive(b"Hello World", b"xyz"));
-    }
-
-    #[test]
-    fn case_insensitive_contains_edge_cases() {
-        // Empty needle always matches.
-        assert!(contains_ascii_case_insensitive(b"", b""));
-        assert!(contains_ascii_case_insensitive(b"anything", b""));
-        // Needle longer than haystack never matches.
-        assert!(!contains_ascii_case_insensitive(b"ab", b"abc"));
-    }
-
     #[test]
     fn parse_hex_prefix_even_and_odd() {
         let (bytes, odd) = parse_hex_prefix("abcd").unwrap();
@@ -833,6 +1058,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn text_search_matches_regardless_of_case() {
+        let mut tr = TestRepo::new();
+        tr.commit("Hello World");
+        tr.commit("HELLO");
+        tr.commit("Änderung am Parser");
+
+        let handler = SearchHandler::new();
+        let path = tr.path().to_path_buf();
+        let search = |query: &str| {
+            handler
+                .find_matching_indices_in_repo(&path, "main", query)
+                .unwrap()
+        };
+
+        assert_eq!(search("world"), vec![2]);
+        assert_eq!(search("WORLD"), vec![2]);
+        assert_eq!(search("hello"), vec![1, 2]);
+        assert_eq!(search("änderung"), vec![0]);
+        assert!(search("xyz").is_empty());
+    }
+
+    #[test]
+    fn text_search_case_edge_cases() {
+        let mut tr = TestRepo::new();
+        tr.commit("ab");
+
+        let handler = SearchHandler::new();
+        let path = tr.path().to_path_buf();
+        let search = |query: &str| {
+            handler
+                .find_matching_indices_in_repo(&path, "main", query)
+                .unwrap()
+        };
+
+        // A needle longer than the message never matches.
+        assert!(search("abc").is_empty());
+        // The whole message matches in any case.
+        assert_eq!(search("AB"), vec![0]);
+        // Whitespace-only queries match nothing, like the empty query.
+        assert!(search("   ").is_empty());
+    }
+
     #[test]
     fn find_matching_indices_sha_prefix() {
         let mut tr = TestRepo::new();
@@ -945,4 +1213,127 @@ mod tests {
             .unwrap();
         assert_eq!(on_feature.len(), 5);
     }
+
+    #[test]
+    fn path_filter_indices_follow_the_filtered_list() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "app.conf", "a\n", "fix: add config");
+        tr.commit("fix: unrelated");
+        tr.commit_file("main", "app.conf", "b\n", "fix: tweak config");
+
+        let filter = Arc::new(Mutex::new(None));
+        let handler = SearchHandler::with_path_filter(filter.clone());
+        let path = tr.path().to_path_buf();
+
+        assert_eq!(
+            handler
+                .find_matching_indices_in_repo(&path, "main", "fix")
+                .unwrap(),
+            vec![0, 1, 2]
+        );
+
+        *filter.lock().unwrap() = Some("app.conf".to_string());
+        assert_eq!(
+            handler
+                .find_matching_indices_in_repo(&path, "main", "fix")
+                .unwrap(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn query_filters_combine_fields_regex_and_negation() {
+        let mut tr = TestRepo::new();
+        tr.commit_by("main", "Add parser", "Alice", "alice@example.com");
+        tr.commit_file(
+            "main",
+            "src/ui/view.rs",
+            "a\n",
+            "Tweak view\n\nCo-authored-by: Carol <carol@example.com>",
+        );
+        tr.commit_by("main", "Fix parser bug", "Bob", "bob@example.com");
+        tr.commit_file("main", "src/ui/view.rs", "b\n", "Polish view");
+
+        let handler = SearchHandler::new();
+        let path = tr.path().to_path_buf();
+        let search = |query: &str| handler.find_matching_indices_in_repo(&path, "main", query);
+
+        assert_eq!(search("author:ALICE").unwrap(), vec![3]);
+        assert_eq!(search("author:carol").unwrap(), vec![2]);
+        assert_eq!(search("parser -author:alice").unwrap(), vec![1]);
+        assert_eq!(search("path:src/ui").unwrap(), vec![0, 2]);
+        assert_eq!(search("-path:src/ui/view.rs").unwrap(), vec![1, 3]);
+        assert_eq!(search("/fix(ed)? parser/").unwrap(), vec![1]);
+        assert_eq!(search("\"tweak view\"").unwrap(), vec![2]);
+        assert_eq!(search("after:2023-01-01").unwrap(), vec![0, 1, 2, 3]);
+        assert!(search("before:2023-01-01").unwrap().is_empty());
+        assert!(search("/(/").is_err());
+    }
+
+    #[test]
+    fn query_is_merge_matches_only_merge_commits() {
+        let mut tr = TestRepo::new();
+        tr.commit("base");
+        tr.create_branch("topic", "main");
+        tr.commit_on("topic", "topic work");
+        tr.commit("main work");
+        let merge = tr.merge_commit("main", "topic", "Merge topic");
+
+        let handler = SearchHandler::new();
+        let path = tr.path().to_path_buf();
+
+        let merges = handler
+            .find_matching_indices_in_repo(&path, "main", "is:merge")
+            .unwrap();
+        assert_eq!(merges, vec![0]);
+        assert_eq!(
+            handler
+                .find_matching_indices_in_repo(&path, "main", "-is:merge")
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let index = handler
+            .oid_cache
+            .get_or_build(&path, "main", None, None)
+            .unwrap();
+        assert_eq!(index.oids[0], merge);
+    }
+
+    #[test]
+    fn content_queries_match_occurrence_changes_and_changed_lines() {
+        let mut tr = TestRepo::new();
+        tr.commit_file("main", "lib.rs", "fn parse_args() {}\n", "Add parser");
+        tr.commit_file(
+            "main",
+            "lib.rs",
+            "fn parse_args() {}\nfn run() { parse_args(); }\n",
+            "Call parser",
+        );
+        tr.commit_file(
+            "main",
+            "lib.rs",
+            "fn parse_args() {}\nfn run() {\n    parse_args();\n}\n",
+            "Reformat",
+        );
+        tr.commit("Unrelated");
+
+        let handler = SearchHandler::new();
+        let path = tr.path().to_path_buf();
+        let search = |query: &str| handler.find_matching_indices_in_repo(&path, "main", query);
+
+        assert_eq!(search("content:parse_args").unwrap(), vec![2, 3]);
+        assert_eq!(search(r"content:/parse_args\(\);/").unwrap(), vec![1, 2]);
+        assert!(search("content:Parse_args").unwrap().is_empty());
+        assert!(search("reformat content:parse_args").unwrap().is_empty());
+        assert_eq!(search("-content:parse_args").unwrap(), vec![0, 1]);
+    }
+
+    #[test]
+    fn count_occurrences_does_not_overlap() {
+        assert_eq!(count_occurrences(b"aaaa", b"aa"), 2);
+        assert_eq!(count_occurrences(b"abc", b""), 0);
+        assert_eq!(count_occurrences(b"ab", b"abc"), 0);
+    }
 }
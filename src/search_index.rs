@@ -0,0 +1,582 @@
+//! Persistent commit index used by search.
+//!
+//! Searching a large history by reading every commit from the object database
+//! is slow, so the commits reachable from a ref are indexed once, in revwalk
+//! order, together with the lowercased message and signatures the query
+//! filters need. The index is saved under the XDG cache dir, keyed by
+//! repository path and ref, and remembers the tip it was built from. When the
+//! ref later moves forward only the new commits are walked and prepended.
+//! Indices that have not been used for a while are pruned on each save.
+
+use crate::git;
+use crate::logger::Logger;
+use git2::{Oid, Repository};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// File header; bump the version when the layout changes.
+const MAGIC: &[u8; 8] = b"GITYIDX2";
+
+/// Smallest size of one saved commit: oid, time, parent count and three
+/// empty strings.
+const MIN_RECORD_LEN: usize = 20 + 8 + 4 + 3 * 4;
+
+/// Most index files kept; the least recently used are removed first.
+const MAX_INDEX_FILES: usize = 64;
+
+/// Index files unused for longer than this are removed.
+const MAX_INDEX_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// The searchable data of one commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedCommit {
+    /// Commit time in seconds since the epoch.
+    pub time: i64,
+    pub parent_count: u32,
+    /// Full message, lowercased.
+    pub message: String,
+    /// Author name and email, then the `Co-authored-by` trailer values,
+    /// separated by newlines, lowercased.
+    pub author: String,
+    /// Committer name and email separated by a newline, lowercased.
+    pub committer: String,
+}
+
+impl IndexedCommit {
+    pub fn from_commit(commit: &git2::Commit) -> Self {
+        let sig = |sig: git2::Signature| {
+            format!(
+                "{}\n{}",
+                String::from_utf8_lossy(sig.name_bytes()),
+                String::from_utf8_lossy(sig.email_bytes())
+            )
+            .to_lowercase()
+        };
+        let message = String::from_utf8_lossy(commit.message_bytes());
+        let mut author = sig(commit.author());
+        for trailer in git::parse_trailers(&message) {
+            if trailer.is(git::CO_AUTHORED_BY) {
+                author.push('\n');
+                author.push_str(&trailer.value.to_lowercase());
+            }
+        }
+        Self {
+            time: commit.time().seconds(),
+            parent_count: commit.parent_count() as u32,
+            message: message.to_lowercase(),
+            author,
+            committer: sig(commit.committer()),
+        }
+    }
+}
+
+/// Commits reachable from `tip` in revwalk order (the commit list order).
+///
+/// `oids` and `commits` are parallel vectors.
+#[derive(Debug, Default)]
+pub struct CommitIndex {
+    pub repo_path: PathBuf,
+    pub branch_ref: String,
+    /// Tip the index was built from. `None` for indices that can't be
+    /// persisted or extended (e.g. path-filtered lists).
+    pub tip: Option<Oid>,
+    pub oids: Vec<Oid>,
+    pub commits: Vec<IndexedCommit>,
+}
+
+/// Directory holding the persisted indices, if the platform has a cache dir.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("gity").join("search-index"))
+}
+
+/// Index file for `branch_ref` of the repository at `repo_path`.
+fn index_file(cache_dir: &Path, repo_path: &Path, branch_ref: &str) -> PathBuf {
+    let key = format!("{}\n{}", repo_path.display(), branch_ref);
+    let name = Oid::hash_object(git2::ObjectType::Blob, key.as_bytes())
+        .map(|oid| oid.to_string())
+        .unwrap_or_else(|_| "index".to_string());
+    cache_dir.join(format!("{name}.idx"))
+}
+
+/// Mark an index file as used, so pruning keeps it.
+fn touch(file: &Path) {
+    let _ = File::options()
+        .append(true)
+        .open(file)
+        .and_then(|f| f.set_modified(SystemTime::now()));
+}
+
+/// Remove the index files in `cache_dir` last used more than `max_age` ago,
+/// and all but the `max_files` most recently used. Returns how many were
+/// removed.
+pub fn prune_cache(cache_dir: &Path, max_files: usize, max_age: Duration) -> io::Result<usize> {
+    let mut files: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for entry in std::fs::read_dir(cache_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "idx") {
+            files.push((std::fs::metadata(&path)?.modified()?, path));
+        }
+    }
+    // Most recently used first.
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for (i, (modified, path)) in files.iter().enumerate() {
+        let expired = now.duration_since(*modified).is_ok_and(|age| age > max_age);
+        if (i >= max_files || expired) && std::fs::remove_file(path).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Commit ids and their indexed data, in revwalk order.
+type Walked = (Vec<Oid>, Vec<IndexedCommit>);
+
+/// Revwalk from `tip` (hiding `hide`), stopping with `Ok(None)` as soon as
+/// `accept` rejects a commit.
+fn walk(
+    repo: &Repository,
+    tip: Oid,
+    hide: Option<Oid>,
+    cancel: Option<&Arc<AtomicBool>>,
+    mut accept: impl FnMut(&git2::Commit) -> bool,
+) -> Result<Option<Walked>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    // Match `CommitWalker::new` defaults for `for_branch` (no explicit sorting, not reversed).
+    revwalk.set_sorting(git2::Sort::NONE)?;
+    revwalk.push(tip)?;
+    if let Some(hide) = hide {
+        revwalk.hide(hide)?;
+    }
+
+    let mut oids = Vec::new();
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(git2::Error::from_str("Cancelled"));
+        }
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        if !accept(&commit) {
+            return Ok(None);
+        }
+        oids.push(oid);
+        commits.push(IndexedCommit::from_commit(&commit));
+    }
+    Ok(Some((oids, commits)))
+}
+
+impl CommitIndex {
+    /// Index everything reachable from `tip`.
+    pub fn build(
+        repo: &Repository,
+        repo_path: &Path,
+        branch_ref: &str,
+        tip: Oid,
+        cancel: Option<&Arc<AtomicBool>>,
+    ) -> Result<Self, git2::Error> {
+        let (oids, commits) = walk(repo, tip, None, cancel, |_| true)?.unwrap_or_default();
+        Ok(Self {
+            repo_path: repo_path.to_path_buf(),
+            branch_ref: branch_ref.to_string(),
+            tip: Some(tip),
+            oids,
+            commits,
+        })
+    }
+
+    /// Index for `tip` built by prepending the commits added since this
+    /// index's tip.
+    ///
+    /// Returns `None` unless the new commits form a simple fast-forward that
+    /// is newer than the old tip: only then is "new commits, then the old
+    /// index" the same order a full revwalk would produce.
+    pub fn extended_to(
+        &self,
+        repo: &Repository,
+        tip: Oid,
+        cancel: Option<&Arc<AtomicBool>>,
+    ) -> Result<Option<Self>, git2::Error> {
+        let Some(old_tip) = self.tip else {
+            return Ok(None);
+        };
+        if self.oids.first() != Some(&old_tip) || !repo.graph_descendant_of(tip, old_tip)? {
+            return Ok(None);
+        }
+        let old_time = self.commits[0].time;
+
+        // Parents of new commits must be new commits or the old tip.
+        let mut pending: HashSet<Oid> = HashSet::new();
+        let walked = walk(repo, tip, Some(old_tip), cancel, |commit| {
+            if commit.time().seconds() <= old_time {
+                return false;
+            }
+            pending.remove(&commit.id());
+            pending.extend(commit.parent_ids().filter(|p| *p != old_tip));
+            true
+        })?;
+        let Some((mut oids, mut commits)) = walked else {
+            return Ok(None);
+        };
+        if !pending.is_empty() {
+            return Ok(None);
+        }
+
+        oids.extend_from_slice(&self.oids);
+        commits.extend_from_slice(&self.commits);
+        Ok(Some(Self {
+            repo_path: self.repo_path.clone(),
+            branch_ref: self.branch_ref.clone(),
+            tip: Some(tip),
+            oids,
+            commits,
+        }))
+    }
+
+    /// Index for `tip`, reusing `previous` (or the copy persisted in
+    /// `cache_dir`) when possible. The result is written back to `cache_dir`.
+    pub fn load_or_build(
+        repo: &Repository,
+        repo_path: &Path,
+        branch_ref: &str,
+        tip: Oid,
+        previous: Option<Arc<CommitIndex>>,
+        cache_dir: Option<&Path>,
+        cancel: Option<&Arc<AtomicBool>>,
+    ) -> Result<Arc<Self>, git2::Error> {
+        let file = cache_dir.map(|dir| index_file(dir, repo_path, branch_ref));
+        let previous = previous
+            .filter(|p| p.repo_path == repo_path && p.branch_ref == branch_ref)
+            .or_else(|| {
+                let file = file.as_ref()?;
+                let loaded = Self::load(file, repo_path, branch_ref)?;
+                touch(file);
+                Some(Arc::new(loaded))
+            });
+
+        if let Some(previous) = &previous {
+            if previous.tip == Some(tip) {
+                return Ok(previous.clone());
+            }
+            if let Some(extended) = previous.extended_to(repo, tip, cancel)? {
+                Logger::info(&format!(
+                    "Extended commit index by {} commits",
+                    extended.oids.len() - previous.oids.len()
+                ));
+                let extended = Arc::new(extended);
+                extended.persist(file.as_deref());
+                return Ok(extended);
+            }
+        }
+
+        let built = Arc::new(Self::build(repo, repo_path, branch_ref, tip, cancel)?);
+        built.persist(file.as_deref());
+        Ok(built)
+    }
+
+    fn persist(&self, file: Option<&Path>) {
+        let Some(file) = file else {
+            return;
+        };
+        if let Err(e) = self.save(file) {
+            Logger::error(&format!(
+                "Failed to save commit index {}: {}",
+                file.display(),
+                e
+            ));
+        }
+        if let Some(dir) = file.parent()
+            && let Err(e) = prune_cache(dir, MAX_INDEX_FILES, MAX_INDEX_AGE)
+        {
+            Logger::error(&format!(
+                "Failed to prune commit indices in {}: {}",
+                dir.display(),
+                e
+            ));
+        }
+    }
+
+    /// Write the index to `path` (atomically, via a temporary file).
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let Some(tip) = self.tip else {
+            return Err(io::Error::other("index has no tip"));
+        };
+        let dir = path.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir)?;
+        // A unique name, so concurrent saves of the same index don't clash.
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        let mut w = BufWriter::new(tmp.as_file_mut());
+        w.write_all(MAGIC)?;
+        write_str(&mut w, &self.repo_path.to_string_lossy())?;
+        write_str(&mut w, &self.branch_ref)?;
+        w.write_all(tip.as_bytes())?;
+        w.write_all(&(self.oids.len() as u64).to_le_bytes())?;
+        for (oid, commit) in self.oids.iter().zip(&self.commits) {
+            w.write_all(oid.as_bytes())?;
+            w.write_all(&commit.time.to_le_bytes())?;
+            w.write_all(&commit.parent_count.to_le_bytes())?;
+            write_str(&mut w, &commit.message)?;
+            write_str(&mut w, &commit.author)?;
+            write_str(&mut w, &commit.committer)?;
+        }
+        w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        tmp.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Read an index saved for `repo_path`/`branch_ref`. Missing, corrupt or
+    /// mismatched files yield `None`.
+    pub fn load(path: &Path, repo_path: &Path, branch_ref: &str) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        let mut r = data.as_slice();
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic).ok()?;
+        if &magic != MAGIC {
+            return None;
+        }
+        if read_str(&mut r).ok()? != repo_path.to_string_lossy()
+            || read_str(&mut r).ok()? != branch_ref
+        {
+            return None;
+        }
+        let tip = read_oid(&mut r).ok()?;
+        let count = u64::from_le_bytes(read_array(&mut r).ok()?);
+        // A corrupt count must not make us loop (or allocate) far past the data.
+        if count > (r.len() / MIN_RECORD_LEN) as u64 {
+            return None;
+        }
+
+        let mut oids = Vec::new();
+        let mut commits = Vec::new();
+        for _ in 0..count {
+            oids.push(read_oid(&mut r).ok()?);
+            commits.push(IndexedCommit {
+                time: i64::from_le_bytes(read_array(&mut r).ok()?),
+                parent_count: u32::from_le_bytes(read_array(&mut r).ok()?),
+                message: read_str(&mut r).ok()?,
+                author: read_str(&mut r).ok()?,
+                committer: read_str(&mut r).ok()?,
+            });
+        }
+
+        Some(Self {
+            repo_path: repo_path.to_path_buf(),
+            branch_ref: branch_ref.to_string(),
+            tip: Some(tip),
+            oids,
+            commits,
+        })
+    }
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> io::Result<()> {
+    w.write_all(&(s.len() as u32).to_le_bytes())?;
+    w.write_all(s.as_bytes())
+}
+
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_str(r: &mut &[u8]) -> io::Result<String> {
+    let len = u32::from_le_bytes(read_array(r)?) as usize;
+    if len > r.len() {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let (bytes, rest) = r.split_at(len);
+    *r = rest;
+    String::from_utf8(bytes.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn read_oid(r: &mut impl Read) -> io::Result<Oid> {
+    let bytes: [u8; 20] = read_array(r)?;
+    Oid::from_bytes(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    fn full_walk(tr: &TestRepo, branch: &str) -> Vec<Oid> {
+        CommitIndex::build(tr.repo(), tr.path(), branch, tr.tip(branch), None)
+            .unwrap()
+            .oids
+    }
+
+    #[test]
+    fn index_round_trips_through_the_cache_file() {
+        let mut tr = TestRepo::new();
+        tr.commit_by("main", "First Commit", "Alice", "Alice@Example.com");
+        tr.commit("Second");
+        let cache = tempfile::tempdir().unwrap();
+        let dir = Some(cache.path());
+
+        let built = CommitIndex::load_or_build(
+            tr.repo(),
+            tr.path(),
+            "main",
+            tr.tip("main"),
+            None,
+            dir,
+            None,
+        )
+        .unwrap();
+        assert_eq!(built.commits[1].message, "first commit");
+        assert_eq!(built.commits[1].author, "alice\nalice@example.com");
+
+        let file = index_file(cache.path(), tr.path(), "main");
+        let loaded = CommitIndex::load(&file, tr.path(), "main").unwrap();
+        assert_eq!(loaded.tip, built.tip);
+        assert_eq!(loaded.oids, built.oids);
+        assert_eq!(loaded.commits, built.commits);
+
+        // Saved for a different ref: not reused.
+        assert!(CommitIndex::load(&file, tr.path(), "other").is_none());
+    }
+
+    #[test]
+    fn corrupt_counts_and_lengths_are_rejected() {
+        let repo_path = Path::new("/repo");
+        let header = |count: u64| {
+            let mut data = MAGIC.to_vec();
+            write_str(&mut data, "/repo").unwrap();
+            write_str(&mut data, "main").unwrap();
+            data.extend_from_slice(Oid::zero().as_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            data
+        };
+        let cache = tempfile::tempdir().unwrap();
+        let file = cache.path().join("bad.idx");
+
+        std::fs::write(&file, header(u64::MAX)).unwrap();
+        assert!(CommitIndex::load(&file, repo_path, "main").is_none());
+
+        let mut data = header(1);
+        data.extend_from_slice(Oid::zero().as_bytes());
+        data.extend_from_slice(&0i64.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.resize(data.len() + 3 * 4, 0);
+        std::fs::write(&file, data).unwrap();
+        assert!(CommitIndex::load(&file, repo_path, "main").is_none());
+
+        // The same layout with sane values loads.
+        let mut data = header(1);
+        data.extend_from_slice(Oid::zero().as_bytes());
+        data.extend_from_slice(&0i64.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.resize(data.len() + 3 * 4, 0);
+        std::fs::write(&file, data).unwrap();
+        let loaded = CommitIndex::load(&file, repo_path, "main").unwrap();
+        assert_eq!(loaded.commits.len(), 1);
+    }
+
+    #[test]
+    fn prune_removes_old_and_least_recently_used_indices() {
+        let cache = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let hour = Duration::from_secs(60 * 60);
+        for (name, age) in [
+            ("a.idx", Duration::ZERO),
+            ("b.idx", hour),
+            ("c.idx", 2 * hour),
+            ("d.idx", 30 * 24 * hour),
+            ("notes.txt", 30 * 24 * hour),
+        ] {
+            let file = File::create(cache.path().join(name)).unwrap();
+            file.set_modified(now - age).unwrap();
+        }
+
+        let removed = prune_cache(cache.path(), 2, 7 * 24 * hour).unwrap();
+        assert_eq!(removed, 2);
+        let mut left: Vec<String> = std::fs::read_dir(cache.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["a.idx", "b.idx", "notes.txt"]);
+    }
+
+    #[test]
+    fn fast_forward_extends_the_persisted_index() {
+        let mut tr = TestRepo::new();
+        tr.commit("a");
+        tr.commit("b");
+        let cache = tempfile::tempdir().unwrap();
+        let dir = Some(cache.path());
+        CommitIndex::load_or_build(
+            tr.repo(),
+            tr.path(),
+            "main",
+            tr.tip("main"),
+            None,
+            dir,
+            None,
+        )
+        .unwrap();
+
+        tr.commit("c");
+        tr.commit("d");
+        let file = index_file(cache.path(), tr.path(), "main");
+        let old = CommitIndex::load(&file, tr.path(), "main").unwrap();
+        let extended = old
+            .extended_to(tr.repo(), tr.tip("main"), None)
+            .unwrap()
+            .expect("fast-forward extends");
+        assert_eq!(extended.oids, full_walk(&tr, "main"));
+        assert_eq!(extended.commits[0].message, "d");
+
+        let updated = CommitIndex::load_or_build(
+            tr.repo(),
+            tr.path(),
+            "main",
+            tr.tip("main"),
+            None,
+            dir,
+            None,
+        )
+        .unwrap();
+        assert_eq!(updated.oids, full_walk(&tr, "main"));
+    }
+
+    #[test]
+    fn merging_older_history_falls_back_to_a_full_rebuild() {
+        let mut tr = TestRepo::new();
+        tr.commit("base");
+        tr.create_branch("topic", "main");
+        tr.commit_on("topic", "old topic work");
+        tr.commit("main work");
+        let before =
+            CommitIndex::build(tr.repo(), tr.path(), "main", tr.tip("main"), None).unwrap();
+
+        tr.merge_commit("main", "topic", "Merge topic");
+        assert!(
+            before
+                .extended_to(tr.repo(), tr.tip("main"), None)
+                .unwrap()
+                .is_none()
+        );
+
+        let rebuilt = CommitIndex::load_or_build(
+            tr.repo(),
+            tr.path(),
+            "main",
+            tr.tip("main"),
+            Some(Arc::new(before)),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(rebuilt.oids, full_walk(&tr, "main"));
+    }
+}
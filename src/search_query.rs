@@ -0,0 +1,325 @@
+//! Query syntax for commit search.
+//!
+//! A query is a whitespace-separated list of terms; a commit matches when it
+//! matches every term:
+//!
+//! - `word` / `"quoted phrase"`: case-insensitive substring of the message
+//! - `/regex/`: regular expression over the message (case-insensitive)
+//! - `author:name` / `committer:name`: substring of the name or email;
+//!   `author:` also matches `Co-authored-by` trailers
+//! - `before:YYYY-MM-DD` / `after:YYYY-MM-DD`: commit date (local time)
+//! - `path:src/ui`: the commit changes this file or folder
+//! - `is:merge`: the commit has more than one parent
+//! - `content:ident`: the commit changes how often `ident` occurs in a file
+//!   (like `git log -S`)
+//! - `content:/regex/`: an added or removed line matches the regex (like
+//!   `git log -G`)
+//!
+//! Content terms are case-sensitive and diff each commit against its first
+//! parent, so they are much slower than the other filters.
+//!
+//! Any term can be negated with a leading `-`. Field values may be quoted
+//! (`author:"Jane Doe"`). Words with an unknown field prefix, like `fix:`,
+//! are plain text.
+
+use chrono::{Local, NaiveDate, TimeZone};
+use regex::{Regex, RegexBuilder};
+
+/// What a single query term checks.
+#[derive(Debug, Clone)]
+pub enum QueryFilter {
+    /// Lowercased text the message must contain.
+    Text(String),
+    Regex(Regex),
+    /// Lowercased text the author name or email, or a co-author, must
+    /// contain.
+    Author(String),
+    /// Lowercased text the committer name or email must contain.
+    Committer(String),
+    /// Commit time (seconds since the epoch) must be before this.
+    Before(i64),
+    /// Commit time (seconds since the epoch) must be at or after this.
+    After(i64),
+    /// Path (relative to the repository root) the commit must change.
+    Path(String),
+    Merge,
+    /// Text whose number of occurrences must differ between the commit and
+    /// its first parent in at least one file.
+    Pickaxe(String),
+    /// Regex an added or removed line must match.
+    DiffRegex(Regex),
+}
+
+impl QueryFilter {
+    /// Whether checking this filter requires diffing the commit.
+    pub fn needs_diff(&self) -> bool {
+        matches!(
+            self,
+            QueryFilter::Path(_) | QueryFilter::Pickaxe(_) | QueryFilter::DiffRegex(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryTerm {
+    pub filter: QueryFilter,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub terms: Vec<QueryTerm>,
+}
+
+impl SearchQuery {
+    /// Parse a search query. Field filters with an empty value (e.g. a
+    /// half-typed `author:`) are ignored.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut i = 0;
+        let mut terms = Vec::new();
+
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+
+            let negated = chars[i] == '-' && chars.get(i + 1).is_some_and(|c| !c.is_whitespace());
+            if negated {
+                i += 1;
+            }
+
+            let filter = match chars[i] {
+                '"' => Some(QueryFilter::Text(
+                    read_quoted(&chars, &mut i).to_lowercase(),
+                )),
+                '/' => Some(QueryFilter::Regex(read_regex(&chars, &mut i, true)?)),
+                _ => match read_field(&chars, &mut i) {
+                    Some(field) if field == "content" && chars.get(i) == Some(&'/') => {
+                        Some(QueryFilter::DiffRegex(read_regex(&chars, &mut i, false)?))
+                    }
+                    Some(field) => {
+                        // Quoted values are taken verbatim, so e.g.
+                        // `content:" x "` keeps its spaces.
+                        let value = if chars.get(i) == Some(&'"') {
+                            read_quoted(&chars, &mut i)
+                        } else {
+                            read_word(&chars, &mut i).trim().to_string()
+                        };
+                        field_filter(&field, &value)?
+                    }
+                    None => Some(QueryFilter::Text(read_word(&chars, &mut i).to_lowercase())),
+                },
+            };
+
+            if let Some(filter) = filter {
+                terms.push(QueryTerm { filter, negated });
+            }
+        }
+
+        Ok(Self { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+const FIELDS: &[&str] = &[
+    "author",
+    "committer",
+    "before",
+    "after",
+    "path",
+    "is",
+    "content",
+];
+
+/// If a known `field:` starts at `*i`, consume it and return the field name.
+fn read_field(chars: &[char], i: &mut usize) -> Option<String> {
+    let name: String = chars[*i..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    let colon = *i + name.chars().count();
+    let name = name.to_lowercase();
+    if chars.get(colon) == Some(&':') && FIELDS.contains(&name.as_str()) {
+        *i = colon + 1;
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Read up to the next whitespace.
+fn read_word(chars: &[char], i: &mut usize) -> String {
+    let start = *i;
+    while *i < chars.len() && !chars[*i].is_whitespace() {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect()
+}
+
+/// Read a `"quoted phrase"` starting at the opening quote. An unterminated
+/// phrase runs to the end of the query.
+fn read_quoted(chars: &[char], i: &mut usize) -> String {
+    *i += 1;
+    let start = *i;
+    while *i < chars.len() && chars[*i] != '"' {
+        *i += 1;
+    }
+    let phrase = chars[start..*i].iter().collect();
+    *i = (*i + 1).min(chars.len());
+    phrase
+}
+
+/// Read and compile a `/regex/` starting at the opening slash. `\/` stands
+/// for a literal slash inside the pattern.
+fn read_regex(chars: &[char], i: &mut usize, case_insensitive: bool) -> Result<Regex, String> {
+    *i += 1;
+    let mut pattern = String::new();
+    loop {
+        match chars.get(*i) {
+            None => return Err("Unterminated regex: missing closing /".to_string()),
+            Some('/') => break,
+            Some('\\') if chars.get(*i + 1) == Some(&'/') => {
+                pattern.push('/');
+                *i += 2;
+            }
+            Some(&c) => {
+                pattern.push(c);
+                *i += 1;
+            }
+        }
+    }
+    *i += 1;
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| format!("Invalid regex /{}/: {}", pattern, e))
+}
+
+fn field_filter(field: &str, value: &str) -> Result<Option<QueryFilter>, String> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let filter = match field {
+        "author" => QueryFilter::Author(value.to_lowercase()),
+        "committer" => QueryFilter::Committer(value.to_lowercase()),
+        "before" => QueryFilter::Before(parse_date(value)?),
+        "after" => QueryFilter::After(parse_date(value)?),
+        "path" => {
+            let path = value.trim_matches('/');
+            if path.is_empty() {
+                return Ok(None);
+            }
+            QueryFilter::Path(path.to_string())
+        }
+        "is" if value.eq_ignore_ascii_case("merge") => QueryFilter::Merge,
+        "is" => return Err(format!("Unknown filter is:{}", value)),
+        "content" => QueryFilter::Pickaxe(value.to_string()),
+        _ => unreachable!("field list and filters out of sync"),
+    };
+    Ok(Some(filter))
+}
+
+/// Start of the given day in local time, as seconds since the epoch.
+fn parse_date(value: &str) -> Result<i64, String> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date \"{}\": expected YYYY-MM-DD", value))?;
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .ok_or_else(|| format!("Invalid date \"{}\"", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<QueryTerm> {
+        SearchQuery::parse(input).unwrap().terms
+    }
+
+    #[test]
+    fn plain_words_and_phrases_are_lowercased_text() {
+        let terms = parse(r#"Fix "Login Page" fix: typo"#);
+        let texts: Vec<&str> = terms
+            .iter()
+            .map(|t| match &t.filter {
+                QueryFilter::Text(s) => s.as_str(),
+                other => panic!("unexpected filter {other:?}"),
+            })
+            .collect();
+        assert_eq!(texts, vec!["fix", "login page", "fix:", "typo"]);
+    }
+
+    #[test]
+    fn fields_negation_and_quoted_values() {
+        let terms = parse(r#"author:"Jane Doe" -is:merge path:/src/ui/ -committer:bot"#);
+        assert_eq!(terms.len(), 4);
+        assert!(matches!(&terms[0].filter, QueryFilter::Author(s) if s == "jane doe"));
+        assert!(!terms[0].negated);
+        assert!(matches!(terms[1].filter, QueryFilter::Merge));
+        assert!(terms[1].negated);
+        assert!(matches!(&terms[2].filter, QueryFilter::Path(p) if p == "src/ui"));
+        assert!(matches!(&terms[3].filter, QueryFilter::Committer(s) if s == "bot"));
+        assert!(terms[3].negated);
+    }
+
+    #[test]
+    fn dates_bound_the_start_of_the_day() {
+        let terms = parse("after:2024-01-01 before:2024-01-02");
+        let (QueryFilter::After(after), QueryFilter::Before(before)) =
+            (&terms[0].filter, &terms[1].filter)
+        else {
+            panic!("unexpected filters {terms:?}");
+        };
+        assert_eq!(before - after, 24 * 60 * 60);
+
+        assert!(SearchQuery::parse("before:yesterday").is_err());
+    }
+
+    #[test]
+    fn regex_terms_allow_spaces_and_escaped_slashes() {
+        let terms = parse(r"/fix(ed)? bug/ /a\/b/");
+        let QueryFilter::Regex(re) = &terms[0].filter else {
+            panic!("expected regex");
+        };
+        assert!(re.is_match("FIXED BUG"));
+        let QueryFilter::Regex(re) = &terms[1].filter else {
+            panic!("expected regex");
+        };
+        assert!(re.is_match("a/b"));
+
+        assert!(SearchQuery::parse("/unterminated").is_err());
+        assert!(SearchQuery::parse("/(/").is_err());
+    }
+
+    #[test]
+    fn content_terms_keep_case() {
+        let terms = parse(r#"content:parseArgs -content:/fn \w+Args/ content:"a b""#);
+        assert!(matches!(&terms[0].filter, QueryFilter::Pickaxe(s) if s == "parseArgs"));
+        let QueryFilter::DiffRegex(re) = &terms[1].filter else {
+            panic!("expected diff regex");
+        };
+        assert!(terms[1].negated);
+        assert!(re.is_match("fn parseArgs()"));
+        assert!(!re.is_match("FN PARSEARGS()"));
+        assert!(matches!(&terms[2].filter, QueryFilter::Pickaxe(s) if s == "a b"));
+
+        let terms = parse(r#"content:" fn (" content:x"#);
+        assert!(matches!(&terms[0].filter, QueryFilter::Pickaxe(s) if s == " fn ("));
+        assert!(matches!(&terms[1].filter, QueryFilter::Pickaxe(s) if s == "x"));
+    }
+
+    #[test]
+    fn empty_field_values_are_ignored() {
+        assert!(SearchQuery::parse("author: is:").unwrap().is_empty());
+        assert!(SearchQuery::parse("is:tag").is_err());
+    }
+}
@@ -168,6 +168,34 @@ impl TestRepo {
             .expect("create commit")
     }
 
+    /// Rename `from` to `to` on `branch` without changing its contents.
+    pub fn rename_file(&mut self, branch: &str, from: &str, to: &str, message: &str) -> Oid {
+        let when = self.next_time();
+        let ref_name = format!("refs/heads/{branch}");
+        let parent = self.repo.find_commit(self.tip(branch)).unwrap();
+
+        let workdir = self.repo.workdir().expect("workdir").to_path_buf();
+        if let Some(dir) = workdir.join(to).parent() {
+            std::fs::create_dir_all(dir).expect("mkdir");
+        }
+        std::fs::rename(workdir.join(from), workdir.join(to)).expect("rename file");
+
+        let mut index = self.repo.index().expect("open index");
+        index
+            .read_tree(&parent.tree().unwrap())
+            .expect("seed index");
+        index.remove_path(Path::new(from)).expect("remove path");
+        index.add_path(Path::new(to)).expect("add path");
+        let tree_oid = index.write_tree().expect("write tree");
+        index.write().expect("persist index");
+
+        let tree = self.repo.find_tree(tree_oid).expect("find tree");
+        let sig = Signature::new("Tester", "tester@example.com", &when).unwrap();
+        self.repo
+            .commit(Some(&ref_name), &sig, &sig, message, &tree, &[&parent])
+            .expect("create rename commit")
+    }
+
     /// Create a merge commit on `branch` with a second parent from `other_branch`.
     /// The resulting tree mirrors `branch`'s current tip (no real merge performed).
     pub fn merge_commit(&mut self, branch: &str, other_branch: &str, message: &str) -> Oid {
@@ -6,13 +6,18 @@
 //! to gsettings.
 
 use chrono::{DateTime, Utc};
-use gtk::{gio, prelude::*};
+use gtk::{gio, glib, prelude::*};
 use std::cell::RefCell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
 
 use crate::APP_ID;
-use crate::git::{BranchInfo, TagInfo};
+use crate::git::{self, AheadBehind, BranchDivergence, BranchInfo, StashInfo, TagInfo};
+use crate::logger::Logger;
+use crate::util::natural_compare;
 
 /// Type of git reference (branch, remote branch, or tag).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,12 +34,13 @@ struct SelectedRef {
     ref_type: RefType,
 }
 
-/// A panel widget that displays local branches, tags, and remote-tracking branches.
+/// A panel widget that displays local branches, tags, remote-tracking branches and stashes.
 ///
 /// The panel shows refs in separate collapsible sections:
 /// - **Branches** — local branches with a checkmark on the checked-out branch
 /// - **Tags**
 /// - **Remotes** — grouped by remote name (e.g. `origin`), each with its own expander
+/// - **Stashes** — stash entries, newest first; hidden when the stash is empty
 ///
 /// Branches are sorted with "main" or "master" first, then by latest commit time.
 /// Tags are sorted alphabetically using natural sort order.
@@ -58,10 +64,21 @@ pub struct BranchPanel {
     _tags_expander: gtk::Expander,
     /// The expander for remotes section (kept for widget lifetime)
     _remotes_expander: gtk::Expander,
+    /// The expander for the stashes section, shown only when there are stashes
+    stashes_expander: gtk::Expander,
+    /// The list box containing stash rows
+    stashes_list_box: gtk::ListBox,
+    /// Stash entries in row order
+    stashes: Rc<RefCell<Vec<StashInfo>>>,
     /// Handler invoked when a ref row is activated (set via `on_ref_selected`)
     activate_handler: Rc<RefCell<Option<Rc<dyn Fn(&str, RefType)>>>>,
     /// Currently selected reference (name and type)
     selected_ref: Rc<RefCell<Option<SelectedRef>>>,
+    /// Last computed ahead/behind counts per local branch, re-applied when
+    /// the rows are rebuilt
+    divergence: Rc<RefCell<HashMap<String, BranchDivergence>>>,
+    /// Cancels the running ahead/behind computation (set via `load_divergence`)
+    divergence_cancel: Rc<RefCell<Option<Arc<AtomicBool>>>>,
     /// GSettings for persisting expanded state (kept for reference lifetime)
     _settings: gio::Settings,
 }
@@ -184,6 +201,35 @@ impl BranchPanel {
         remotes_expander.set_child(Some(&remotes_content_box));
         content_box.append(&remotes_expander);
 
+        // Create stashes section (last; hidden until stashes are loaded)
+        let stashes_expander = gtk::Expander::builder()
+            .expanded(settings.boolean("stashes-expanded"))
+            .visible(false)
+            .build();
+        stashes_expander.add_css_class("branch-panel-expander");
+
+        let stashes_label = gtk::Label::builder()
+            .label("Stashes")
+            .halign(gtk::Align::Start)
+            .build();
+        stashes_label.add_css_class("heading");
+        let stashes_label_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .build();
+        stashes_label_box.add_css_class("branch-panel-expander-label");
+        stashes_label_box.append(&stashes_label);
+        stashes_expander.set_label_widget(Some(&stashes_label_box));
+        set_expander_chevron_margin(&stashes_expander, 10);
+
+        // Selecting a stash shows its changes without switching refs, so its
+        // selection is independent of the ref lists.
+        let stashes_list_box = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Single)
+            .build();
+
+        stashes_expander.set_child(Some(&stashes_list_box));
+        content_box.append(&stashes_expander);
+
         let remote_list_boxes = Rc::new(RefCell::new(Vec::new()));
         let activate_handler: Rc<RefCell<Option<Rc<dyn Fn(&str, RefType)>>>> =
             Rc::new(RefCell::new(None));
@@ -221,6 +267,13 @@ impl BranchPanel {
             let _ = settings_for_remotes.set_boolean("remotes-expanded", exp.is_expanded());
         });
 
+        let settings_for_stashes = settings.clone();
+        stashes_expander.connect_expanded_notify(move |exp| {
+            let _ = settings_for_stashes.set_boolean("stashes-expanded", exp.is_expanded());
+        });
+
+        attach_branch_context_menu(&branches_list_box);
+
         wire_local_list_selection(
             &branches_list_box,
             &tags_list_box,
@@ -237,8 +290,13 @@ impl BranchPanel {
             _branches_expander: branches_expander,
             _tags_expander: tags_expander,
             _remotes_expander: remotes_expander,
+            stashes_expander,
+            stashes_list_box,
+            stashes: Rc::new(RefCell::new(Vec::new())),
             activate_handler,
             selected_ref,
+            divergence: Rc::new(RefCell::new(HashMap::new())),
+            divergence_cancel: Rc::new(RefCell::new(None)),
             _settings: settings,
         };
 
@@ -281,6 +339,11 @@ impl BranchPanel {
         clear_remotes_section(&self.remotes_content_box, &self.remote_list_boxes);
 
         populate_branches_list(&self.branches_list_box, branches, checked_out_branch);
+        if branches.is_empty() {
+            self.cancel_divergence();
+            self.divergence.borrow_mut().clear();
+        }
+        apply_divergence(&self.branches_list_box, &self.divergence.borrow());
         populate_tags_list(&self.tags_list_box, tags);
         populate_remotes_section(
             &self.remotes_content_box,
@@ -383,6 +446,84 @@ impl BranchPanel {
         // Remote list boxes are wired when populated in `populate_remotes_section`.
     }
 
+    /// Replace the listed stash entries. The section is hidden when `stashes`
+    /// is empty.
+    pub fn update_stashes(&self, stashes: &[StashInfo]) {
+        while let Some(row) = self.stashes_list_box.row_at_index(0) {
+            self.stashes_list_box.remove(&row);
+        }
+        for stash in stashes {
+            self.stashes_list_box.append(&create_stash_row(stash));
+        }
+        *self.stashes.borrow_mut() = stashes.to_vec();
+        self.stashes_expander.set_visible(!stashes.is_empty());
+    }
+
+    /// Register a callback for when a stash row is activated.
+    pub fn on_stash_selected<F: Fn(&StashInfo) + 'static>(&self, callback: F) {
+        let stashes = self.stashes.clone();
+        self.stashes_list_box.connect_row_activated(move |_, row| {
+            let stash = usize::try_from(row.index())
+                .ok()
+                .and_then(|idx| stashes.borrow().get(idx).cloned());
+            if let Some(stash) = stash {
+                callback(&stash);
+            }
+        });
+    }
+
+    /// Clear the stash row selection (e.g. once a commit is selected instead).
+    pub fn unselect_stash(&self) {
+        self.stashes_list_box.unselect_all();
+    }
+
+    /// Compute ahead/behind counts for the local branches of the repository
+    /// at `repo_path` in the background and show them as badges when done.
+    /// A computation still running for earlier refs is cancelled.
+    pub fn load_divergence(&self, repo_path: PathBuf) {
+        self.cancel_divergence();
+        let cancel = Arc::new(AtomicBool::new(false));
+        *self.divergence_cancel.borrow_mut() = Some(cancel.clone());
+
+        let (tx, rx) = mpsc::channel();
+        let cancel_for_thread = cancel.clone();
+        std::thread::spawn(move || {
+            let result = git::get_branch_divergence(&repo_path, Some(&cancel_for_thread));
+            let _ = tx.send(result);
+        });
+        poll_divergence_result(
+            rx,
+            cancel,
+            self.branches_list_box.clone(),
+            self.divergence.clone(),
+        );
+    }
+
+    fn cancel_divergence(&self) {
+        if let Some(cancel) = self.divergence_cancel.borrow_mut().take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// All refs shown in the panel, in display order: branches, tags, then
+    /// remote-tracking branches.
+    pub fn ref_names(&self) -> Vec<(String, RefType)> {
+        let mut list_boxes = vec![self.branches_list_box.clone(), self.tags_list_box.clone()];
+        list_boxes.extend(self.remote_list_boxes.borrow().iter().cloned());
+
+        let mut refs = Vec::new();
+        for list_box in list_boxes {
+            let mut i = 0;
+            while let Some(row) = list_box.row_at_index(i) {
+                if let Some(ref_info) = row_ref_info(&row) {
+                    refs.push((ref_info.name, ref_info.ref_type));
+                }
+                i += 1;
+            }
+        }
+        refs
+    }
+
     fn selected_row_ref_info(&self) -> Option<SelectedRef> {
         self.branches_list_box
             .selected_row()
@@ -638,6 +779,34 @@ fn unselect_remote_lists(
     }
 }
 
+/// Right-click menu for local branch rows. Its items are window actions
+/// (e.g. `win.reflog`) that take the branch name.
+fn attach_branch_context_menu(list_box: &gtk::ListBox) {
+    let popover = gtk::PopoverMenu::from_model(None::<&gio::MenuModel>);
+    popover.set_parent(list_box);
+    popover.set_has_arrow(true);
+
+    let right_click = gtk::GestureClick::new();
+    right_click.set_button(3);
+    let list_for_click = list_box.clone();
+    right_click.connect_pressed(move |_, _, x, y| {
+        let Some(ref_info) = list_for_click
+            .row_at_y(y as i32)
+            .and_then(|row| row_ref_info(&row))
+        else {
+            return;
+        };
+        let menu = gio::Menu::new();
+        let item = gio::MenuItem::new(Some("Show Reflog"), None);
+        item.set_action_and_target_value(Some("win.reflog"), Some(&ref_info.name.to_variant()));
+        menu.append_item(&item);
+        popover.set_menu_model(Some(&menu));
+        popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    });
+    list_box.add_controller(right_click);
+}
+
 /// Populate the tags list box.
 fn populate_tags_list(list_box: &gtk::ListBox, tags: &[TagInfo]) {
     let sorted_tags = sort_tags(tags);
@@ -771,6 +940,16 @@ fn create_branch_row(
     branch_label.set_tooltip_text(Some(&branch_info.name));
     row_box.append(&branch_label);
 
+    // Filled in by `apply_divergence` once the counts are known.
+    let badges = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(4)
+        .valign(gtk::Align::Center)
+        .visible(false)
+        .build();
+    badges.set_widget_name("branch-divergence");
+    row_box.append(&badges);
+
     let time_label = gtk::Label::builder()
         .halign(gtk::Align::End)
         .hexpand(true)
@@ -792,6 +971,99 @@ fn create_branch_row(
     row
 }
 
+/// Badge text for ahead/behind counts, e.g. "↑2 ↓5". `None` when the
+/// branches point at the same commit.
+fn divergence_text(counts: AheadBehind) -> Option<String> {
+    let mut parts = Vec::new();
+    if counts.ahead > 0 {
+        parts.push(format!("↑{}", counts.ahead));
+    }
+    if counts.behind > 0 {
+        parts.push(format!("↓{}", counts.behind));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Tooltip line for one badge, e.g. "2 ahead, 5 behind origin/main".
+fn divergence_tooltip(other: &str, counts: AheadBehind) -> String {
+    format!("{} ahead, {} behind {}", counts.ahead, counts.behind, other)
+}
+
+/// Fill the badge box of every branch row from `divergence`.
+fn apply_divergence(list_box: &gtk::ListBox, divergence: &HashMap<String, BranchDivergence>) {
+    let mut i = 0;
+    while let Some(row) = list_box.row_at_index(i) {
+        i += 1;
+        let Some(ref_info) = row_ref_info(&row) else {
+            continue;
+        };
+        let Some(badges) = row.child().and_then(|c| {
+            let mut child = c.first_child();
+            while let Some(widget) = child {
+                if widget.widget_name() == "branch-divergence" {
+                    return widget.downcast::<gtk::Box>().ok();
+                }
+                child = widget.next_sibling();
+            }
+            None
+        }) else {
+            continue;
+        };
+        while let Some(child) = badges.first_child() {
+            badges.remove(&child);
+        }
+
+        let Some(branch) = divergence.get(&ref_info.name) else {
+            badges.set_visible(false);
+            continue;
+        };
+        let mut tooltip = Vec::new();
+        for (side, css_class) in [
+            (&branch.upstream, "upstream-divergence"),
+            (&branch.main, "main-divergence"),
+        ] {
+            let Some((other, counts)) = side else {
+                continue;
+            };
+            tooltip.push(divergence_tooltip(other, *counts));
+            if let Some(text) = divergence_text(*counts) {
+                let badge = gtk::Label::new(Some(&text));
+                badge.add_css_class("divergence-badge");
+                badge.add_css_class(css_class);
+                badges.append(&badge);
+            }
+        }
+        badges.set_tooltip_text(Some(&tooltip.join("\n")));
+        badges.set_visible(badges.first_child().is_some());
+    }
+}
+
+fn poll_divergence_result(
+    rx: mpsc::Receiver<Result<HashMap<String, BranchDivergence>, git2::Error>>,
+    cancel: Arc<AtomicBool>,
+    list_box: gtk::ListBox,
+    divergence: Rc<RefCell<HashMap<String, BranchDivergence>>>,
+) {
+    if cancel.load(Ordering::Relaxed) {
+        return;
+    }
+    match rx.try_recv() {
+        Ok(Ok(result)) => {
+            apply_divergence(&list_box, &result);
+            *divergence.borrow_mut() = result;
+        }
+        Ok(Err(e)) => {
+            Logger::error(&format!("Error computing ahead/behind counts: {}", e));
+        }
+        Err(mpsc::TryRecvError::Empty) => {
+            glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+                poll_divergence_result(rx, cancel, list_box, divergence);
+            });
+        }
+        Err(mpsc::TryRecvError::Disconnected) => {}
+    }
+}
+
 /// Create a GTK row widget for a remote-tracking branch.
 fn create_remote_branch_row(branch_info: &BranchInfo) -> gtk::ListBoxRow {
     let short_name = remote_branch_short_name(&branch_info.name);
@@ -885,66 +1157,52 @@ fn create_tag_row(tag_info: &TagInfo) -> gtk::ListBoxRow {
     row
 }
 
-/// Natural comparison for strings (e.g., "branch-2" < "branch-10").
-fn natural_compare(a: &str, b: &str) -> std::cmp::Ordering {
-    let mut a_chars = a.chars().peekable();
-    let mut b_chars = b.chars().peekable();
+/// Create a GTK row widget for a stash entry.
+fn create_stash_row(stash: &StashInfo) -> gtk::ListBoxRow {
+    let row_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .margin_start(12)
+        .margin_end(12)
+        .margin_top(6)
+        .margin_bottom(6)
+        .spacing(8)
+        .build();
 
-    loop {
-        let a_done = a_chars.peek().is_none();
-        let b_done = b_chars.peek().is_none();
+    // Placeholder for checkmark (keeps alignment consistent with branches)
+    let check_icon = gtk::Image::from_icon_name("object-select-symbolic");
+    check_icon.set_pixel_size(16);
+    check_icon.set_opacity(0.0);
+    row_box.append(&check_icon);
 
-        if a_done && b_done {
-            return std::cmp::Ordering::Equal;
-        }
-        if a_done {
-            return std::cmp::Ordering::Less;
-        }
-        if b_done {
-            return std::cmp::Ordering::Greater;
-        }
+    let message_label = gtk::Label::builder().halign(gtk::Align::Start).build();
+    message_label.set_text(&stash.message);
+    message_label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    message_label.set_tooltip_text(Some(&format!(
+        "stash@{{{}}}: {}",
+        stash.index, stash.message
+    )));
+    row_box.append(&message_label);
 
-        let a_is_digit = a_chars.peek().map(|c| c.is_ascii_digit()).unwrap_or(false);
-        let b_is_digit = b_chars.peek().map(|c| c.is_ascii_digit()).unwrap_or(false);
-
-        if a_is_digit && b_is_digit {
-            // Both start with digits - compare numerically
-            let a_num: String = a_chars
-                .by_ref()
-                .take_while(|c| c.is_ascii_digit())
-                .collect();
-            let b_num: String = b_chars
-                .by_ref()
-                .take_while(|c| c.is_ascii_digit())
-                .collect();
-
-            let a_val: u64 = a_num.parse().unwrap_or(0);
-            let b_val: u64 = b_num.parse().unwrap_or(0);
-
-            match a_val.cmp(&b_val) {
-                std::cmp::Ordering::Equal => continue,
-                other => return other,
-            }
-        } else {
-            // At least one is not a digit - compare lexicographically
-            let a_char = a_chars.next().unwrap();
-            let b_char = b_chars.next().unwrap();
-
-            match a_char
-                .to_ascii_lowercase()
-                .cmp(&b_char.to_ascii_lowercase())
-            {
-                std::cmp::Ordering::Equal => {
-                    // If case-insensitive equal, compare case-sensitive
-                    match a_char.cmp(&b_char) {
-                        std::cmp::Ordering::Equal => continue,
-                        other => return other,
-                    }
-                }
-                other => return other,
-            }
-        }
-    }
+    // Time ago label (lighter color, smaller font, right-aligned)
+    let time_label = gtk::Label::builder()
+        .halign(gtk::Align::End)
+        .hexpand(true)
+        .build();
+
+    let time_ago = format_time_ago(stash.time);
+    let markup = format!(
+        "<span size='small'>{}</span>",
+        gtk::glib::markup_escape_text(&time_ago)
+    );
+    time_label.set_markup(&markup);
+    time_label.add_css_class("dim-label");
+
+    row_box.append(&time_label);
+    row_box.set_hexpand(true);
+
+    let row = gtk::ListBoxRow::new();
+    row.set_child(Some(&row_box));
+    row
 }
 
 /// Format a datetime as a relative time string (e.g., "2h", "3d", "1mo").
@@ -1049,4 +1307,12 @@ mod tests {
         let names: Vec<_> = sorted.iter().map(|branch| branch.name.as_str()).collect();
         assert_eq!(names, ["branch-1", "branch-2", "branch-10"]);
     }
+
+    #[test]
+    fn divergence_text_omits_zero_counts() {
+        let counts = |ahead, behind| AheadBehind { ahead, behind };
+        assert_eq!(divergence_text(counts(2, 5)).as_deref(), Some("↑2 ↓5"));
+        assert_eq!(divergence_text(counts(0, 3)).as_deref(), Some("↓3"));
+        assert_eq!(divergence_text(counts(0, 0)), None);
+    }
 }
@@ -0,0 +1,50 @@
+//! Optional "Changes" column of the commit list: files changed and lines
+//! added/removed per commit. Each value costs a tree diff, so it is computed
+//! lazily for the bound rows (see `lazy_column`).
+
+use std::path::Path;
+use std::rc::Rc;
+
+use super::lazy_column::{self, LazyValues};
+use crate::git;
+
+/// Lazily computed diff stats for the rows of the commit list.
+pub type ChangeStats = LazyValues<git::DiffStats>;
+
+pub fn new_change_stats() -> ChangeStats {
+    LazyValues::new(
+        || Box::new(|path: &Path, sha: &str| git::get_commit_stats(path, sha).ok()),
+        set_label,
+    )
+}
+
+fn set_label(label: &gtk::Label, stats: Option<git::DiffStats>) {
+    match stats {
+        Some(stats) => {
+            label.set_label(&format!("+{} −{}", stats.insertions, stats.deletions));
+            label.set_tooltip_text(Some(&format!(
+                "{} {} changed",
+                stats.files_changed,
+                if stats.files_changed == 1 {
+                    "file"
+                } else {
+                    "files"
+                }
+            )));
+        }
+        None => {
+            label.set_label("");
+            label.set_tooltip_text(None);
+        }
+    }
+}
+
+/// Create the "Changes" column. It follows the `show-changes-column`
+/// setting and computes stats only while visible.
+pub fn create_changes_column(
+    title: &str,
+    width: i32,
+    stats: Rc<ChangeStats>,
+) -> gtk::ColumnViewColumn {
+    lazy_column::create_lazy_column(title, width, "show-changes-column", stats)
+}
@@ -0,0 +1,425 @@
+//! Commit graph lane layout and drawing for the commit list.
+//!
+//! Lanes are assigned incrementally as commits arrive from the paging worker,
+//! so the layout stays continuous across page boundaries. Each row records the
+//! edges entering it from above and leaving it below; the drawing code only
+//! needs the row itself to render its slice of the graph.
+
+use gtk::cairo;
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Horizontal distance between lane centers, in pixels.
+const LANE_WIDTH: f64 = 14.0;
+
+/// Radius of the commit node dot, in pixels.
+const NODE_RADIUS: f64 = 4.0;
+
+/// Stroke width of graph edges, in pixels.
+const EDGE_WIDTH: f64 = 2.0;
+
+/// Lane colors, cycled by lane index (GNOME palette, mid tones so they read
+/// on both light and dark backgrounds).
+const LANE_COLORS: [(f64, f64, f64); 8] = [
+    (0.21, 0.52, 0.89), // blue
+    (0.18, 0.76, 0.49), // green
+    (0.90, 0.65, 0.04), // yellow
+    (0.78, 0.27, 0.00), // orange
+    (0.75, 0.11, 0.16), // red
+    (0.51, 0.24, 0.61), // purple
+    (0.53, 0.37, 0.24), // brown
+    (0.38, 0.21, 0.51), // violet
+];
+
+/// Graph layout for a single commit row.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GraphRow {
+    /// Lane holding this commit's node.
+    pub node_lane: usize,
+    /// Whether the commit has more than one parent.
+    pub is_merge: bool,
+    /// Edges in the upper half of the row, as (lane at top, lane at middle).
+    pub top_edges: Vec<(usize, usize)>,
+    /// Edges in the lower half of the row, as (lane at middle, lane at bottom).
+    pub bottom_edges: Vec<(usize, usize)>,
+    /// Number of lanes the row spans.
+    pub width: usize,
+}
+
+/// Incremental lane assignment over a stream of commits in revwalk order.
+///
+/// Every active lane remembers the commit it is waiting for. A commit takes
+/// over the lane waiting for it (or a free one for branch tips), hands its
+/// first parent down the same lane and opens new lanes for further parents.
+#[derive(Debug, Default)]
+pub struct LaneBuilder {
+    lanes: Vec<Option<String>>,
+}
+
+impl LaneBuilder {
+    /// Lay out the next commit and return its row.
+    pub fn push(&mut self, id: &str, parents: &[String]) -> GraphRow {
+        let node_lane = match self.lane_waiting_for(id) {
+            Some(lane) => lane,
+            None => self.free_lane(),
+        };
+
+        let mut top_edges = Vec::new();
+        let mut passthrough = Vec::new();
+        for (lane, waiting) in self.lanes.iter().enumerate() {
+            match waiting.as_deref() {
+                Some(sha) if sha == id => top_edges.push((lane, node_lane)),
+                Some(_) => {
+                    top_edges.push((lane, lane));
+                    passthrough.push(lane);
+                }
+                None => {}
+            }
+        }
+
+        // Lanes converging on this commit end here.
+        for waiting in self.lanes.iter_mut() {
+            if waiting.as_deref() == Some(id) {
+                *waiting = None;
+            }
+        }
+
+        let mut bottom_edges: Vec<(usize, usize)> =
+            passthrough.iter().map(|&lane| (lane, lane)).collect();
+
+        if let Some(first) = parents.first() {
+            match self.lane_waiting_for(first) {
+                // Another branch already leads to this parent: join it.
+                Some(lane) => bottom_edges.push((node_lane, lane)),
+                None => {
+                    self.set_lane(node_lane, first.clone());
+                    bottom_edges.push((node_lane, node_lane));
+                }
+            }
+        }
+
+        for parent in parents.iter().skip(1) {
+            let lane = match self.lane_waiting_for(parent) {
+                Some(lane) => lane,
+                None => {
+                    let lane = self.free_lane();
+                    self.set_lane(lane, parent.clone());
+                    lane
+                }
+            };
+            bottom_edges.push((node_lane, lane));
+        }
+
+        let width = top_edges
+            .iter()
+            .chain(bottom_edges.iter())
+            .flat_map(|&(a, b)| [a, b])
+            .chain(std::iter::once(node_lane))
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        while matches!(self.lanes.last(), Some(None)) {
+            self.lanes.pop();
+        }
+
+        GraphRow {
+            node_lane,
+            is_merge: parents.len() > 1,
+            top_edges,
+            bottom_edges,
+            width,
+        }
+    }
+
+    fn lane_waiting_for(&self, id: &str) -> Option<usize> {
+        self.lanes.iter().position(|w| w.as_deref() == Some(id))
+    }
+
+    fn free_lane(&mut self) -> usize {
+        match self.lanes.iter().position(|w| w.is_none()) {
+            Some(lane) => lane,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+
+    fn set_lane(&mut self, lane: usize, id: String) {
+        if lane >= self.lanes.len() {
+            self.lanes.resize(lane + 1, None);
+        }
+        self.lanes[lane] = Some(id);
+    }
+}
+
+/// Graph rows for the commits currently in the list store, index-aligned
+/// with the store, plus the builder that extends them as pages arrive.
+#[derive(Debug, Default)]
+pub struct CommitGraph {
+    builder: LaneBuilder,
+    rows: Vec<GraphRow>,
+    /// Chain rows in list order instead of following parents. Used for
+    /// path-filtered history, where neighbouring rows are rarely parent and
+    /// child and real parent links would leave lanes open forever.
+    simplified: bool,
+}
+
+impl CommitGraph {
+    /// Append the next commit in list order.
+    pub fn push(&mut self, id: &str, parents: &[String]) {
+        let row = if self.simplified {
+            // Every row waits for the same placeholder, so it lands in lane 0
+            // and links to the row above it.
+            self.builder.push("", &[String::new()])
+        } else {
+            self.builder.push(id, parents)
+        };
+        self.rows.push(row);
+    }
+
+    /// Row layout at a list position.
+    pub fn row(&self, position: usize) -> Option<&GraphRow> {
+        self.rows.get(position)
+    }
+
+    /// Forget all rows and lane state (used when a new load starts).
+    pub fn clear(&mut self) {
+        *self = Self {
+            simplified: self.simplified,
+            ..Self::default()
+        };
+    }
+
+    /// Switch between parent-following and list-order layout. Takes effect
+    /// for rows pushed after the next `clear`.
+    pub fn set_simplified(&mut self, simplified: bool) {
+        self.simplified = simplified;
+    }
+}
+
+/// Create the graph column. Rows are looked up by list position, so the
+/// store and `graph` must be appended to in lockstep.
+pub fn create_graph_column(
+    title: &str,
+    width: i32,
+    graph: Rc<RefCell<CommitGraph>>,
+) -> gtk::ColumnViewColumn {
+    let factory = gtk::SignalListItemFactory::new();
+
+    factory.connect_setup(|_factory, item| {
+        let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+        let area = gtk::DrawingArea::builder()
+            .hexpand(true)
+            .vexpand(true)
+            .content_height(24)
+            .build();
+        area.add_css_class("commit-graph");
+        item.set_child(Some(&area));
+    });
+
+    factory.connect_bind(move |_factory, item| {
+        let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+        let area = item.child().and_downcast::<gtk::DrawingArea>().unwrap();
+        let row = graph.borrow().row(item.position() as usize).cloned();
+        area.set_draw_func(move |_area, cr, width, height| {
+            if let Some(row) = row.as_ref() {
+                draw_row(cr, row, width as f64, height as f64);
+            }
+        });
+        area.queue_draw();
+    });
+
+    factory.connect_unbind(|_factory, item| {
+        let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+        if let Some(area) = item.child().and_downcast::<gtk::DrawingArea>() {
+            area.set_draw_func(|_, _, _, _| {});
+        }
+    });
+
+    let column = gtk::ColumnViewColumn::new(Some(title), Some(factory));
+    column.set_resizable(true);
+    column.set_fixed_width(width);
+    column
+}
+
+fn lane_color(lane: usize) -> (f64, f64, f64) {
+    LANE_COLORS[lane % LANE_COLORS.len()]
+}
+
+/// Draw one row's slice of the graph. Lanes are squeezed together when the
+/// row is wider than the column.
+fn draw_row(cr: &cairo::Context, row: &GraphRow, width: f64, height: f64) {
+    let spacing = if row.width as f64 * LANE_WIDTH > width {
+        (width / row.width as f64).max(2.0)
+    } else {
+        LANE_WIDTH
+    };
+    let x = |lane: usize| spacing * lane as f64 + spacing / 2.0;
+    let mid = height / 2.0;
+
+    cr.set_line_width(EDGE_WIDTH);
+    cr.set_line_cap(cairo::LineCap::Round);
+
+    for &(from, to) in &row.top_edges {
+        let (r, g, b) = lane_color(from);
+        cr.set_source_rgb(r, g, b);
+        draw_edge(cr, x(from), 0.0, x(to), mid);
+    }
+    for &(from, to) in &row.bottom_edges {
+        let (r, g, b) = lane_color(if from == row.node_lane { to } else { from });
+        cr.set_source_rgb(r, g, b);
+        draw_edge(cr, x(from), mid, x(to), height);
+    }
+
+    // Merge commits get a slightly larger dot to set them apart.
+    let radius = if row.is_merge {
+        NODE_RADIUS + 1.0
+    } else {
+        NODE_RADIUS
+    };
+    let (r, g, b) = lane_color(row.node_lane);
+    cr.set_source_rgb(r, g, b);
+    cr.arc(
+        x(row.node_lane),
+        mid,
+        radius,
+        0.0,
+        2.0 * std::f64::consts::PI,
+    );
+    let _ = cr.fill();
+}
+
+/// Draw a vertical edge, curving between lanes when they differ.
+fn draw_edge(cr: &cairo::Context, x1: f64, y1: f64, x2: f64, y2: f64) {
+    cr.move_to(x1, y1);
+    if (x1 - x2).abs() < f64::EPSILON {
+        cr.line_to(x2, y2);
+    } else {
+        let ym = (y1 + y2) / 2.0;
+        cr.curve_to(x1, ym, x2, ym, x2, y2);
+    }
+    let _ = cr.stroke();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn linear_history_stays_in_one_lane() {
+        let mut builder = LaneBuilder::default();
+        let a = builder.push("c", &ids(&["b"]));
+        let b = builder.push("b", &ids(&["a"]));
+        let c = builder.push("a", &[]);
+
+        assert_eq!(a.node_lane, 0);
+        assert_eq!(a.top_edges, vec![]);
+        assert_eq!(a.bottom_edges, vec![(0, 0)]);
+        assert_eq!(b.top_edges, vec![(0, 0)]);
+        assert_eq!(b.bottom_edges, vec![(0, 0)]);
+        assert_eq!(c.top_edges, vec![(0, 0)]);
+        assert_eq!(c.bottom_edges, vec![]);
+        assert_eq!(c.width, 1);
+    }
+
+    #[test]
+    fn merge_opens_a_lane_that_rejoins_at_the_fork() {
+        // m merges f into b; both fork from a.
+        let mut builder = LaneBuilder::default();
+        let m = builder.push("m", &ids(&["b", "f"]));
+        let b = builder.push("b", &ids(&["a"]));
+        let f = builder.push("f", &ids(&["a"]));
+        let a = builder.push("a", &[]);
+
+        assert!(m.is_merge);
+        assert_eq!(m.bottom_edges, vec![(0, 0), (0, 1)]);
+        assert_eq!(b.node_lane, 0);
+        assert_eq!(b.bottom_edges, vec![(1, 1), (0, 0)]);
+        assert_eq!(f.node_lane, 1);
+        // f's parent is already awaited on lane 0, so its line joins it.
+        assert_eq!(f.bottom_edges, vec![(0, 0), (1, 0)]);
+        assert_eq!(a.node_lane, 0);
+        assert_eq!(a.top_edges, vec![(0, 0)]);
+        assert_eq!(a.width, 1);
+    }
+
+    #[test]
+    fn unrelated_tips_get_their_own_lanes() {
+        let mut builder = LaneBuilder::default();
+        let x = builder.push("x", &ids(&["base"]));
+        let y = builder.push("y", &ids(&["other"]));
+        assert_eq!(x.node_lane, 0);
+        assert_eq!(y.node_lane, 1);
+        assert_eq!(y.top_edges, vec![(0, 0)]);
+        assert_eq!(y.bottom_edges, vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn lanes_continue_across_page_boundaries() {
+        // m2 merges t (forked from a) and m1 merges f (forked from b). With
+        // pages of three, f arrives on the page after its merge, and t's lane
+        // opens on the first page and closes on the last.
+        let history: Vec<(&str, Vec<String>)> = vec![
+            ("m2", ids(&["d", "t"])),
+            ("d", ids(&["m1"])),
+            ("m1", ids(&["c", "f"])),
+            ("f", ids(&["b"])),
+            ("c", ids(&["b"])),
+            ("b", ids(&["a"])),
+            ("t", ids(&["a"])),
+            ("a", vec![]),
+        ];
+        let mut graph = CommitGraph::default();
+        for page in history.chunks(3) {
+            for (id, parents) in page {
+                graph.push(id, parents);
+            }
+        }
+        let row = |position: usize| graph.row(position).unwrap();
+
+        // First boundary: the merge's second parent opens lane 2, and f
+        // picks it up at the top of the next page.
+        assert_eq!(row(2).bottom_edges, vec![(1, 1), (0, 0), (0, 2)]);
+        assert_eq!(row(3).node_lane, 2);
+        assert_eq!(row(3).top_edges, vec![(0, 0), (1, 1), (2, 2)]);
+
+        // t's lane passes through every row between its merge and its node.
+        for position in 1..6 {
+            assert!(row(position).top_edges.contains(&(1, 1)));
+            assert!(row(position).bottom_edges.contains(&(1, 1)));
+        }
+
+        // Second boundary: b hands a down lane 2, and t joins it there.
+        assert_eq!(row(5).bottom_edges, vec![(1, 1), (2, 2)]);
+        assert_eq!(row(6).node_lane, 1);
+        assert_eq!(row(6).top_edges, vec![(1, 1), (2, 2)]);
+        assert_eq!(row(6).bottom_edges, vec![(2, 2), (1, 2)]);
+        assert_eq!(row(7).node_lane, 2);
+        assert_eq!(row(7).top_edges, vec![(2, 2)]);
+        assert_eq!(row(7).bottom_edges, vec![]);
+    }
+
+    #[test]
+    fn simplified_graph_chains_rows_in_list_order() {
+        let mut graph = CommitGraph::default();
+        graph.set_simplified(true);
+        graph.push("c", &ids(&["x"]));
+        graph.push("a", &ids(&["y", "z"]));
+
+        let first = graph.row(0).unwrap();
+        let second = graph.row(1).unwrap();
+        assert_eq!(first.top_edges, vec![]);
+        assert_eq!(first.bottom_edges, vec![(0, 0)]);
+        assert_eq!(second.node_lane, 0);
+        assert_eq!(second.top_edges, vec![(0, 0)]);
+        assert!(!second.is_merge);
+        assert_eq!(second.width, 1);
+    }
+}
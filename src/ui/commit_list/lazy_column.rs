@@ -0,0 +1,240 @@
+//! Optional commit list columns whose values are expensive to compute per
+//! commit (a tree diff, a signature check).
+//!
+//! Values are only computed for rows GTK has bound (the visible ones plus a
+//! small margin), in batches on a background thread, and cached by SHA.
+//! Rows scrolled away before their batch starts are skipped. Nothing is
+//! computed while the column is hidden.
+
+use gtk::prelude::*;
+use gtk::{gio, glib};
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+
+use crate::APP_ID;
+use crate::git::{self, GitCommit};
+
+/// Maximum number of commits handed to one worker thread. Small enough that
+/// fast scrolling doesn't leave the worker busy with rows no longer shown.
+const BATCH_SIZE: usize = 32;
+
+/// Computes the value of one commit on the worker thread; `None` when it
+/// could not be read.
+pub type Job<T> = Box<dyn Fn(&Path, &str) -> Option<T> + Send>;
+
+/// Lazily computed values for the rows of one column.
+pub struct LazyValues<T> {
+    /// Repository the cached values belong to.
+    repo_path: RefCell<Option<PathBuf>>,
+    /// Computed values by SHA; `None` when the value could not be read.
+    cache: RefCell<HashMap<String, Option<T>>>,
+    /// Labels currently bound to a row, by the SHA of that row.
+    bound: RefCell<HashMap<String, Vec<gtk::Label>>>,
+    /// SHAs waiting for the worker, in bind order.
+    queue: RefCell<VecDeque<String>>,
+    /// Whether the column is shown; nothing is computed while it is hidden.
+    enabled: Cell<bool>,
+    /// Whether a worker batch is in flight.
+    running: Cell<bool>,
+    /// Bumped when the repository changes, so a running batch is dropped.
+    generation: Cell<u64>,
+    /// Builds the job for a batch, on the main thread (so it can read
+    /// settings).
+    make_job: Box<dyn Fn() -> Job<T>>,
+    /// Shows a value (or a missing one) in a label.
+    render: fn(&gtk::Label, Option<T>),
+}
+
+impl<T: Copy + Send + 'static> LazyValues<T> {
+    pub fn new(
+        make_job: impl Fn() -> Job<T> + 'static,
+        render: fn(&gtk::Label, Option<T>),
+    ) -> Self {
+        Self {
+            repo_path: RefCell::new(None),
+            cache: RefCell::new(HashMap::new()),
+            bound: RefCell::new(HashMap::new()),
+            queue: RefCell::new(VecDeque::new()),
+            enabled: Cell::new(false),
+            running: Cell::new(false),
+            generation: Cell::new(0),
+            make_job: Box::new(make_job),
+            render,
+        }
+    }
+
+    /// Use the values of `path`, dropping the cache if it held another
+    /// repository's. Commits never change, so a refresh keeps it.
+    pub fn set_repo_path(&self, path: &Path) {
+        if self.repo_path.borrow().as_deref() == Some(path) {
+            return;
+        }
+        *self.repo_path.borrow_mut() = Some(path.to_path_buf());
+        self.cache.borrow_mut().clear();
+        self.queue.borrow_mut().clear();
+        self.generation.set(self.generation.get() + 1);
+        self.running.set(false);
+    }
+
+    /// Show or hide the column. Showing it computes the rows already bound.
+    fn set_enabled(self: &Rc<Self>, enabled: bool) {
+        self.enabled.set(enabled);
+        if enabled {
+            let shas: Vec<String> = self.bound.borrow().keys().cloned().collect();
+            self.queue.borrow_mut().extend(shas);
+            self.start_batch();
+        }
+    }
+
+    fn bind(self: &Rc<Self>, sha: &str, label: &gtk::Label) {
+        self.bound
+            .borrow_mut()
+            .entry(sha.to_string())
+            .or_default()
+            .push(label.clone());
+        let cached = self.cache.borrow().get(sha).copied();
+        match cached {
+            Some(value) => (self.render)(label, value),
+            None => {
+                (self.render)(label, None);
+                self.queue.borrow_mut().push_back(sha.to_string());
+                self.start_batch();
+            }
+        }
+    }
+
+    fn unbind(&self, label: &gtk::Label) {
+        self.bound.borrow_mut().retain(|_, labels| {
+            labels.retain(|l| l != label);
+            !labels.is_empty()
+        });
+    }
+
+    /// Hand the queued SHAs that are still bound and not yet computed to a
+    /// worker thread, unless one is already running.
+    fn start_batch(self: &Rc<Self>) {
+        if self.running.get() || !self.enabled.get() {
+            return;
+        }
+        let Some(repo_path) = self.repo_path.borrow().clone() else {
+            return;
+        };
+        let mut batch: Vec<String> = Vec::new();
+        {
+            let mut queue = self.queue.borrow_mut();
+            let bound = self.bound.borrow();
+            let cache = self.cache.borrow();
+            while batch.len() < BATCH_SIZE {
+                let Some(sha) = queue.pop_front() else { break };
+                if bound.contains_key(&sha) && !cache.contains_key(&sha) && !batch.contains(&sha) {
+                    batch.push(sha);
+                }
+            }
+        }
+        if batch.is_empty() {
+            return;
+        }
+
+        self.running.set(true);
+        let job = (self.make_job)();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for sha in batch {
+                let value = job(&repo_path, &sha);
+                if tx.send((sha, value)).is_err() {
+                    return;
+                }
+            }
+        });
+        poll_batch(rx, self.clone(), self.generation.get());
+    }
+
+    fn apply(&self, sha: String, value: Option<T>) {
+        if let Some(labels) = self.bound.borrow().get(&sha) {
+            for label in labels {
+                (self.render)(label, value);
+            }
+        }
+        self.cache.borrow_mut().insert(sha, value);
+    }
+}
+
+fn poll_batch<T: Copy + Send + 'static>(
+    rx: mpsc::Receiver<(String, Option<T>)>,
+    values: Rc<LazyValues<T>>,
+    expected_generation: u64,
+) {
+    loop {
+        if values.generation.get() != expected_generation {
+            return;
+        }
+        match rx.try_recv() {
+            Ok((sha, result)) => values.apply(sha, result),
+            Err(mpsc::TryRecvError::Empty) => {
+                glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+                    poll_batch(rx, values, expected_generation);
+                });
+                return;
+            }
+            Err(mpsc::TryRecvError::Disconnected) => {
+                values.running.set(false);
+                values.start_batch();
+                return;
+            }
+        }
+    }
+}
+
+/// Create a lazily computed column. Its visibility follows the boolean
+/// GSettings key `visible_key`.
+pub fn create_lazy_column<T: Copy + Send + 'static>(
+    title: &str,
+    width: i32,
+    visible_key: &str,
+    values: Rc<LazyValues<T>>,
+) -> gtk::ColumnViewColumn {
+    let factory = gtk::SignalListItemFactory::new();
+
+    factory.connect_setup(|_factory, item| {
+        let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+        let label = gtk::Label::builder().xalign(1.0).build();
+        label.add_css_class("numeric");
+        label.add_css_class("dim-label");
+        item.set_child(Some(&label));
+    });
+
+    let values_for_bind = values.clone();
+    factory.connect_bind(move |_factory, item| {
+        let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+        let label = item.child().and_downcast::<gtk::Label>().unwrap();
+        let entry_obj = item.item().and_downcast::<glib::BoxedAnyObject>().unwrap();
+        let commit: Ref<GitCommit> = entry_obj.borrow();
+        if commit.id == git::WORKING_TREE_ID {
+            (values_for_bind.render)(&label, None);
+            return;
+        }
+        values_for_bind.bind(&commit.id, &label);
+    });
+
+    let values_for_unbind = values.clone();
+    factory.connect_unbind(move |_factory, item| {
+        let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+        if let Some(label) = item.child().and_downcast::<gtk::Label>() {
+            values_for_unbind.unbind(&label);
+        }
+    });
+
+    let column = gtk::ColumnViewColumn::new(Some(title), Some(factory));
+    column.set_resizable(true);
+    column.set_fixed_width(width);
+
+    gio::Settings::new(APP_ID)
+        .bind(visible_key, &column, "visible")
+        .build();
+    values.set_enabled(column.is_visible());
+    column.connect_visible_notify(move |column| values.set_enabled(column.is_visible()));
+    column
+}
@@ -1,7 +1,13 @@
 //! Commit list UI component for displaying git commits with infinite scroll.
 //!
 //! This module provides the `CommitList` widget which displays a paginated,
-//! scrollable list of git commits with columns for message, author, SHA, and date.
+//! scrollable list of git commits with columns for graph, message, author, SHA, and date,
+//! plus optional columns with the size of each commit's changes and its signature status.
+
+mod changes;
+mod graph;
+mod lazy_column;
+mod signatures;
 
 use gtk::prelude::*;
 use gtk::{gio, glib};
@@ -16,6 +22,9 @@ use std::time::Instant;
 use crate::git::{self, GitCommit};
 use crate::logger::Logger;
 use crate::ui::{Entry, GridCell};
+use changes::ChangeStats;
+use graph::CommitGraph;
+use signatures::SignatureStates;
 
 /// Number of commits to load per page during infinite scroll.
 const COMMIT_PAGE_SIZE: usize = 200;
@@ -67,9 +76,12 @@ pub struct CommitPagingState {
 /// A scrollable list widget displaying git commits with infinite scroll support.
 ///
 /// The widget displays commits in a column view with:
+/// - Graph lanes
 /// - Commit message (with optional tag chips)
 /// - Author name
 /// - SHA (abbreviated)
+/// - Changes (lines added/removed; hidden unless enabled in preferences)
+/// - Signature (verification status; hidden unless enabled in preferences)
 /// - Date
 ///
 /// Commits are loaded on-demand as the user scrolls, with pages loaded
@@ -92,6 +104,14 @@ pub struct CommitList {
     upstream: Rc<RefCell<Option<(String, String)>>>,
     /// Primary branch head: (branch_name, commit_sha) to show a chip when viewing another branch.
     branch_head: Rc<RefCell<Option<(String, String)>>>,
+    /// Graph lane layout, index-aligned with `store`.
+    graph: Rc<RefCell<CommitGraph>>,
+    /// Path the list is limited to (file or folder history), if any.
+    path_filter: Rc<RefCell<Option<String>>>,
+    /// Diff stats shown in the "Changes" column, computed for bound rows only.
+    change_stats: Rc<ChangeStats>,
+    /// Signature checks shown in the "Signature" column, for bound rows only.
+    signature_states: Rc<SignatureStates>,
 }
 
 impl CommitList {
@@ -104,8 +124,10 @@ impl CommitList {
         let tags: Rc<RefCell<HashMap<String, Vec<String>>>> = Rc::new(RefCell::new(HashMap::new()));
         let upstream: Rc<RefCell<Option<(String, String)>>> = Rc::new(RefCell::new(None));
         let branch_head: Rc<RefCell<Option<(String, String)>>> = Rc::new(RefCell::new(None));
+        let graph = Rc::new(RefCell::new(CommitGraph::default()));
 
         // Create column factories
+        let graph_column = graph::create_graph_column("Graph", 80, graph.clone());
         let message_column = create_message_column_with_tags(
             "Message",
             600,
@@ -115,11 +137,19 @@ impl CommitList {
         );
         let author_column = create_column("Author", 150, false, |c: &GitCommit| c.author.clone());
         let sha_column = create_column("SHA", 120, false, |c: &GitCommit| short_sha(&c.id));
+        let change_stats = Rc::new(changes::new_change_stats());
+        let changes_column = changes::create_changes_column("Changes", 110, change_stats.clone());
+        let signature_states = Rc::new(signatures::new_signature_states());
+        let signature_column =
+            signatures::create_signature_column("Signature", 100, signature_states.clone());
         let date_column = create_column("Date", 200, false, |c: &GitCommit| c.date.clone());
 
+        column_view.append_column(&graph_column);
         column_view.append_column(&message_column);
         column_view.append_column(&author_column);
         column_view.append_column(&sha_column);
+        column_view.append_column(&changes_column);
+        column_view.append_column(&signature_column);
         column_view.append_column(&date_column);
 
         let scrolled_window = gtk::ScrolledWindow::builder().build();
@@ -132,6 +162,7 @@ impl CommitList {
 
         // Infinite scroll: request the next page when nearing the bottom.
         setup_infinite_scroll(&scrolled_window, &paging_state);
+        attach_commit_context_menu(&column_view, &store, &selection_model);
 
         Self {
             widget: scrolled_window,
@@ -142,6 +173,10 @@ impl CommitList {
             tags,
             upstream,
             branch_head,
+            graph,
+            path_filter: Rc::new(RefCell::new(None)),
+            change_stats,
+            signature_states,
         }
     }
 
@@ -161,6 +196,8 @@ impl CommitList {
         initial_selection_sha: Option<String>,
         on_first_page_branch: impl Fn(String) + 'static,
     ) {
+        self.change_stats.set_repo_path(&path);
+        self.signature_states.set_repo_path(&path);
         let on_first_page_branch: Rc<dyn Fn(String)> = Rc::new(on_first_page_branch);
         start_commit_paging(
             &self.widget,
@@ -168,13 +205,21 @@ impl CommitList {
             &self.selection_model,
             &self.paging_state,
             &self.generation_counter,
+            &self.graph,
             path,
             branch_ref,
+            self.path_filter.borrow().clone(),
             initial_selection_sha,
             on_first_page_branch,
         );
     }
 
+    /// Limit the list to commits touching `path` (following renames for a
+    /// single file). Takes effect on the next `load_commits`.
+    pub fn set_path_filter(&self, path: Option<String>) {
+        *self.path_filter.borrow_mut() = path;
+    }
+
     /// Get a clone of the paging state for external access.
     pub fn paging_state(&self) -> Rc<RefCell<CommitPagingState>> {
         self.paging_state.clone()
@@ -194,6 +239,11 @@ impl CommitList {
         }
     }
 
+    /// Names of the tags pointing at `sha`, from the map set by `set_tags`.
+    pub fn tags_of(&self, sha: &str) -> Vec<String> {
+        self.tags.borrow().get(sha).cloned().unwrap_or_default()
+    }
+
     /// Set the upstream branch for the current ref. When set, a chip is shown on the commit
     /// where the upstream points. Pass `None` to hide the upstream chip.
     pub fn set_upstream(&self, upstream: Option<(String, String)>) {
@@ -229,9 +279,26 @@ impl CommitList {
             .and_then(|&idx| commit_sha_at(&self.store, idx))
     }
 
+    /// SHAs of all selected commits, newest first (list order). The
+    /// uncommitted-changes row is skipped.
+    pub fn selected_commit_shas(&self) -> Vec<String> {
+        let skip = working_tree_rows(&self.store);
+        self.selected_indices()
+            .into_iter()
+            .filter(|&idx| idx >= skip)
+            .filter_map(|idx| commit_sha_at(&self.store, idx))
+            .collect()
+    }
+
     /// Return (oldest_sha, newest_sha, count) when multiple commits are selected.
+    /// The uncommitted-changes row is not part of any range.
     pub fn selected_commit_range(&self) -> Option<(String, String, usize)> {
-        let indices = self.selected_indices();
+        let skip = working_tree_rows(&self.store);
+        let indices: Vec<u32> = self
+            .selected_indices()
+            .into_iter()
+            .filter(|&idx| idx >= skip)
+            .collect();
         if indices.len() <= 1 {
             return None;
         }
@@ -242,6 +309,34 @@ impl CommitList {
         Some((oldest_sha, newest_sha, indices.len()))
     }
 
+    /// Select the commit with `sha` and scroll it into view. If it has not
+    /// been loaded yet, further pages are loaded until it appears (bounded by
+    /// `MAX_PENDING_SELECT_PAGES`, like a refresh that preserves selection).
+    pub fn select_commit(&self, sha: &str) {
+        if let Some(idx) = find_commit_index_by_sha(&self.store, sha) {
+            self.selection_model.select_item(idx, true);
+            crate::search::SearchHandler::scroll_to_item(&self.widget, &self.selection_model, idx);
+            return;
+        }
+
+        let mut st = self.paging_state.borrow_mut();
+        if st.done {
+            Logger::info(&format!(
+                "Commit {} is not in the current list",
+                short_sha(sha)
+            ));
+            return;
+        }
+        st.pending_select_sha = Some(sha.to_string());
+        st.pending_select_pages_loaded = 0;
+        if !st.is_loading
+            && let Some(tx) = st.request_tx.clone()
+        {
+            st.is_loading = true;
+            let _ = tx.send(CommitLoadRequest::NextPage);
+        }
+    }
+
     /// Clear all commits and stop any in-flight loading.
     pub fn clear(&self) {
         // Invalidate any in-flight paging generation and stop the worker.
@@ -266,8 +361,10 @@ impl CommitList {
         self.tags.borrow_mut().clear();
         *self.upstream.borrow_mut() = None;
         *self.branch_head.borrow_mut() = None;
+        *self.path_filter.borrow_mut() = None;
 
         // Clear UI list + selection.
+        self.graph.borrow_mut().clear();
         self.store.remove_all();
         self.selection_model.unselect_all();
     }
@@ -294,6 +391,13 @@ enum CommitLoadResponse {
         done: bool,
         is_first_page: bool,
     },
+    /// The uncommitted-changes row (`None` when the tree is clean or the
+    /// row doesn't apply). Sent once, right after the first page, so reading
+    /// the working tree status doesn't delay the first commits.
+    WorkingTree {
+        generation: u64,
+        commit: Option<GitCommit>,
+    },
     /// An error occurred while loading commits.
     Error { generation: u64, message: String },
 }
@@ -490,6 +594,11 @@ fn create_message_column_with_tags(
                 if let Some(label) = widget.downcast_ref::<gtk::Inscription>() {
                     let first_line = commit.message.lines().next().unwrap_or("").trim();
                     label.set_text(Some(first_line));
+                    if commit.is_working_tree() {
+                        label.add_css_class("uncommitted-changes");
+                    } else {
+                        label.remove_css_class("uncommitted-changes");
+                    }
                 }
             }
             child = widget.next_sibling();
@@ -553,6 +662,47 @@ fn setup_infinite_scroll(
         });
 }
 
+/// Right-click menu acting on the selected commits (patch export).
+fn attach_commit_context_menu(
+    column_view: &gtk::ColumnView,
+    store: &gio::ListStore,
+    selection_model: &gtk::MultiSelection,
+) {
+    let menu = gio::Menu::new();
+    menu.append(Some("Copy as Patch"), Some("win.copy-patch"));
+    menu.append(Some("Export as Patches…"), Some("win.export-patches"));
+    let popover = gtk::PopoverMenu::from_model(Some(&menu));
+    popover.set_parent(column_view);
+    popover.set_has_arrow(true);
+
+    let right_click = gtk::GestureClick::new();
+    right_click.set_button(3);
+    let store = store.clone();
+    let selection_model = selection_model.clone();
+    right_click.connect_pressed(move |_, _, x, y| {
+        // Only real commits can be exported, not the uncommitted-changes row.
+        let selection = selection_model.selection();
+        if selection.is_empty() || selection.maximum() < working_tree_rows(&store) {
+            return;
+        }
+        popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    });
+    column_view.add_controller(right_click);
+}
+
+/// Number of rows (0 or 1) above the first real commit, i.e. whether the
+/// list starts with the uncommitted-changes row.
+fn working_tree_rows(store: &gio::ListStore) -> u32 {
+    commit_sha_at(store, 0).map_or(0, |id| u32::from(id == git::WORKING_TREE_ID))
+}
+
+/// Store row of the commit at `commit_index` in revwalk order (as used by
+/// search results), accounting for the uncommitted-changes row.
+pub fn commit_row(store: &gio::ListStore, commit_index: u32) -> u32 {
+    commit_index + working_tree_rows(store)
+}
+
 /// Find the index of a commit by SHA in the store. Returns `None` if not found.
 fn commit_sha_at(store: &gio::ListStore, idx: u32) -> Option<String> {
     let item = store.item(idx)?;
@@ -576,7 +726,33 @@ fn find_commit_index_by_sha(store: &gio::ListStore, sha: &str) -> Option<u32> {
     None
 }
 
+/// Put the uncommitted-changes row above the loaded commits. The graph is
+/// laid out again from the top and the rows below are rebound, as their
+/// positions shift.
+fn insert_working_tree_row(
+    store: &gio::ListStore,
+    graph: &Rc<RefCell<CommitGraph>>,
+    row: GitCommit,
+) {
+    {
+        let mut graph = graph.borrow_mut();
+        graph.clear();
+        graph.push(&row.id, &row.parents);
+        for i in 0..store.n_items() {
+            let Some(boxed) = store.item(i).and_downcast::<glib::BoxedAnyObject>() else {
+                continue;
+            };
+            let commit: Ref<GitCommit> = boxed.borrow();
+            graph.push(&commit.id, &commit.parents);
+        }
+    }
+    store.insert(0, &glib::BoxedAnyObject::new(row));
+    let n_items = store.n_items();
+    store.items_changed(0, n_items, n_items);
+}
+
 /// Poll for commit page results from the background worker.
+#[allow(clippy::too_many_arguments)]
 fn poll_commit_pages(
     rx: mpsc::Receiver<CommitLoadResponse>,
     expected_generation: u64,
@@ -584,6 +760,7 @@ fn poll_commit_pages(
     store: gio::ListStore,
     selection_model: gtk::MultiSelection,
     paging_state: Rc<std::cell::RefCell<CommitPagingState>>,
+    graph: Rc<RefCell<CommitGraph>>,
     repo_path: PathBuf,
     on_first_page_branch: Rc<dyn Fn(String)>,
 ) {
@@ -606,12 +783,9 @@ fn poll_commit_pages(
                 }
 
                 for commit in commits {
-                    store.append(&glib::BoxedAnyObject::new(GitCommit {
-                        id: commit.id,
-                        message: commit.message,
-                        author: commit.author,
-                        date: commit.date,
-                    }));
+                    // Lay out the graph row first so it exists when the row binds.
+                    graph.borrow_mut().push(&commit.id, &commit.parents);
+                    store.append(&glib::BoxedAnyObject::new(commit));
                 }
 
                 {
@@ -623,10 +797,13 @@ fn poll_commit_pages(
                 // Try to honor any pending selection SHA before the default
                 // "select first commit on first page" behavior. This is what
                 // preserves the user's selected commit across a refresh.
-                let had_pending_target = paging_state.borrow().pending_select_sha.is_some();
-                let mut found_pending_target = false;
+                // A pending uncommitted-changes row is resolved once the
+                // `WorkingTree` response arrives.
                 let pending_sha = paging_state.borrow().pending_select_sha.clone();
-                if let Some(sha) = pending_sha {
+                let awaiting_working_tree = pending_sha.as_deref() == Some(git::WORKING_TREE_ID);
+                let had_pending_target = pending_sha.is_some();
+                let mut found_pending_target = false;
+                if let Some(sha) = pending_sha.filter(|_| !awaiting_working_tree) {
                     if let Some(idx) = find_commit_index_by_sha(&store, &sha) {
                         selection_model.select_item(idx, true);
                         crate::search::SearchHandler::scroll_to_item(
@@ -665,7 +842,7 @@ fn poll_commit_pages(
                 // If a target was pending and we still haven't found it,
                 // either auto-load the next page or fall back to selecting
                 // the first commit if we've exhausted our budget.
-                if had_pending_target && !found_pending_target {
+                if had_pending_target && !found_pending_target && !awaiting_working_tree {
                     let mut st = paging_state.borrow_mut();
                     if st.pending_select_sha.is_some() {
                         st.pending_select_pages_loaded =
@@ -687,6 +864,33 @@ fn poll_commit_pages(
                     }
                 }
             }
+            Ok(CommitLoadResponse::WorkingTree { generation, commit }) => {
+                if generation != expected_generation {
+                    continue;
+                }
+                let inserted = commit.is_some();
+                if let Some(commit) = commit {
+                    insert_working_tree_row(&store, &graph, commit);
+                }
+
+                let mut st = paging_state.borrow_mut();
+                if st.pending_select_sha.as_deref() == Some(git::WORKING_TREE_ID) {
+                    // The row was selected before a refresh. If the tree is
+                    // clean now, select the newest commit instead.
+                    st.pending_select_sha = None;
+                    drop(st);
+                    if inserted {
+                        selection_model.select_item(0, true);
+                        crate::search::SearchHandler::scroll_to_item(
+                            &scrolled_window,
+                            &selection_model,
+                            0,
+                        );
+                    } else if selection_model.selection().is_empty() && store.n_items() > 0 {
+                        selection_model.select_item(0, true);
+                    }
+                }
+            }
             Ok(CommitLoadResponse::Error {
                 generation,
                 message,
@@ -714,6 +918,7 @@ fn poll_commit_pages(
     let store_clone = store.clone();
     let selection_model_clone = selection_model.clone();
     let paging_state_clone = paging_state.clone();
+    let graph_clone = graph.clone();
     let repo_path_clone = repo_path.clone();
     let on_first_page_branch_clone = on_first_page_branch.clone();
     let source_id = glib::timeout_add_local_once(std::time::Duration::from_millis(30), move || {
@@ -724,6 +929,7 @@ fn poll_commit_pages(
             store_clone,
             selection_model_clone,
             paging_state_clone,
+            graph_clone,
             repo_path_clone,
             on_first_page_branch_clone,
         );
@@ -739,12 +945,19 @@ fn start_commit_paging(
     selection_model: &gtk::MultiSelection,
     paging_state: &Rc<std::cell::RefCell<CommitPagingState>>,
     generation_counter: &Arc<AtomicU64>,
+    graph: &Rc<RefCell<CommitGraph>>,
     path: PathBuf,
     branch_ref: String,
+    path_filter: Option<String>,
     initial_selection_sha: Option<String>,
     on_first_page_branch: Rc<dyn Fn(String)>,
 ) {
     // Clear existing items + cancel any in-flight worker.
+    {
+        let mut graph = graph.borrow_mut();
+        graph.set_simplified(path_filter.is_some());
+        graph.clear();
+    }
     store.remove_all();
     selection_model.unselect_all();
     {
@@ -783,6 +996,7 @@ fn start_commit_paging(
         store.clone(),
         selection_model.clone(),
         paging_state.clone(),
+        graph.clone(),
         path.clone(),
         on_first_page_branch,
     );
@@ -806,7 +1020,15 @@ fn start_commit_paging(
             }
         };
 
-        let opts = git::CommitQueryOptions::for_branch(&commit_ref);
+        // Uncommitted changes sit on top of the checked-out branch, so they are
+        // only shown for the unfiltered history.
+        let show_working_tree = path_filter.is_none();
+
+        let mut opts = git::CommitQueryOptions::for_branch(&commit_ref);
+        if let Some(path) = path_filter {
+            opts.pathspecs = vec![path];
+            opts.follow_renames = true;
+        }
         let mut walker = match git::CommitWalker::new(&repo, opts) {
             Ok(w) => w,
             Err(e) => {
@@ -846,6 +1068,18 @@ fn start_commit_paging(
                         is_first_page,
                     });
 
+                    if is_first_page {
+                        let commit = if show_working_tree {
+                            git::working_tree_commit(&repo, &commit_ref)
+                        } else {
+                            None
+                        };
+                        let _ = res_tx.send(CommitLoadResponse::WorkingTree {
+                            generation: expected_generation,
+                            commit,
+                        });
+                    }
+
                     is_first_page = false;
                     if done {
                         break;
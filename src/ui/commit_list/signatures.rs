@@ -0,0 +1,62 @@
+//! Optional "Signature" column of the commit list: whether each commit's
+//! signature verifies. Checking runs an external verifier per signed
+//! commit, so it is done lazily for the bound rows (see `lazy_column`).
+
+use gtk::prelude::*;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::lazy_column::{self, LazyValues};
+use crate::git::{self, SignatureStatus};
+use crate::ui::signature_settings;
+
+/// CSS classes of the signature badges, one per status.
+const STATUS_CLASSES: &[&str] = &[
+    "signature-verified",
+    "signature-unverified",
+    "signature-unknown",
+];
+
+/// Lazily checked commit signatures; `Some(None)` for unsigned commits.
+pub type SignatureStates = LazyValues<Option<SignatureStatus>>;
+
+pub fn new_signature_states() -> SignatureStates {
+    LazyValues::new(
+        || {
+            // Read per batch so changed preferences apply to new rows.
+            let verifier = signature_settings::verifier();
+            Box::new(move |path: &Path, sha: &str| {
+                let checks = git::get_signatures(path.to_str()?, sha, &[], &verifier).ok()?;
+                Some(checks.first().map(|check| check.status))
+            })
+        },
+        set_label,
+    )
+}
+
+fn set_label(label: &gtk::Label, status: Option<Option<SignatureStatus>>) {
+    for class in STATUS_CLASSES {
+        label.remove_css_class(class);
+    }
+    let (text, class) = match status.flatten() {
+        Some(SignatureStatus::Verified) => ("Verified", STATUS_CLASSES[0]),
+        Some(SignatureStatus::Unverified) => ("Unverified", STATUS_CLASSES[1]),
+        Some(SignatureStatus::Unknown) => ("Unknown", STATUS_CLASSES[2]),
+        None => {
+            label.set_label("");
+            return;
+        }
+    };
+    label.set_label(text);
+    label.add_css_class(class);
+}
+
+/// Create the "Signature" column. It follows the `show-signature-column`
+/// setting and checks signatures only while visible.
+pub fn create_signature_column(
+    title: &str,
+    width: i32,
+    states: Rc<SignatureStates>,
+) -> gtk::ColumnViewColumn {
+    lazy_column::create_lazy_column(title, width, "show-signature-column", states)
+}
@@ -0,0 +1,303 @@
+//! File tree of a single commit, listed in a `gtk::TreeListModel`.
+//!
+//! Directories are read from git only when expanded, on a background thread,
+//! and cached until another commit is shown.
+
+use gtk::{gio, glib, prelude::*};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc;
+
+use crate::git::{self, TreeEntry, TreeEntryKind};
+use crate::logger::Logger;
+
+/// Repository and commit whose tree is shown.
+#[derive(Clone, PartialEq)]
+struct TreeSource {
+    repo_path: PathBuf,
+    commit_sha: String,
+}
+
+#[derive(Default)]
+struct TreeState {
+    source: Option<TreeSource>,
+    /// Bumped whenever `source` changes so results for an older commit are
+    /// dropped.
+    generation: u64,
+    /// Directory listings by directory path ("" for the root).
+    cache: HashMap<String, Vec<TreeEntry>>,
+}
+
+type FileCallback = Rc<RefCell<Option<Box<dyn Fn(&str)>>>>;
+
+#[derive(Clone)]
+pub struct FileTree {
+    /// Root widget: the tree, or a placeholder while nothing is shown.
+    pub widget: gtk::Stack,
+    list_view: gtk::ListView,
+    state: Rc<RefCell<TreeState>>,
+    on_file_activated: FileCallback,
+}
+
+impl Default for FileTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileTree {
+    pub fn new() -> Self {
+        let state: Rc<RefCell<TreeState>> = Rc::default();
+        let on_file_activated: FileCallback = Rc::new(RefCell::new(None));
+
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let icon = gtk::Image::new();
+            let label = gtk::Label::builder()
+                .xalign(0.0)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            let row = gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(6)
+                .build();
+            row.append(&icon);
+            row.append(&label);
+            let expander = gtk::TreeExpander::new();
+            expander.set_child(Some(&row));
+            item.set_child(Some(&expander));
+        });
+        factory.connect_bind(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let Some(expander) = item.child().and_downcast::<gtk::TreeExpander>() else {
+                return;
+            };
+            let Some(row) = item.item().and_downcast::<gtk::TreeListRow>() else {
+                return;
+            };
+            expander.set_list_row(Some(&row));
+            let Some(entry) = row.item().and_downcast::<glib::BoxedAnyObject>() else {
+                return;
+            };
+            let entry = entry.borrow::<TreeEntry>();
+            let Some(content) = expander.child() else {
+                return;
+            };
+            if let Some(icon) = content.first_child().and_downcast::<gtk::Image>() {
+                icon.set_icon_name(Some(entry_icon(entry.kind)));
+            }
+            if let Some(label) = content.last_child().and_downcast::<gtk::Label>() {
+                label.set_text(&entry.name);
+            }
+            content.set_tooltip_text(Some(&entry.path));
+        });
+        factory.connect_unbind(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            if let Some(expander) = item.child().and_downcast::<gtk::TreeExpander>() {
+                expander.set_list_row(None);
+            }
+        });
+
+        let list_view = gtk::ListView::builder()
+            .factory(&factory)
+            .vexpand(true)
+            .build();
+        list_view.add_css_class("file-tree");
+
+        let on_file_activated_for_list = on_file_activated.clone();
+        list_view.connect_activate(move |view, position| {
+            let Some(row) = view
+                .model()
+                .and_then(|m| m.item(position))
+                .and_downcast::<gtk::TreeListRow>()
+            else {
+                return;
+            };
+            let Some(entry) = row.item().and_downcast::<glib::BoxedAnyObject>() else {
+                return;
+            };
+            let entry = entry.borrow::<TreeEntry>();
+            match entry.kind {
+                TreeEntryKind::Directory => row.set_expanded(!row.is_expanded()),
+                TreeEntryKind::File | TreeEntryKind::Symlink => {
+                    if let Some(callback) = on_file_activated_for_list.borrow().as_ref() {
+                        callback(&entry.path);
+                    }
+                }
+                TreeEntryKind::Submodule => {}
+            }
+        });
+
+        let scrolled = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .child(&list_view)
+            .build();
+
+        let widget = gtk::Stack::new();
+        widget.add_named(&scrolled, Some("tree"));
+
+        let tree = Self {
+            widget,
+            list_view,
+            state,
+            on_file_activated,
+        };
+        tree.clear("Select a commit to browse its files");
+        tree
+    }
+
+    /// Show the tree of `commit_sha`. Does nothing if it is already shown.
+    pub fn show_commit(&self, repo_path: PathBuf, commit_sha: &str) {
+        let source = TreeSource {
+            repo_path,
+            commit_sha: commit_sha.to_string(),
+        };
+        if self.state.borrow().source.as_ref() == Some(&source) {
+            return;
+        }
+        {
+            let mut state = self.state.borrow_mut();
+            state.source = Some(source);
+            state.generation += 1;
+            state.cache.clear();
+        }
+
+        let root = gio::ListStore::new::<glib::BoxedAnyObject>();
+        let state_for_children = self.state.clone();
+        let model = gtk::TreeListModel::new(root.clone(), false, false, move |item| {
+            let entry = item.downcast_ref::<glib::BoxedAnyObject>()?;
+            let entry = entry.borrow::<TreeEntry>();
+            if entry.kind != TreeEntryKind::Directory {
+                return None;
+            }
+            let children = gio::ListStore::new::<glib::BoxedAnyObject>();
+            // The tree list also creates (and drops) child models just to
+            // learn whether a row can expand, so only load directories whose
+            // model is still alive once the main loop is idle again.
+            let weak = children.downgrade();
+            let state = state_for_children.clone();
+            let dir = entry.path.clone();
+            glib::idle_add_local_once(move || {
+                if let Some(children) = weak.upgrade() {
+                    load_directory(&state, &children, dir);
+                }
+            });
+            Some(children.upcast())
+        });
+        self.list_view
+            .set_model(Some(&gtk::SingleSelection::new(Some(model))));
+        load_directory(&self.state, &root, String::new());
+        self.widget.set_visible_child_name("tree");
+    }
+
+    /// The commit whose tree is shown.
+    pub fn commit_sha(&self) -> Option<String> {
+        self.state
+            .borrow()
+            .source
+            .as_ref()
+            .map(|s| s.commit_sha.clone())
+    }
+
+    /// Forget the shown commit and show `placeholder` instead.
+    pub fn clear(&self, placeholder: &str) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.source = None;
+            state.generation += 1;
+            state.cache.clear();
+        }
+        self.list_view.set_model(None::<&gtk::SingleSelection>);
+        if let Some(old) = self.widget.child_by_name("placeholder") {
+            self.widget.remove(&old);
+        }
+        let widget =
+            crate::ui::placeholder::centered(crate::ui::placeholder::ICON_INFO, placeholder);
+        self.widget.add_named(&widget, Some("placeholder"));
+        self.widget.set_visible_child_name("placeholder");
+    }
+
+    /// Call `callback` with the file's path when a file row is activated.
+    pub fn on_file_activated<F: Fn(&str) + 'static>(&self, callback: F) {
+        *self.on_file_activated.borrow_mut() = Some(Box::new(callback));
+    }
+}
+
+fn entry_icon(kind: TreeEntryKind) -> &'static str {
+    match kind {
+        TreeEntryKind::Directory => "folder-symbolic",
+        TreeEntryKind::File => "text-x-generic-symbolic",
+        TreeEntryKind::Symlink => "emblem-symbolic-link-symbolic",
+        TreeEntryKind::Submodule => "folder-remote-symbolic",
+    }
+}
+
+fn fill_store(store: &gio::ListStore, entries: &[TreeEntry]) {
+    let items: Vec<glib::BoxedAnyObject> = entries
+        .iter()
+        .cloned()
+        .map(glib::BoxedAnyObject::new)
+        .collect();
+    store.splice(0, store.n_items(), &items);
+}
+
+/// Fill `store` with the entries of `dir`, from the cache or from a
+/// background thread.
+fn load_directory(state: &Rc<RefCell<TreeState>>, store: &gio::ListStore, dir: String) {
+    let (source, generation) = {
+        let state = state.borrow();
+        if let Some(entries) = state.cache.get(&dir) {
+            fill_store(store, entries);
+            return;
+        }
+        let Some(source) = state.source.clone() else {
+            return;
+        };
+        (source, state.generation)
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let dir_for_thread = dir.clone();
+    std::thread::spawn(move || {
+        let result = git::get_tree_entries(
+            source.repo_path.to_str().unwrap(),
+            &source.commit_sha,
+            &dir_for_thread,
+        );
+        let _ = tx.send(result);
+    });
+    poll_directory_result(rx, state.clone(), store.downgrade(), dir, generation);
+}
+
+fn poll_directory_result(
+    rx: mpsc::Receiver<Result<Vec<TreeEntry>, git2::Error>>,
+    state: Rc<RefCell<TreeState>>,
+    store: glib::WeakRef<gio::ListStore>,
+    dir: String,
+    generation: u64,
+) {
+    if state.borrow().generation != generation {
+        return;
+    }
+    match rx.try_recv() {
+        Ok(Ok(entries)) => {
+            if let Some(store) = store.upgrade() {
+                fill_store(&store, &entries);
+            }
+            state.borrow_mut().cache.insert(dir, entries);
+        }
+        Ok(Err(e)) => {
+            Logger::error(&format!("Error listing {}: {}", dir, e));
+        }
+        Err(mpsc::TryRecvError::Empty) => {
+            glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+                poll_directory_result(rx, state, store, dir, generation);
+            });
+        }
+        Err(mpsc::TryRecvError::Disconnected) => {}
+    }
+}
@@ -6,16 +6,19 @@
 pub mod branch_panel;
 pub mod commit_list;
 pub mod copy_on_hover;
+pub mod file_tree;
 pub mod grid_cell;
 pub mod placeholder;
 pub mod repo_view;
+pub mod signature_settings;
 pub mod styles;
 pub mod welcome_view;
 pub mod window;
 
 pub use branch_panel::{BranchPanel, RefType};
-pub use commit_list::{CommitList, CommitLoadRequest, CommitPagingState};
+pub use commit_list::{CommitList, CommitLoadRequest, CommitPagingState, commit_row};
+pub use file_tree::FileTree;
 pub use grid_cell::{Entry, GridCell};
 pub use repo_view::RepoView;
 pub use welcome_view::WelcomeView;
-pub use window::{build_ui, setup_app_action, setup_shortcuts};
+pub use window::{build_ui, open_launch_target, setup_app_action, setup_shortcuts};
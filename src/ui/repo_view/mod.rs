@@ -3,10 +3,14 @@ use gtk::{
     prelude::*,
 };
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
-use crate::ui::{BranchPanel, CommitList, CommitPagingState, copy_on_hover};
+use crate::git::{
+    CO_AUTHORED_BY, MergeDiffMode, REVIEWED_BY, SignatureCheck, SignatureKind, SignatureStatus,
+    Trailer,
+};
+use crate::ui::{BranchPanel, CommitList, CommitPagingState, FileTree, copy_on_hover};
 
 #[derive(Clone)]
 pub struct RepoView {
@@ -24,6 +28,9 @@ pub struct RepoView {
     pub branch_panel: BranchPanel,
     pub commit_list: CommitList,
     pub commit_paging_state: Rc<RefCell<CommitPagingState>>,
+    /// Bar above the commit list shown while the list is limited to a path.
+    pub path_filter_bar: gtk::Box,
+    pub path_filter_label: gtk::Label,
 
     // Diff UI
     pub diff_files_box: gtk::Box,
@@ -37,8 +44,28 @@ pub struct RepoView {
     pub diff_sha_row: gtk::Box,
     pub diff_sha_label: gtk::Label,
     pub diff_sha_copy_text: Rc<RefCell<String>>,
+    /// Signature badges of the shown commit and its annotated tags.
+    pub diff_signature_box: gtk::Box,
+    /// Co-author and reviewer chips from the shown commit's trailers.
+    pub diff_people_box: gtk::Box,
     pub diff_expand_all_button: gtk::Button,
     pub diff_collapse_all_button: gtk::Button,
+    /// Picks how a merge commit is diffed. Only shown for merge commits.
+    pub diff_merge_mode_dropdown: gtk::DropDown,
+    /// Modes listed in `diff_merge_mode_dropdown`, in order. Empty while the
+    /// dropdown is hidden.
+    pub diff_merge_modes: Rc<RefCell<Vec<MergeDiffMode>>>,
+    /// Last mode picked in the dropdown; kept across merge commits.
+    pub diff_merge_mode: Rc<Cell<MergeDiffMode>>,
+    /// Toggles the side-by-side diff layout (persisted in GSettings).
+    pub diff_split_button: gtk::ToggleButton,
+    /// Whether file sections are built in the side-by-side layout.
+    pub diff_split_view: Rc<RefCell<bool>>,
+    /// Switches the diff area between the changes and the commit's file tree.
+    pub diff_browse_button: gtk::ToggleButton,
+    /// Holds the "changes" (diff) and "files" (file tree) pages.
+    pub diff_stack: gtk::Stack,
+    pub file_tree: FileTree,
     pub commit_message_label: gtk::Label,
     pub expand_label: gtk::Label,
     pub full_message: Rc<RefCell<String>>,
@@ -79,6 +106,50 @@ impl RepoView {
         *self.is_expanded.borrow_mut() = false;
         self.diff_expand_all_button.set_sensitive(false);
         self.diff_collapse_all_button.set_sensitive(false);
+        self.set_merge_diff_modes(Vec::new(), MergeDiffMode::default());
+        self.set_signatures(&[]);
+        self.set_trailer_people(&[]);
+    }
+
+    /// Show a badge per checked signature next to the commit SHA.
+    pub fn set_signatures(&self, checks: &[SignatureCheck]) {
+        while let Some(child) = self.diff_signature_box.first_child() {
+            self.diff_signature_box.remove(&child);
+        }
+        for check in checks {
+            self.diff_signature_box.append(&signature_badge(check));
+        }
+        self.diff_signature_box.set_visible(!checks.is_empty());
+    }
+
+    /// Show a chip per co-author and reviewer named in `trailers`.
+    pub fn set_trailer_people(&self, trailers: &[Trailer]) {
+        while let Some(child) = self.diff_people_box.first_child() {
+            self.diff_people_box.remove(&child);
+        }
+        for trailer in trailers {
+            if let Some(chip) = person_chip(trailer) {
+                self.diff_people_box.append(&chip);
+            }
+        }
+        self.diff_people_box
+            .set_visible(self.diff_people_box.first_child().is_some());
+    }
+
+    /// List `modes` in the merge-mode dropdown with `selected` picked, or hide
+    /// the dropdown when `modes` is empty. Does not change `diff_merge_mode`.
+    pub fn set_merge_diff_modes(&self, modes: Vec<MergeDiffMode>, selected: MergeDiffMode) {
+        // Cleared first so the selection changes below are not taken as picks.
+        self.diff_merge_modes.borrow_mut().clear();
+        let labels: Vec<String> = modes.iter().map(MergeDiffMode::label).collect();
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.diff_merge_mode_dropdown
+            .set_model(Some(&gtk::StringList::new(&labels)));
+        let position = modes.iter().position(|m| *m == selected).unwrap_or(0);
+        self.diff_merge_mode_dropdown.set_selected(position as u32);
+        self.diff_merge_mode_dropdown
+            .set_visible(!modes.is_empty() && !self.diff_browse_button.is_active());
+        *self.diff_merge_modes.borrow_mut() = modes;
     }
 
     /// Show or hide the diff "chrome" (the header row with the metadata label
@@ -92,6 +163,23 @@ impl RepoView {
         self.commit_message_container.set_visible(visible);
     }
 
+    /// Show the path-filter bar for `path`, or hide it when `None`.
+    pub fn set_path_filter(&self, path: Option<&str>) {
+        match path {
+            Some(path) => {
+                self.path_filter_label
+                    .set_text(&format!("History of {}", path));
+                self.path_filter_label.set_tooltip_text(Some(path));
+                self.path_filter_bar.set_visible(true);
+            }
+            None => {
+                self.path_filter_label.set_text("");
+                self.path_filter_label.set_tooltip_text(None);
+                self.path_filter_bar.set_visible(false);
+            }
+        }
+    }
+
     /// Builds the full repo screen (search bar + branch/commit panels + diff view).
     ///
     /// The `window` is only used for a couple of UX touches (entry width sizing).
@@ -99,6 +187,12 @@ impl RepoView {
         // Search bar component
         let search_entry = gtk::SearchEntry::builder()
             .placeholder_text("Search")
+            .tooltip_text(
+                "Search commit messages. Filters: author:, committer:, before:YYYY-MM-DD, \
+                 after:YYYY-MM-DD, path:, is:merge, \"exact phrase\", /regex/. \
+                 content:text and content:/regex/ search the changes themselves. \
+                 Prefix a term with - to exclude it.",
+            )
             .build();
         // adw::Spinner animates automatically whenever it is visible, so loading
         // state is controlled purely via `set_visible`. It expands to fill its
@@ -189,6 +283,43 @@ impl RepoView {
         let commit_list = CommitList::new();
         let commit_paging_state = commit_list.paging_state();
 
+        // Path filter bar: a chip naming the filtered path with a clear button.
+        let path_filter_label = gtk::Label::builder()
+            .ellipsize(gtk::pango::EllipsizeMode::Middle)
+            .xalign(0.0)
+            .build();
+        let path_filter_clear_button = gtk::Button::builder()
+            .icon_name("window-close-symbolic")
+            .tooltip_text("Show full history")
+            .action_name("win.clear-path-filter")
+            .build();
+        path_filter_clear_button.add_css_class("flat");
+        path_filter_clear_button.add_css_class("circular");
+
+        let path_filter_chip = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(4)
+            .build();
+        path_filter_chip.add_css_class("path-filter-chip");
+        path_filter_chip.append(&path_filter_label);
+        path_filter_chip.append(&path_filter_clear_button);
+
+        let path_filter_bar = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .margin_start(6)
+            .margin_end(6)
+            .margin_top(4)
+            .margin_bottom(4)
+            .visible(false)
+            .build();
+        path_filter_bar.append(&path_filter_chip);
+
+        let commit_pane = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        commit_pane.append(&path_filter_bar);
+        commit_pane.append(&commit_list.widget);
+
         let branch_panel = BranchPanel::new(&[]);
         let side_panel = branch_panel.widget.clone();
 
@@ -248,8 +379,26 @@ impl RepoView {
         let diff_sha_copy_text = diff_sha_copy_row.copy_text.clone();
         diff_sha_row.set_visible(false);
 
+        let diff_signature_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(4)
+            .margin_start(8)
+            .valign(gtk::Align::Center)
+            .visible(false)
+            .build();
+
         diff_metadata_box.append(&diff_metadata_label);
         diff_metadata_box.append(&diff_sha_row);
+        let diff_people_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(4)
+            .margin_start(8)
+            .valign(gtk::Align::Center)
+            .visible(false)
+            .build();
+
+        diff_metadata_box.append(&diff_signature_box);
+        diff_metadata_box.append(&diff_people_box);
 
         // Diff header controls
         let diff_expand_all_button = gtk::Button::builder()
@@ -266,6 +415,27 @@ impl RepoView {
         diff_collapse_all_button.add_css_class("flat");
         diff_collapse_all_button.set_sensitive(false);
 
+        let diff_merge_mode_dropdown = gtk::DropDown::builder()
+            .tooltip_text("How to diff this merge commit")
+            .visible(false)
+            .build();
+        diff_merge_mode_dropdown.add_css_class("flat");
+        let diff_merge_modes = Rc::new(RefCell::new(Vec::new()));
+        let diff_merge_mode = Rc::new(Cell::new(MergeDiffMode::default()));
+
+        let diff_split_button = gtk::ToggleButton::builder()
+            .icon_name("view-dual-symbolic")
+            .tooltip_text("Side-by-side diff")
+            .build();
+        diff_split_button.add_css_class("flat");
+        let diff_split_view = Rc::new(RefCell::new(false));
+
+        let diff_browse_button = gtk::ToggleButton::builder()
+            .icon_name("folder-symbolic")
+            .tooltip_text("Browse files at this commit")
+            .build();
+        diff_browse_button.add_css_class("flat");
+
         let diff_header = gtk::Box::builder()
             .orientation(gtk::Orientation::Horizontal)
             .margin_start(10)
@@ -277,8 +447,11 @@ impl RepoView {
             .visible(false)
             .build();
         diff_header.append(&diff_metadata_box);
+        diff_header.append(&diff_merge_mode_dropdown);
         diff_header.append(&diff_expand_all_button);
         diff_header.append(&diff_collapse_all_button);
+        diff_header.append(&diff_split_button);
+        diff_header.append(&diff_browse_button);
 
         let commit_message_container = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
@@ -348,11 +521,15 @@ impl RepoView {
             .build();
         diff_box.append(&diff_header);
         diff_box.append(&commit_message_container);
-        diff_box.append(&diff_scrolled_window);
+        let file_tree = FileTree::new();
+        let diff_stack = gtk::Stack::builder().vexpand(true).build();
+        diff_stack.add_named(&diff_scrolled_window, Some("changes"));
+        diff_stack.add_named(&file_tree.widget, Some("files"));
+        diff_box.append(&diff_stack);
 
         // Layout (paned widgets)
         let main_content_paned = gtk::Paned::new(gtk::Orientation::Vertical);
-        main_content_paned.set_start_child(Some(&commit_list.widget));
+        main_content_paned.set_start_child(Some(&commit_pane));
         main_content_paned.set_end_child(Some(&diff_box));
         main_content_paned.set_resize_start_child(true);
         main_content_paned.set_resize_end_child(true);
@@ -384,6 +561,8 @@ impl RepoView {
             branch_panel,
             commit_list,
             commit_paging_state,
+            path_filter_bar,
+            path_filter_label,
             diff_files_box,
             diff_header,
             commit_message_container,
@@ -391,8 +570,18 @@ impl RepoView {
             diff_sha_row,
             diff_sha_label,
             diff_sha_copy_text,
+            diff_signature_box,
+            diff_people_box,
             diff_expand_all_button,
             diff_collapse_all_button,
+            diff_merge_mode_dropdown,
+            diff_merge_modes,
+            diff_merge_mode,
+            diff_split_button,
+            diff_split_view,
+            diff_browse_button,
+            diff_stack,
+            file_tree,
             commit_message_label,
             expand_label,
             full_message,
@@ -402,3 +591,59 @@ impl RepoView {
         }
     }
 }
+
+/// Badge for one signature, e.g. "Verified" or "Tag v1.0: Unknown", with the
+/// signer and the verifier's explanation in the tooltip.
+fn signature_badge(check: &SignatureCheck) -> gtk::Label {
+    let (state, class) = match check.status {
+        SignatureStatus::Verified => ("Verified", "signature-verified"),
+        SignatureStatus::Unverified => ("Unverified", "signature-unverified"),
+        SignatureStatus::Unknown => ("Unknown", "signature-unknown"),
+    };
+    let text = match check.object.strip_prefix("Tag ") {
+        Some(tag) => format!("{tag}: {state}"),
+        None => state.to_string(),
+    };
+    let kind = match check.kind {
+        SignatureKind::Gpg => "GPG",
+        SignatureKind::Ssh => "SSH",
+        SignatureKind::X509 => "X.509",
+        SignatureKind::Other => "Unrecognized",
+    };
+    let mut tooltip = format!("{} – {kind} signature", check.object);
+    if let Some(signer) = &check.signer {
+        tooltip.push_str(&format!("\nSigned by {signer}"));
+    }
+    if !check.detail.is_empty() {
+        tooltip.push_str(&format!("\n{}", check.detail));
+    }
+
+    let badge = gtk::Label::builder()
+        .label(text)
+        .tooltip_text(tooltip)
+        .build();
+    badge.add_css_class("signature-badge");
+    badge.add_css_class(class);
+    badge
+}
+
+/// Chip for a `Co-authored-by` or `Reviewed-by` trailer; `None` for others.
+fn person_chip(trailer: &Trailer) -> Option<gtk::Label> {
+    let role = if trailer.is(CO_AUTHORED_BY) {
+        "Co-author"
+    } else if trailer.is(REVIEWED_BY) {
+        "Reviewer"
+    } else {
+        return None;
+    };
+    let name = match trailer.identity() {
+        ("", Some(email)) => email,
+        (name, _) => name,
+    };
+    let chip = gtk::Label::builder()
+        .label(format!("{role}: {name}"))
+        .tooltip_text(format!("{}: {}", trailer.key, trailer.value))
+        .build();
+    chip.add_css_class("person-chip");
+    Some(chip)
+}
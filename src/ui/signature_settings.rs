@@ -0,0 +1,28 @@
+//! Signature verification preferences.
+
+use gtk::gio;
+use gtk::prelude::*;
+use std::path::PathBuf;
+
+use crate::APP_ID;
+use crate::git;
+
+/// Keyring and allowed signers from the preferences, with `~/` expanded.
+pub fn verifier() -> git::SignatureVerifier {
+    let settings = gio::Settings::new(APP_ID);
+    let path_setting = |key: &str| {
+        let value = settings.string(key);
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        match (value.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => Some(home.join(rest)),
+            _ => Some(PathBuf::from(value)),
+        }
+    };
+    git::SignatureVerifier {
+        gnupg_home: path_setting("gpg-home"),
+        allowed_signers: path_setting("ssh-allowed-signers"),
+    }
+}
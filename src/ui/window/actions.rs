@@ -1,16 +1,21 @@
 use adw::prelude::AdwDialogExt;
 use gio::ActionEntry;
-use gtk::{gio, prelude::*};
+use gtk::{gio, glib, prelude::*};
 
 use super::build_ui_for_new_window;
+use super::compare;
+use super::patches;
+use super::preferences;
+use super::reflog;
+use super::releases;
 use super::repo;
-use super::search;
-use super::state::AppState;
-use super::ui::WindowUi;
+use super::tabs::Tabs;
+use crate::launch::LaunchTarget;
 use crate::{APP_ID, DEVELOPER_NAME};
 
 pub fn setup_shortcuts(app: &adw::Application) {
-    app.set_accels_for_action("win.close", &["<Ctrl>W"]);
+    app.set_accels_for_action("win.new-tab", &["<Ctrl>T"]);
+    app.set_accels_for_action("win.close-tab", &["<Ctrl>W"]);
     app.set_accels_for_action("app.quit", &["<Ctrl>Q"]);
     app.set_accels_for_action("win.open", &["<Ctrl>O"]);
     app.set_accels_for_action("win.close-repo", &["<Ctrl><Shift>W", "<Alt>Left"]);
@@ -20,35 +25,49 @@ pub fn setup_shortcuts(app: &adw::Application) {
     app.set_accels_for_action("win.find-next", &["<Ctrl>G"]);
     app.set_accels_for_action("win.find-previous", &["<Ctrl><Shift>G"]);
     app.set_accels_for_action("win.refresh", &["<Ctrl>R"]);
+    app.set_accels_for_action("win.filter-path", &["<Ctrl><Shift>H"]);
+    app.set_accels_for_action("win.compare", &["<Ctrl><Shift>C"]);
+    app.set_accels_for_action("win.export-patches", &["<Ctrl><Shift>E"]);
     app.set_accels_for_action("app.new-window", &["<Ctrl>N"]);
+    app.set_accels_for_action("win.preferences", &["<Ctrl>comma"]);
 }
 
-pub fn install(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState) {
+pub fn install(window: &gtk::ApplicationWindow, tabs: &Tabs) {
     // Hook open button to the same open action (deduped open dialog)
-    let open_button = ui.open_button.clone();
+    let open_button = tabs.chrome.open_button.clone();
     let window_for_open_btn = window.clone();
     open_button.connect_clicked(move |_| {
         let _ = gtk::prelude::WidgetExt::activate_action(&window_for_open_btn, "win.open", None);
     });
 
-    // Actions
+    // Actions (all but the window-level ones act on the selected tab)
     let action_close = ActionEntry::builder("close")
         .activate(|window: &gtk::ApplicationWindow, _, _| {
             window.close();
         })
         .build();
 
-    let ui_for_open_action = ui.clone();
-    let state_for_open_action = state.clone();
+    let tabs_for_new_tab = tabs.clone();
+    let action_new_tab = ActionEntry::builder("new-tab")
+        .activate(move |window: &gtk::ApplicationWindow, _, _| {
+            super::add_tab(window, &tabs_for_new_tab);
+        })
+        .build();
+
+    let tabs_for_close_tab = tabs.clone();
+    let action_close_tab = ActionEntry::builder("close-tab")
+        .activate(move |_, _, _| {
+            tabs_for_close_tab.close_current();
+        })
+        .build();
+
+    let tabs_for_open_action = tabs.clone();
     let action_open = ActionEntry::builder("open")
         .activate(move |window: &gtk::ApplicationWindow, _, _| {
             crate::logger::Logger::info("DEBUG: Open repository action triggered");
-            repo::open_repo_dialog(
-                window,
-                &ui_for_open_action,
-                &state_for_open_action,
-                super::APP_NAME,
-            );
+            if let Some(tab) = tabs_for_open_action.current() {
+                repo::open_repo_dialog(window, &tab.ui, &tab.state, super::APP_NAME);
+            }
         })
         .build();
 
@@ -63,39 +82,153 @@ pub fn install(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState)
         })
         .build();
 
-    // Search actions + wiring
-    let search_controller = search::SearchController::connect(ui, state);
-    let action_show_search = search_controller.action_show_search(ui);
-    let action_hide_search = search_controller.action_hide_search(ui);
-    let action_find_next = search_controller.action_find_next(ui, state);
-    let action_find_previous = search_controller.action_find_previous(ui, state);
+    // Search actions (each tab has its own search controller)
+    let tabs_for_show_search = tabs.clone();
+    let action_show_search = ActionEntry::builder("show-search")
+        .activate(move |_, _, _| {
+            if let Some(tab) = tabs_for_show_search.current() {
+                tab.search.show_search(&tab.ui);
+            }
+        })
+        .build();
+
+    let tabs_for_hide_search = tabs.clone();
+    let action_hide_search = ActionEntry::builder("hide-search")
+        .activate(move |_, _, _| {
+            if let Some(tab) = tabs_for_hide_search.current() {
+                tab.search.hide_search(&tab.ui);
+            }
+        })
+        .build();
+
+    let tabs_for_find_next = tabs.clone();
+    let action_find_next = ActionEntry::builder("find-next")
+        .activate(move |_, _, _| {
+            if let Some(tab) = tabs_for_find_next.current() {
+                tab.search.find_next(&tab.ui, &tab.state);
+            }
+        })
+        .build();
+
+    let tabs_for_find_previous = tabs.clone();
+    let action_find_previous = ActionEntry::builder("find-previous")
+        .activate(move |_, _, _| {
+            if let Some(tab) = tabs_for_find_previous.current() {
+                tab.search.find_previous(&tab.ui, &tab.state);
+            }
+        })
+        .build();
 
     // Refresh action - created as SimpleAction so we can enable/disable it based on repo state
     let action_refresh = gio::SimpleAction::new("refresh", None);
-    action_refresh.set_enabled(state.is_repo_loaded());
-    let ui_for_refresh_action = ui.clone();
-    let state_for_refresh_action = state.clone();
+    action_refresh.set_enabled(false);
+    let tabs_for_refresh_action = tabs.clone();
     action_refresh.connect_activate(move |_, _| {
-        if state_for_refresh_action.is_repo_loaded() {
-            repo::refresh_repo(
-                &ui_for_refresh_action,
-                &state_for_refresh_action,
-                super::APP_NAME,
-            );
+        if let Some(tab) = tabs_for_refresh_action.current()
+            && tab.state.is_repo_loaded()
+        {
+            repo::refresh_repo(&tab.ui, &tab.state, super::APP_NAME);
         }
     });
     window.add_action(&action_refresh);
-    ui.set_refresh_action(action_refresh);
+    tabs.chrome.set_refresh_action(action_refresh);
 
-    let ui_for_close_repo_action = ui.clone();
-    let state_for_close_repo_action = state.clone();
+    let tabs_for_close_repo_action = tabs.clone();
     let action_close_repo = ActionEntry::builder("close-repo")
         .activate(move |_, _, _| {
-            repo::close_repo(
-                &ui_for_close_repo_action,
-                &state_for_close_repo_action,
-                super::APP_NAME,
-            );
+            if let Some(tab) = tabs_for_close_repo_action.current() {
+                repo::close_repo(&tab.ui, &tab.state);
+            }
+        })
+        .build();
+
+    // Path filter (file/folder history) actions
+    let tabs_for_filter_path_action = tabs.clone();
+    let action_filter_path = ActionEntry::builder("filter-path")
+        .activate(move |window: &gtk::ApplicationWindow, _, _| {
+            if let Some(tab) = tabs_for_filter_path_action.current() {
+                repo::show_path_filter_dialog(window, &tab.ui, &tab.state);
+            }
+        })
+        .build();
+
+    let tabs_for_clear_filter_action = tabs.clone();
+    let action_clear_path_filter = ActionEntry::builder("clear-path-filter")
+        .activate(move |_, _, _| {
+            if let Some(tab) = tabs_for_clear_filter_action.current() {
+                repo::set_path_filter(&tab.ui, &tab.state, None);
+            }
+        })
+        .build();
+
+    let tabs_for_compare_action = tabs.clone();
+    let action_compare = ActionEntry::builder("compare")
+        .activate(move |window: &gtk::ApplicationWindow, _, _| {
+            if let Some(tab) = tabs_for_compare_action.current() {
+                compare::show_compare_dialog(window, &tab.ui, &tab.state);
+            }
+        })
+        .build();
+
+    // Reflog dialog for the ref named by the parameter (`HEAD` or a branch)
+    let tabs_for_reflog_action = tabs.clone();
+    let action_reflog = ActionEntry::builder("reflog")
+        .parameter_type(Some(glib::VariantTy::STRING))
+        .activate(move |window: &gtk::ApplicationWindow, _, param| {
+            let Some(name) = param.and_then(|p| p.get::<String>()) else {
+                return;
+            };
+            if let Some(tab) = tabs_for_reflog_action.current() {
+                reflog::show_reflog_dialog(window, &tab.ui, &tab.state, &name);
+            }
+        })
+        .build();
+
+    let tabs_for_releases_action = tabs.clone();
+    let action_releases = ActionEntry::builder("releases")
+        .activate(move |window: &gtk::ApplicationWindow, _, _| {
+            if let Some(tab) = tabs_for_releases_action.current() {
+                releases::show_releases_dialog(window, &tab.ui, &tab.state);
+            }
+        })
+        .build();
+
+    let tabs_for_copy_patch_action = tabs.clone();
+    let action_copy_patch = ActionEntry::builder("copy-patch")
+        .activate(move |_, _, _| {
+            if let Some(tab) = tabs_for_copy_patch_action.current() {
+                patches::copy_as_patch(&tab.ui, &tab.state);
+            }
+        })
+        .build();
+
+    let tabs_for_export_patches_action = tabs.clone();
+    let action_export_patches = ActionEntry::builder("export-patches")
+        .activate(move |window: &gtk::ApplicationWindow, _, _| {
+            if let Some(tab) = tabs_for_export_patches_action.current() {
+                patches::export_patches(window, &tab.ui, &tab.state);
+            }
+        })
+        .build();
+
+    // Deep link (`x-gity://open?...`) forwarded by the application
+    let tabs_for_link_action = tabs.clone();
+    let action_open_link = ActionEntry::builder("open-link")
+        .parameter_type(Some(glib::VariantTy::STRING))
+        .activate(move |window: &gtk::ApplicationWindow, _, param| {
+            let Some(uri) = param.and_then(|p| p.get::<String>()) else {
+                return;
+            };
+            match LaunchTarget::from_uri(&uri) {
+                Ok(target) => super::open_target(window, &tabs_for_link_action, &target),
+                Err(message) => crate::logger::Logger::error(&message),
+            }
+        })
+        .build();
+
+    let action_preferences = ActionEntry::builder("preferences")
+        .activate(|window: &gtk::ApplicationWindow, _, _| {
+            preferences::show_preferences(window);
         })
         .build();
 
@@ -121,6 +254,8 @@ pub fn install(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState)
 
     window.add_action_entries([
         action_close,
+        action_new_tab,
+        action_close_tab,
         action_open,
         action_show_help,
         action_show_search,
@@ -128,6 +263,15 @@ pub fn install(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState)
         action_find_next,
         action_find_previous,
         action_close_repo,
+        action_filter_path,
+        action_clear_path_filter,
+        action_compare,
+        action_reflog,
+        action_releases,
+        action_copy_patch,
+        action_export_patches,
+        action_open_link,
+        action_preferences,
         action_about,
     ]);
 }
@@ -0,0 +1,301 @@
+//! Line-by-line blame dialog for a file at a given commit.
+//!
+//! The blame runs on a background thread and is polled from the main loop,
+//! like the diff loaders. Closing the dialog cancels the blame; clicking a
+//! line's SHA selects that commit in the commit list.
+
+use adw::prelude::*;
+use gtk::glib;
+use sourceview5 as sv;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use sv::prelude::*;
+
+use crate::git;
+use crate::logger::Logger;
+
+use super::state::AppState;
+use super::ui::WindowUi;
+
+/// Number of SHA characters shown in the blame gutter.
+const BLAME_SHA_LEN: usize = 7;
+
+/// Maximum number of author-name characters shown in the blame gutter.
+const BLAME_AUTHOR_CHARS: usize = 18;
+
+/// Open the blame dialog for `file_path` as it exists at `commit_sha`.
+pub fn show_blame_dialog(ui: &WindowUi, state: &AppState, commit_sha: &str, file_path: &str) {
+    let Some(repo_path) = state.current_path.borrow().clone() else {
+        return;
+    };
+
+    let short = commit_sha.get(..BLAME_SHA_LEN).unwrap_or(commit_sha);
+    let title = adw::WindowTitle::new(file_path, &format!("Blame at {short}"));
+    let header = adw::HeaderBar::new();
+    header.set_title_widget(Some(&title));
+
+    let stack = gtk::Stack::new();
+    let spinner = adw::Spinner::builder()
+        .width_request(32)
+        .height_request(32)
+        .halign(gtk::Align::Center)
+        .valign(gtk::Align::Center)
+        .build();
+    stack.add_named(&spinner, Some("loading"));
+
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.set_content(Some(&stack));
+
+    let dialog = adw::Dialog::builder()
+        .content_width(960)
+        .content_height(640)
+        .child(&toolbar)
+        .build();
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let cancel_on_close = cancel.clone();
+    dialog.connect_closed(move |_| {
+        cancel_on_close.store(true, Ordering::Relaxed);
+    });
+
+    let (tx, rx) = mpsc::channel();
+    let sha = commit_sha.to_string();
+    let path = file_path.to_string();
+    let cancel_for_thread = cancel.clone();
+    std::thread::spawn(move || {
+        let result = git::get_file_blame(
+            repo_path.to_str().unwrap(),
+            &sha,
+            &path,
+            Some(&cancel_for_thread),
+        );
+        let _ = tx.send(result);
+    });
+
+    poll_blame_result(rx, cancel, stack, dialog.clone(), ui.clone());
+    dialog.present(Some(&ui.repo_view.widget));
+}
+
+fn poll_blame_result(
+    rx: mpsc::Receiver<Result<Vec<git::BlameLine>, git2::Error>>,
+    cancel: Arc<AtomicBool>,
+    stack: gtk::Stack,
+    dialog: adw::Dialog,
+    ui: WindowUi,
+) {
+    if cancel.load(Ordering::Relaxed) {
+        return;
+    }
+    match rx.try_recv() {
+        Ok(Ok(lines)) => {
+            let view = build_blame_view(lines, &dialog, &ui);
+            stack.add_named(&view, Some("blame"));
+            stack.set_visible_child_name("blame");
+        }
+        Ok(Err(e)) => {
+            Logger::error(&format!("Error loading blame: {}", e));
+            let placeholder = crate::ui::placeholder::centered(
+                crate::ui::placeholder::ICON_ERROR,
+                &format!("Error loading blame: {}", e.message()),
+            );
+            stack.add_named(&placeholder, Some("error"));
+            stack.set_visible_child_name("error");
+        }
+        Err(mpsc::TryRecvError::Empty) => {
+            glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+                poll_blame_result(rx, cancel, stack, dialog, ui);
+            });
+        }
+        Err(mpsc::TryRecvError::Disconnected) => {}
+    }
+}
+
+/// Gutter text color of lines from the same commit as the line above, which
+/// are dimmed so each run of lines stands out.
+fn repeat_color() -> &'static str {
+    if adw::StyleManager::default().is_dark() {
+        "#77767b"
+    } else {
+        "#9a9996"
+    }
+}
+
+/// Whether line `idx` comes from the same commit as the line above it.
+fn repeats_previous(lines: &[git::BlameLine], idx: usize) -> bool {
+    idx > 0 && lines[idx - 1].commit_id == lines[idx].commit_id
+}
+
+/// Build the gutter text: the SHA, author and date of every line.
+fn blame_gutter_text(lines: &[git::BlameLine]) -> String {
+    let author_width = lines
+        .iter()
+        .map(|l| l.author.chars().count().min(BLAME_AUTHOR_CHARS))
+        .max()
+        .unwrap_or(0);
+
+    let mut gutter = String::new();
+    for line in lines {
+        let sha = line
+            .commit_id
+            .get(..BLAME_SHA_LEN)
+            .unwrap_or(&line.commit_id);
+        let author: String = line.author.chars().take(BLAME_AUTHOR_CHARS).collect();
+        gutter.push_str(&format!(" {sha} {author:<author_width$} {} \n", line.date));
+    }
+    gutter
+}
+
+fn build_blame_view(
+    lines: Vec<git::BlameLine>,
+    dialog: &adw::Dialog,
+    ui: &WindowUi,
+) -> gtk::Widget {
+    let gutter_buffer = gtk::TextBuffer::new(None);
+    gutter_buffer.set_text(&blame_gutter_text(&lines));
+
+    let link_tag = gtk::TextTag::builder()
+        .name("blame-sha")
+        .underline(gtk::pango::Underline::Single)
+        .build();
+    gutter_buffer.tag_table().add(&link_tag);
+    let repeat_tag = gtk::TextTag::builder()
+        .name("blame-repeat")
+        .foreground(repeat_color())
+        .build();
+    gutter_buffer.tag_table().add(&repeat_tag);
+    for idx in 0..lines.len() {
+        let Some(start) = gutter_buffer.iter_at_line(idx as i32) else {
+            break;
+        };
+        let mut end = start;
+        end.forward_chars(BLAME_SHA_LEN as i32 + 1);
+        gutter_buffer.apply_tag(&link_tag, &start, &end);
+        if repeats_previous(&lines, idx) {
+            let mut line_end = start;
+            line_end.forward_to_line_end();
+            gutter_buffer.apply_tag(&repeat_tag, &start, &line_end);
+        }
+    }
+
+    let gutter_view = gtk::TextView::with_buffer(&gutter_buffer);
+    // Follow light/dark switches while the dialog is open, like the diff view.
+    let style_manager = adw::StyleManager::default();
+    let repeat_tag_for_theme = repeat_tag.downgrade();
+    let theme_handler = style_manager.connect_dark_notify(move |_| {
+        if let Some(tag) = repeat_tag_for_theme.upgrade() {
+            tag.set_foreground(Some(repeat_color()));
+        }
+    });
+    let theme_handler = std::cell::Cell::new(Some(theme_handler));
+    gutter_view.connect_destroy(move |_| {
+        if let Some(handler) = theme_handler.take() {
+            style_manager.disconnect(handler);
+        }
+    });
+    gutter_view.set_editable(false);
+    gutter_view.set_cursor_visible(false);
+    gutter_view.set_monospace(true);
+    gutter_view.set_can_focus(false);
+    gutter_view.set_hexpand(false);
+    gutter_view.set_cursor_from_name(Some("pointer"));
+    gutter_view.add_css_class("diff-gutter");
+    gutter_view.set_tooltip_text(Some("Click a line to select its commit"));
+
+    let commit_ids: Rc<Vec<String>> = Rc::new(lines.iter().map(|l| l.commit_id.clone()).collect());
+    let click = gtk::GestureClick::new();
+    let gutter_for_click = gutter_view.clone();
+    let dialog_for_click = dialog.clone();
+    let ui_for_click = ui.clone();
+    click.connect_released(move |_, _, x, y| {
+        let (bx, by) = gutter_for_click.window_to_buffer_coords(
+            gtk::TextWindowType::Widget,
+            x as i32,
+            y as i32,
+        );
+        let Some(iter) = gutter_for_click.iter_at_location(bx, by) else {
+            return;
+        };
+        let Some(sha) = commit_ids
+            .get(iter.line() as usize)
+            .filter(|s| !s.is_empty())
+        else {
+            return;
+        };
+        ui_for_click.repo_view.commit_list.select_commit(sha);
+        dialog_for_click.close();
+    });
+    gutter_view.add_controller(click);
+
+    let text: String = lines.iter().map(|l| format!("{}\n", l.content)).collect();
+    let buffer = sv::Buffer::new(None);
+    buffer.set_highlight_syntax(false);
+    buffer.set_text(&text);
+
+    let view = sv::View::with_buffer(&buffer);
+    view.set_editable(false);
+    view.set_cursor_visible(false);
+    view.set_monospace(true);
+    view.set_hexpand(true);
+    view.set_left_margin(6);
+    view.add_css_class("diff-view");
+
+    let h_scroller = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Automatic)
+        .vscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .hexpand(true)
+        .child(&view)
+        .build();
+
+    let row = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .build();
+    row.append(&gutter_view);
+    row.append(&h_scroller);
+
+    gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .child(&row)
+        .build()
+        .upcast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(commit_id: &str, author: &str, content: &str) -> git::BlameLine {
+        git::BlameLine {
+            content: content.to_string(),
+            commit_id: commit_id.to_string(),
+            author: author.to_string(),
+            date: "2024-01-02".to_string(),
+        }
+    }
+
+    #[test]
+    fn gutter_annotates_every_line_and_marks_repeats() {
+        let lines = vec![
+            line("aaaaaaaaaa", "Ann", "one"),
+            line("aaaaaaaaaa", "Ann", "two"),
+            line("bbbbbbbbbb", "Bob Builder", "three"),
+        ];
+        let gutter = blame_gutter_text(&lines);
+        let rows: Vec<&str> = gutter.lines().collect();
+        assert_eq!(
+            rows,
+            vec![
+                " aaaaaaa Ann         2024-01-02 ",
+                " aaaaaaa Ann         2024-01-02 ",
+                " bbbbbbb Bob Builder 2024-01-02 ",
+            ]
+        );
+        let repeats: Vec<bool> = (0..lines.len())
+            .map(|idx| repeats_previous(&lines, idx))
+            .collect();
+        assert_eq!(repeats, vec![false, true, false]);
+    }
+}
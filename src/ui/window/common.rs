@@ -0,0 +1,62 @@
+//! Helpers shared by the window's dialogs: short SHAs, generation counters,
+//! loading and placeholder pages, and running work off the main loop.
+
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::Cell;
+use std::sync::mpsc;
+
+/// Number of SHA characters shown in rows and titles.
+pub(super) const SHORT_SHA_LEN: usize = 7;
+
+pub(super) fn short_sha(sha: &str) -> &str {
+    sha.get(..SHORT_SHA_LEN).unwrap_or(sha)
+}
+
+/// Invalidate results requested under the current generation.
+pub(super) fn bump(generation: &Cell<u64>) {
+    generation.set(generation.get() + 1);
+}
+
+pub(super) fn loading_spinner() -> adw::Spinner {
+    adw::Spinner::builder()
+        .width_request(32)
+        .height_request(32)
+        .halign(gtk::Align::Center)
+        .valign(gtk::Align::Center)
+        .build()
+}
+
+/// Replace the page `name` of `stack` with a placeholder and show it.
+pub(super) fn show_placeholder(stack: &gtk::Stack, name: &str, icon: &str, text: &str) {
+    if let Some(old) = stack.child_by_name(name) {
+        stack.remove(&old);
+    }
+    stack.add_named(&crate::ui::placeholder::centered(icon, text), Some(name));
+    stack.set_visible_child_name(name);
+}
+
+/// Run `work` on a background thread and hand its result to `on_done` on
+/// the main loop, like the diff loaders.
+pub(super) fn run_in_background<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+    on_done: impl FnOnce(T) + 'static,
+) {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(work());
+    });
+    poll_result(rx, Box::new(on_done));
+}
+
+fn poll_result<T: 'static>(rx: mpsc::Receiver<T>, on_done: Box<dyn FnOnce(T)>) {
+    match rx.try_recv() {
+        Ok(result) => on_done(result),
+        Err(mpsc::TryRecvError::Empty) => {
+            glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+                poll_result(rx, on_done);
+            });
+        }
+        Err(mpsc::TryRecvError::Disconnected) => {}
+    }
+}
@@ -0,0 +1,459 @@
+//! "Compare…" dialog: diff any two revisions (branches, tags, remote
+//! branches or SHAs) and list the commits unique to each side.
+//!
+//! The comparison runs on a background thread and is polled from the main
+//! loop, like the diff loaders.
+
+use adw::prelude::*;
+use gtk::{gio, glib};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::git;
+use crate::logger::Logger;
+use crate::ui::RefType;
+
+use super::common::{bump, loading_spinner, run_in_background, short_sha, show_placeholder};
+use super::state::AppState;
+use super::ui::WindowUi;
+
+/// Widgets of one diff page (two-dot or three-dot).
+struct DiffPage {
+    widget: gtk::Box,
+    files_box: gtk::Box,
+    expand_button: gtk::Button,
+    collapse_button: gtk::Button,
+}
+
+/// Widgets filled in once a comparison finishes.
+#[derive(Clone)]
+struct ResultWidgets {
+    stack: gtk::Stack,
+    head_heading: gtk::Label,
+    head_list: gtk::ListBox,
+    base_heading: gtk::Label,
+    base_list: gtk::ListBox,
+    three_dot: Rc<DiffPage>,
+    two_dot: Rc<DiffPage>,
+    three_dot_page: adw::ViewStackPage,
+}
+
+/// Open the compare dialog. The base defaults to the main branch and the
+/// compared side to the ref shown in the commit list.
+pub fn show_compare_dialog(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState) {
+    if !state.is_repo_loaded() {
+        return;
+    }
+
+    let refs = ui.repo_view.branch_panel.ref_names();
+    let current_ref = state.current_ref.borrow().clone().unwrap_or_default();
+    let base_default = ["main", "master"]
+        .into_iter()
+        .find(|name| {
+            refs.iter()
+                .any(|(r, ty)| r == name && *ty == RefType::Branch)
+        })
+        .unwrap_or("")
+        .to_string();
+    let head_default = if current_ref == base_default {
+        String::new()
+    } else {
+        current_ref
+    };
+
+    let base_entry = gtk::Entry::builder()
+        .placeholder_text("Base (e.g. main)")
+        .text(base_default)
+        .hexpand(true)
+        .activates_default(true)
+        .build();
+    let head_entry = gtk::Entry::builder()
+        .placeholder_text("Compare (e.g. release/1.2 or a SHA)")
+        .text(head_default)
+        .hexpand(true)
+        .activates_default(true)
+        .build();
+
+    let swap_button = gtk::Button::builder()
+        .icon_name("object-flip-horizontal-symbolic")
+        .tooltip_text("Swap sides")
+        .build();
+    swap_button.add_css_class("flat");
+    let base_for_swap = base_entry.clone();
+    let head_for_swap = head_entry.clone();
+    swap_button.connect_clicked(move |_| {
+        let base = base_for_swap.text();
+        base_for_swap.set_text(&head_for_swap.text());
+        head_for_swap.set_text(&base);
+    });
+
+    let compare_button = gtk::Button::builder()
+        .label("_Compare")
+        .use_underline(true)
+        .build();
+    compare_button.add_css_class("suggested-action");
+
+    let controls = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(6)
+        .margin_start(12)
+        .margin_end(12)
+        .margin_top(6)
+        .margin_bottom(6)
+        .build();
+    controls.append(&base_entry);
+    controls.append(&ref_menu_button("compare.set-base", &refs));
+    controls.append(&swap_button);
+    controls.append(&head_entry);
+    controls.append(&ref_menu_button("compare.set-head", &refs));
+    controls.append(&compare_button);
+
+    let (widgets, view_stack) = build_result_widgets();
+    let switcher = adw::ViewSwitcher::builder()
+        .stack(&view_stack)
+        .policy(adw::ViewSwitcherPolicy::Wide)
+        .build();
+    let header = adw::HeaderBar::new();
+    header.set_title_widget(Some(&switcher));
+
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.add_top_bar(&controls);
+    toolbar.set_content(Some(&widgets.stack));
+
+    let dialog = adw::Dialog::builder()
+        .title("Compare")
+        .content_width(1000)
+        .content_height(720)
+        .child(&toolbar)
+        .default_widget(&compare_button)
+        .build();
+
+    let group = gio::SimpleActionGroup::new();
+    for (name, entry) in [("set-base", &base_entry), ("set-head", &head_entry)] {
+        let action = gio::SimpleAction::new(name, Some(glib::VariantTy::STRING));
+        let entry = entry.clone();
+        action.connect_activate(move |_, param| {
+            if let Some(name) = param.and_then(|p| p.get::<String>()) {
+                entry.set_text(&name);
+            }
+        });
+        group.add_action(&action);
+    }
+    dialog.insert_action_group("compare", Some(&group));
+
+    // Bumped per comparison, and on close, so a superseded result is dropped.
+    let generation = Rc::new(Cell::new(0u64));
+    let generation_on_close = generation.clone();
+    dialog.connect_closed(move |_| bump(&generation_on_close));
+
+    let ui = ui.clone();
+    let state = state.clone();
+    compare_button.connect_clicked(move |_| {
+        let base = base_entry.text().trim().to_string();
+        let head = head_entry.text().trim().to_string();
+        if base.is_empty() || head.is_empty() {
+            return;
+        }
+        let Some(repo_path) = state.current_path.borrow().clone() else {
+            return;
+        };
+        bump(&generation);
+        widgets.stack.set_visible_child_name("loading");
+
+        let names = (base.clone(), head.clone());
+        let generation = generation.clone();
+        let expected_generation = generation.get();
+        let widgets = widgets.clone();
+        let ui = ui.clone();
+        let state = state.clone();
+        run_in_background(
+            move || git::compare_refs(repo_path.to_str().unwrap(), &base, &head),
+            move |result| {
+                if generation.get() == expected_generation {
+                    show_comparison(result, &names, &widgets, &ui, &state);
+                }
+            },
+        );
+    });
+
+    dialog.present(Some(window));
+    if head_entry.text().is_empty() {
+        head_entry.grab_focus();
+    } else {
+        compare_button.emit_clicked();
+    }
+}
+
+/// Menu button listing the panel's refs by section; picking one activates
+/// `action` with the ref name.
+fn ref_menu_button(action: &str, refs: &[(String, RefType)]) -> gtk::MenuButton {
+    let menu = gio::Menu::new();
+    for (title, ref_type) in [
+        ("Branches", RefType::Branch),
+        ("Tags", RefType::Tag),
+        ("Remotes", RefType::Remote),
+    ] {
+        let section = gio::Menu::new();
+        for (name, _) in refs.iter().filter(|(_, ty)| *ty == ref_type) {
+            // Menu labels treat `_` as a mnemonic marker.
+            let item = gio::MenuItem::new(Some(&name.replace('_', "__")), None);
+            item.set_action_and_target_value(Some(action), Some(&name.to_variant()));
+            section.append_item(&item);
+        }
+        if section.n_items() > 0 {
+            menu.append_section(Some(title), &section);
+        }
+    }
+
+    gtk::MenuButton::builder()
+        .icon_name("pan-down-symbolic")
+        .tooltip_text("Pick a branch or tag")
+        .menu_model(&menu)
+        .build()
+}
+
+fn build_diff_page() -> DiffPage {
+    let expand_button = gtk::Button::builder()
+        .label("Expand all")
+        .tooltip_text("Expand all file diffs")
+        .build();
+    expand_button.add_css_class("flat");
+    let collapse_button = gtk::Button::builder()
+        .label("Collapse all")
+        .tooltip_text("Collapse all file diffs")
+        .build();
+    collapse_button.add_css_class("flat");
+
+    let header = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .halign(gtk::Align::End)
+        .margin_start(10)
+        .margin_end(10)
+        .margin_top(6)
+        .spacing(8)
+        .build();
+    header.append(&expand_button);
+    header.append(&collapse_button);
+
+    let files_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .vexpand(true)
+        .build();
+    // See the main diff view: don't scroll clicked file views into view.
+    let viewport = gtk::Viewport::builder()
+        .scroll_to_focus(false)
+        .child(&files_box)
+        .build();
+    let scrolled = gtk::ScrolledWindow::builder()
+        .vexpand(true)
+        .hexpand(true)
+        .child(&viewport)
+        .build();
+
+    let widget = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .build();
+    widget.append(&header);
+    widget.append(&scrolled);
+
+    super::diff::connect_expand_collapse(&files_box, &expand_button, &collapse_button);
+    DiffPage {
+        widget,
+        files_box,
+        expand_button,
+        collapse_button,
+    }
+}
+
+/// One column of the commits page: a heading above a list of commits.
+fn build_commit_column() -> (gtk::Box, gtk::Label, gtk::ListBox) {
+    let heading = gtk::Label::builder()
+        .halign(gtk::Align::Start)
+        .xalign(0.0)
+        .margin_start(10)
+        .margin_top(8)
+        .margin_bottom(4)
+        .build();
+    heading.add_css_class("heading");
+
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .build();
+    list.add_css_class("navigation-sidebar");
+    let scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .child(&list)
+        .build();
+
+    let column = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .hexpand(true)
+        .build();
+    column.append(&heading);
+    column.append(&scrolled);
+    (column, heading, list)
+}
+
+fn build_result_widgets() -> (ResultWidgets, adw::ViewStack) {
+    let (head_column, head_heading, head_list) = build_commit_column();
+    let (base_column, base_heading, base_list) = build_commit_column();
+    let commits = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .homogeneous(true)
+        .build();
+    commits.append(&head_column);
+    commits.append(&gtk::Separator::new(gtk::Orientation::Vertical));
+    commits.append(&base_column);
+
+    let three_dot = build_diff_page();
+    let two_dot = build_diff_page();
+
+    let view_stack = adw::ViewStack::new();
+    view_stack.add_titled_with_icon(&commits, Some("commits"), "Commits", "view-list-symbolic");
+    let three_dot_page = view_stack.add_titled_with_icon(
+        &three_dot.widget,
+        Some("three-dot"),
+        "Since Merge Base",
+        "media-playlist-consecutive-symbolic",
+    );
+    view_stack.add_titled_with_icon(
+        &two_dot.widget,
+        Some("two-dot"),
+        "Tree Difference",
+        "view-dual-symbolic",
+    );
+
+    let stack = gtk::Stack::new();
+    stack.add_named(
+        &crate::ui::placeholder::centered(
+            crate::ui::placeholder::ICON_INFO,
+            "Pick two branches, tags or commits to compare",
+        ),
+        Some("empty"),
+    );
+    stack.add_named(&loading_spinner(), Some("loading"));
+    stack.add_named(&view_stack, Some("result"));
+    stack.set_visible_child_name("empty");
+
+    let widgets = ResultWidgets {
+        stack,
+        head_heading,
+        head_list,
+        base_heading,
+        base_list,
+        three_dot: Rc::new(three_dot),
+        two_dot: Rc::new(two_dot),
+        three_dot_page,
+    };
+    (widgets, view_stack)
+}
+
+fn commit_row(commit: &git::GitCommit) -> gtk::ListBoxRow {
+    let sha_label = gtk::Label::builder()
+        .label(short_sha(&commit.id))
+        .selectable(true)
+        .build();
+    sha_label.add_css_class("monospace");
+    sha_label.add_css_class("dim-label");
+
+    let summary = commit.message.lines().next().unwrap_or("");
+    let summary_label = gtk::Label::builder()
+        .label(summary)
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .tooltip_text(format!("{} - {}", commit.author, commit.date))
+        .build();
+
+    let row_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    row_box.append(&sha_label);
+    row_box.append(&summary_label);
+
+    gtk::ListBoxRow::builder()
+        .activatable(false)
+        .child(&row_box)
+        .build()
+}
+
+fn fill_commit_list(list: &gtk::ListBox, commits: &[git::GitCommit]) {
+    list.remove_all();
+    for commit in commits {
+        list.append(&commit_row(commit));
+    }
+}
+
+/// Heading for one side's commit list, e.g. "Only in release (3)".
+fn commits_heading(side: &str, count: usize, truncated: bool) -> String {
+    let more = if truncated && count >= git::COMPARE_COMMIT_LIMIT {
+        "+"
+    } else {
+        ""
+    };
+    format!("Only in {side} ({count}{more})")
+}
+
+/// Fill the result pages with a finished comparison of `names` (base, head).
+fn show_comparison(
+    result: Result<git::RefComparison, git2::Error>,
+    names: &(String, String),
+    widgets: &ResultWidgets,
+    ui: &WindowUi,
+    state: &AppState,
+) {
+    let cmp = match result {
+        Ok(cmp) => cmp,
+        Err(e) => {
+            Logger::error(&format!("Error comparing refs: {}", e));
+            show_placeholder(
+                &widgets.stack,
+                "error",
+                crate::ui::placeholder::ICON_ERROR,
+                &format!("Cannot compare: {}", e.message()),
+            );
+            return;
+        }
+    };
+
+    let (base_name, head_name) = names;
+    widgets.head_heading.set_text(&commits_heading(
+        head_name,
+        cmp.only_in_head.len(),
+        cmp.truncated,
+    ));
+    widgets.base_heading.set_text(&commits_heading(
+        base_name,
+        cmp.only_in_base.len(),
+        cmp.truncated,
+    ));
+    fill_commit_list(&widgets.head_list, &cmp.only_in_head);
+    fill_commit_list(&widgets.base_list, &cmp.only_in_base);
+
+    widgets
+        .three_dot_page
+        .set_title(Some(&match cmp.merge_base.as_deref() {
+            Some(sha) => format!("Since Merge Base {}", short_sha(sha)),
+            None => "Since Merge Base".to_string(),
+        }));
+
+    for (page, diff) in [
+        (&widgets.three_dot, &cmp.three_dot),
+        (&widgets.two_dot, &cmp.two_dot),
+    ] {
+        super::diff::show_diff(
+            ui,
+            state,
+            &cmp.head_sha,
+            diff,
+            &page.files_box,
+            &page.expand_button,
+            &page.collapse_button,
+        );
+    }
+    widgets.stack.set_visible_child_name("result");
+}
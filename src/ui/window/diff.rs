@@ -1,4 +1,4 @@
-use gtk::{glib, prelude::*};
+use gtk::{gio, glib, prelude::*};
 use sourceview5 as sv;
 use std::sync::mpsc;
 use sv::prelude::*;
@@ -15,6 +15,8 @@ struct DiffColors {
     remove_bg: &'static str,
     gutter_add_bg: &'static str,
     gutter_remove_bg: &'static str,
+    add_word_bg: &'static str,
+    remove_word_bg: &'static str,
     hunk_fg: &'static str,
     header_fg: &'static str,
 }
@@ -25,6 +27,8 @@ const DIFF_COLORS_LIGHT: DiffColors = DiffColors {
     remove_bg: "#ffebee",
     gutter_add_bg: "#c1f7c2",    // Darker green for gutter
     gutter_remove_bg: "#ffcdd2", // Darker red for gutter
+    add_word_bg: "#acf2bd",      // Changed words within an added line
+    remove_word_bg: "#fdb8c0",   // Changed words within a removed line
     hunk_fg: "#1565c0",
     header_fg: "#6a1b9a",
 };
@@ -35,6 +39,8 @@ const DIFF_COLORS_DARK: DiffColors = DiffColors {
     remove_bg: "#3d1a1a",
     gutter_add_bg: "#2e5a2e",    // Brighter green for gutter
     gutter_remove_bg: "#5a2e2e", // Brighter red for gutter
+    add_word_bg: "#2f6b2f",      // Changed words within an added line
+    remove_word_bg: "#7a3232",   // Changed words within a removed line
     hunk_fg: "#64b5f6",
     header_fg: "#ce93d8",
 };
@@ -61,6 +67,12 @@ fn update_diff_tag_colors(buffer: &gtk::TextBuffer) {
     if let Some(remove_tag) = tag_table.lookup("diff-remove") {
         remove_tag.set_property("paragraph-background", &colors.remove_bg);
     }
+    if let Some(add_word_tag) = tag_table.lookup("diff-add-word") {
+        add_word_tag.set_property("background", &colors.add_word_bg);
+    }
+    if let Some(remove_word_tag) = tag_table.lookup("diff-remove-word") {
+        remove_word_tag.set_property("background", &colors.remove_word_bg);
+    }
     if let Some(hunk_tag) = tag_table.lookup("diff-hunk") {
         hunk_tag.set_property("foreground", &colors.hunk_fg);
     }
@@ -88,33 +100,47 @@ fn refresh_diff_colors(diff_files_box: &gtk::Box) {
             // Only process expanded sections (collapsed ones have no child widget)
             if let Some(row) = expander.child() {
                 if let Ok(row_box) = row.downcast::<gtk::Box>() {
-                    // The row contains: gutter (TextView) + h_scroller (ScrolledWindow)
-                    let mut row_child = row_box.first_child();
-                    while let Some(rc) = row_child {
-                        let rc_next = rc.next_sibling();
-
-                        // Update gutter colors (first child is the gutter TextView)
-                        if let Ok(gutter_view) = rc.clone().downcast::<gtk::TextView>() {
-                            let buffer = gutter_view.buffer();
-                            update_diff_tag_colors(&buffer);
-                        }
+                    refresh_row_colors(&row_box);
+                }
+            }
+        }
 
-                        // Update code view colors (ScrolledWindow contains the SourceView)
-                        if let Ok(scroller) = rc.clone().downcast::<gtk::ScrolledWindow>() {
-                            if let Some(view_widget) = scroller.child() {
-                                if let Ok(source_view) = view_widget.downcast::<sv::View>() {
-                                    let buffer = source_view.buffer();
-                                    update_diff_tag_colors(buffer.upcast_ref::<gtk::TextBuffer>());
-                                }
-                            }
-                        }
-                        row_child = rc_next;
+        child = next;
+    }
+}
+
+/// Refresh the colors of one file row. A unified row contains gutter (TextView) +
+/// h_scroller (ScrolledWindow); a split row contains one such pair per pane.
+fn refresh_row_colors(row_box: &gtk::Box) {
+    let mut row_child = row_box.first_child();
+    while let Some(rc) = row_child {
+        let rc_next = rc.next_sibling();
+
+        // Update gutter colors (first child is the gutter TextView)
+        if let Ok(gutter_view) = rc.clone().downcast::<gtk::TextView>() {
+            let buffer = gutter_view.buffer();
+            update_diff_tag_colors(&buffer);
+        }
+
+        // Update code view colors (ScrolledWindow contains the SourceView)
+        if let Ok(scroller) = rc.clone().downcast::<gtk::ScrolledWindow>() {
+            if let Some(view_widget) = scroller.child() {
+                if let Ok(source_view) = view_widget.downcast::<sv::View>() {
+                    let buffer = source_view.buffer();
+                    update_diff_tag_colors(buffer.upcast_ref::<gtk::TextBuffer>());
+                    // Switch the syntax highlighting scheme along with the diff colors
+                    if let Ok(source_buffer) = buffer.downcast::<sv::Buffer>() {
+                        source_buffer.set_style_scheme(diff_style_scheme().as_ref());
                     }
                 }
             }
         }
 
-        child = next;
+        // Split layout: recurse into each pane
+        if let Ok(pane) = rc.clone().downcast::<gtk::Box>() {
+            refresh_row_colors(&pane);
+        }
+        row_child = rc_next;
     }
 }
 
@@ -139,10 +165,14 @@ enum DiffLineKind {
 #[derive(Debug)]
 struct PreparedDiffSection {
     label: String,
+    path: Option<String>,
     gutter_text: String,
     right_text: String,
     kinds: Vec<DiffLineKind>,
     gutter_chars: usize,
+    /// Path the source language is guessed from (the old path of a deleted
+    /// file), when the file's row is built.
+    language_path: Option<String>,
 }
 
 fn clear_container(container: &gtk::Box) {
@@ -271,9 +301,23 @@ fn set_all_file_expanders(diff_files_box: &gtk::Box, expanded: bool) {
 #[derive(Debug)]
 struct DiffSection {
     label: String,
+    /// Path of the file on the new side of the diff (`None` for deletions).
+    path: Option<String>,
+    /// Path of the file on the old side of the diff (`None` for additions).
+    old_path: Option<String>,
     text: String,
 }
 
+/// What the per-file header menu needs to act on a file: the window it
+/// lives in and the commit whose version of the file is shown
+/// (`git::WORKING_TREE_ID` for uncommitted changes).
+#[derive(Clone)]
+struct FileHeaderContext {
+    ui: WindowUi,
+    state: AppState,
+    commit_sha: String,
+}
+
 fn file_change_label(file: &git::FileChange) -> String {
     match file.kind {
         git::FileChangeKind::Renamed | git::FileChangeKind::Copied => {
@@ -286,6 +330,21 @@ fn file_change_label(file: &git::FileChange) -> String {
             .clone()
             .or_else(|| file.new_path.clone())
             .unwrap_or_else(|| "Deleted file".to_string()),
+        git::FileChangeKind::Untracked
+        | git::FileChangeKind::Ignored
+        | git::FileChangeKind::Conflicted => {
+            let path = file
+                .new_path
+                .as_deref()
+                .or(file.old_path.as_deref())
+                .unwrap_or("?");
+            let status = match file.kind {
+                git::FileChangeKind::Untracked => "untracked",
+                git::FileChangeKind::Ignored => "ignored",
+                _ => "conflicted",
+            };
+            format!("{path} ({status})")
+        }
         _ => file
             .new_path
             .clone()
@@ -303,12 +362,24 @@ fn sections_from_commit_diff(diff: &git::CommitDiff) -> Vec<DiffSection> {
                 match file.kind {
                     git::FileChangeKind::Renamed => "Renamed with no content changes\n".to_string(),
                     git::FileChangeKind::Copied => "Copied with no content changes\n".to_string(),
+                    git::FileChangeKind::Untracked => "Empty untracked file\n".to_string(),
+                    git::FileChangeKind::Ignored => "Ignored by .gitignore\n".to_string(),
+                    git::FileChangeKind::Conflicted => "Unresolved merge conflict\n".to_string(),
                     _ => String::new(),
                 }
             } else {
                 file.patch.clone()
             };
-            DiffSection { label, text }
+            let path = match file.kind {
+                git::FileChangeKind::Deleted => None,
+                _ => file.new_path.clone(),
+            };
+            DiffSection {
+                label,
+                path,
+                old_path: file.old_path.clone(),
+                text,
+            }
         })
         .collect()
 }
@@ -338,6 +409,22 @@ fn apply_basic_diff_line_tags(buffer: &gtk::TextBuffer) {
     remove_tag.set_property("paragraph-background-set", true);
     remove_tag.set_property("weight-set", true);
 
+    // Changed words inside added/removed lines. Created after the line tags so they take
+    // priority, and using `background` so they sit on top of the paragraph background.
+    let add_word_tag = tag_table.lookup("diff-add-word").unwrap_or_else(|| {
+        let tag = gtk::TextTag::new(Some("diff-add-word"));
+        tag_table.add(&tag);
+        tag
+    });
+    add_word_tag.set_property("background", &colors.add_word_bg);
+
+    let remove_word_tag = tag_table.lookup("diff-remove-word").unwrap_or_else(|| {
+        let tag = gtk::TextTag::new(Some("diff-remove-word"));
+        tag_table.add(&tag);
+        tag
+    });
+    remove_word_tag.set_property("background", &colors.remove_word_bg);
+
     let hunk_tag = tag_table.lookup("diff-hunk").unwrap_or_else(|| {
         let tag = gtk::TextTag::new(Some("diff-hunk"));
         tag_table.add(&tag);
@@ -579,6 +666,200 @@ fn apply_diff_line_tags_by_kind(buffer: &gtk::TextBuffer, kinds: &[DiffLineKind]
     }
 }
 
+/// Char-offset `(start, end)` ranges of the changed words in one line.
+type WordSpans = Vec<(usize, usize)>;
+
+/// Highlight the changed words of paired lines (see `word_change_spans`) on top of the
+/// line colors applied by `apply_diff_line_tags_by_kind`.
+fn apply_word_change_tags(
+    buffer: &gtk::TextBuffer,
+    kinds: &[DiffLineKind],
+    word_spans: &[WordSpans],
+) {
+    let tag_table = buffer.tag_table();
+    let add_word_tag = tag_table.lookup("diff-add-word");
+    let remove_word_tag = tag_table.lookup("diff-remove-word");
+
+    for (idx, (kind, spans)) in kinds.iter().zip(word_spans).enumerate() {
+        let tag = match kind {
+            DiffLineKind::Add => &add_word_tag,
+            DiffLineKind::Remove => &remove_word_tag,
+            _ => continue,
+        };
+        let Some(tag) = tag else {
+            continue;
+        };
+        for &(start, end) in spans {
+            let (Some(span_start), Some(span_end)) = (
+                buffer.iter_at_line_offset(idx as i32, start as i32),
+                buffer.iter_at_line_offset(idx as i32, end as i32),
+            ) else {
+                continue;
+            };
+            buffer.apply_tag(tag, &span_start, &span_end);
+        }
+    }
+}
+
+/// Above this many token pairs, a changed line is not diffed word by word (the middle
+/// part left after trimming the common prefix/suffix is highlighted as a whole).
+const MAX_WORD_DIFF_CELLS: usize = 40_000;
+
+/// Split a line into word, whitespace and punctuation tokens as char-offset ranges.
+fn word_tokens(line: &str) -> Vec<(usize, usize)> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev: Option<Class> = None;
+    for (idx, c) in line.chars().enumerate() {
+        let current = class(c);
+        // Punctuation is one token per char; words and whitespace are runs.
+        let joins = prev.as_ref() == Some(&current) && current != Class::Other;
+        if !joins && idx > start {
+            tokens.push((start, idx));
+            start = idx;
+        }
+        prev = Some(current);
+    }
+    let len = line.chars().count();
+    if len > start {
+        tokens.push((start, len));
+    }
+    tokens
+}
+
+/// Changed-word spans of a removed line and the added line it was paired with.
+///
+/// Tokens that are not part of the longest common token subsequence are changed;
+/// neighbouring changed tokens are merged into one span. Lines with no words in
+/// common get no spans: the whole line is different and the line color says enough.
+fn changed_word_spans(old: &str, new: &str) -> (WordSpans, WordSpans) {
+    let old_chars: Vec<char> = old.chars().collect();
+    let new_chars: Vec<char> = new.chars().collect();
+    let old_tokens = word_tokens(old);
+    let new_tokens = word_tokens(new);
+    let old_words: Vec<&[char]> = old_tokens.iter().map(|&(s, e)| &old_chars[s..e]).collect();
+    let new_words: Vec<&[char]> = new_tokens.iter().map(|&(s, e)| &new_chars[s..e]).collect();
+
+    // Trim the common prefix and suffix first; most edits touch a small middle part.
+    let prefix = old_words
+        .iter()
+        .zip(&new_words)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_words[prefix..]
+        .iter()
+        .rev()
+        .zip(new_words[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_words[prefix..old_words.len() - suffix];
+    let new_mid = &new_words[prefix..new_words.len() - suffix];
+
+    // Common flags for every token; prefix and suffix are common by construction.
+    let mut old_common = vec![true; old_words.len()];
+    let mut new_common = vec![true; new_words.len()];
+    old_common[prefix..old_words.len() - suffix].fill(false);
+    new_common[prefix..new_words.len() - suffix].fill(false);
+
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if n * m <= MAX_WORD_DIFF_CELLS {
+        // Classic LCS table over the middle tokens, then walk it to mark common ones.
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old_mid[i] == new_mid[j] {
+                old_common[prefix + i] = true;
+                new_common[prefix + j] = true;
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    let shares_words = old_words
+        .iter()
+        .zip(&old_common)
+        .any(|(word, &common)| common && word.iter().any(|c| !c.is_whitespace()));
+    if !shares_words {
+        return (Vec::new(), Vec::new());
+    }
+
+    let merge = |tokens: &[(usize, usize)], common: &[bool]| {
+        let mut spans: WordSpans = Vec::new();
+        for (&(start, end), _) in tokens.iter().zip(common).filter(|(_, c)| !**c) {
+            match spans.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => spans.push((start, end)),
+            }
+        }
+        spans
+    };
+    (
+        merge(&old_tokens, &old_common),
+        merge(&new_tokens, &new_common),
+    )
+}
+
+/// Changed-word spans (char offsets) for every line of a section, index-aligned with
+/// `kinds`. Within each run of changes, removed lines are paired in order with the
+/// added lines that follow them; unpaired lines get no spans.
+fn word_change_spans(right_text: &str, kinds: &[DiffLineKind]) -> Vec<WordSpans> {
+    let lines: Vec<&str> = right_text.lines().collect();
+    let mut spans = vec![Vec::new(); kinds.len()];
+    let mut removed: Vec<usize> = Vec::new();
+    let mut added: Vec<usize> = Vec::new();
+
+    // One extra iteration (`None`) flushes the last run.
+    for idx in 0..=kinds.len() {
+        match kinds.get(idx) {
+            Some(DiffLineKind::Remove) if added.is_empty() => removed.push(idx),
+            Some(DiffLineKind::Add) => added.push(idx),
+            kind => {
+                for (&o, &n) in removed.iter().zip(&added) {
+                    let old = lines.get(o).copied().unwrap_or("");
+                    let new = lines.get(n).copied().unwrap_or("");
+                    (spans[o], spans[n]) = changed_word_spans(old, new);
+                }
+                removed.clear();
+                added.clear();
+                if kind == Some(&DiffLineKind::Remove) {
+                    removed.push(idx);
+                }
+            }
+        }
+    }
+    spans
+}
+
 /// Apply diff color tags to the gutter buffer based on line kinds.
 /// Uses darker/heavier colors than the code view for visual distinction.
 fn apply_gutter_line_tags(buffer: &gtk::TextBuffer, kinds: &[DiffLineKind]) {
@@ -626,12 +907,8 @@ fn apply_gutter_line_tags(buffer: &gtk::TextBuffer, kinds: &[DiffLineKind]) {
     }
 }
 
-fn build_file_row(prepared: &PreparedDiffSection, global_gutter_chars: usize) -> gtk::Box {
-    let gutter_text = &prepared.gutter_text;
-    let right_text = &prepared.right_text;
-    let kinds = &prepared.kinds;
-
-    // Left: gutter
+/// Line-number gutter shown to the left of a code view.
+fn build_gutter_view(gutter_text: &str, kinds: &[DiffLineKind], chars: usize) -> gtk::TextView {
     let gutter_buffer = gtk::TextBuffer::new(None);
     gutter_buffer.set_text(gutter_text);
     apply_gutter_line_tags(&gutter_buffer, kinds);
@@ -653,19 +930,66 @@ fn build_file_row(prepared: &PreparedDiffSection, global_gutter_chars: usize) ->
     // Size the gutter to the minimum width needed to show line numbers and symbols.
     // (Non-expanding, so the right pane takes the remaining space.)
     // Text padding is included in gutter_chars via leading spaces.
-    let probe = "0".repeat(global_gutter_chars.max(1));
+    let probe = "0".repeat(chars.max(1));
     let layout = gutter_view.create_pango_layout(Some(&probe));
     let (probe_px, _) = layout.pixel_size();
     gutter_view.set_width_request(probe_px);
     gutter_view.add_css_class("diff-gutter");
+    gutter_view
+}
+
+/// Guess the source language of a diffed file from its path and the code in the diff.
+fn guess_language(
+    path: Option<&str>,
+    right_text: &str,
+    kinds: &[DiffLineKind],
+) -> Option<sv::Language> {
+    let path = path?;
+    // Sample only code lines: hunk headers would hide e.g. a shebang from content sniffing.
+    let sample = right_text
+        .lines()
+        .zip(kinds)
+        .filter(|(_, kind)| {
+            matches!(
+                kind,
+                DiffLineKind::Add | DiffLineKind::Remove | DiffLineKind::Context
+            )
+        })
+        .map(|(line, _)| line)
+        .take(64)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let (content_type, _uncertain) = gio::content_type_guess(Some(path), sample.as_bytes());
+    sv::LanguageManager::default().guess_language(Some(path), Some(content_type.as_str()))
+}
 
-    // Right: diff text (single editable component)
+/// Syntax highlighting scheme matching the current adw light/dark style.
+pub(super) fn diff_style_scheme() -> Option<sv::StyleScheme> {
+    let id = if adw::StyleManager::default().is_dark() {
+        "Adwaita-dark"
+    } else {
+        "Adwaita"
+    };
+    sv::StyleSchemeManager::default().scheme(id)
+}
+
+/// Read-only code view for diff text, wrapped in a horizontal-only scroller.
+fn build_code_scroller(
+    text: &str,
+    kinds: &[DiffLineKind],
+    word_spans: &[WordSpans],
+    language: Option<&sv::Language>,
+) -> gtk::ScrolledWindow {
     let buffer = sv::Buffer::new(None);
-    // We do our own line coloring, so keep syntax highlighting off for consistent results.
-    buffer.set_highlight_syntax(false);
-    buffer.set_text(right_text);
+    // Syntax highlighting only sets foreground styles at the lowest tag priority, so it
+    // combines with our add/remove paragraph backgrounds and hunk/header colors.
+    buffer.set_language(language);
+    buffer.set_highlight_syntax(language.is_some());
+    buffer.set_style_scheme(diff_style_scheme().as_ref());
+    buffer.set_text(text);
 
-    apply_diff_line_tags_by_kind(buffer.upcast_ref::<gtk::TextBuffer>(), &kinds);
+    apply_diff_line_tags_by_kind(buffer.upcast_ref::<gtk::TextBuffer>(), kinds);
+    apply_word_change_tags(buffer.upcast_ref::<gtk::TextBuffer>(), kinds, word_spans);
 
     let view = sv::View::with_buffer(&buffer);
     // Diff is a viewer: keep it read-only to avoid IME/input-method paths on Wayland
@@ -698,6 +1022,38 @@ fn build_file_row(prepared: &PreparedDiffSection, global_gutter_chars: usize) ->
         .build();
     h_scroller.set_hexpand(true);
     h_scroller.set_child(Some(&view));
+    h_scroller
+}
+
+fn build_file_row(
+    prepared: &PreparedDiffSection,
+    global_gutter_chars: usize,
+    split: bool,
+) -> gtk::Box {
+    // Computed here rather than when the diff loads, so collapsed files cost nothing.
+    let word_spans = word_change_spans(&prepared.right_text, &prepared.kinds);
+    let language = guess_language(
+        prepared.language_path.as_deref(),
+        &prepared.right_text,
+        &prepared.kinds,
+    );
+    if split {
+        return build_split_file_row(
+            prepared,
+            &word_spans,
+            language.as_ref(),
+            global_gutter_chars,
+        );
+    }
+
+    let gutter_view =
+        build_gutter_view(&prepared.gutter_text, &prepared.kinds, global_gutter_chars);
+    let h_scroller = build_code_scroller(
+        &prepared.right_text,
+        &prepared.kinds,
+        &word_spans,
+        language.as_ref(),
+    );
 
     // Two-widget layout: gutter on the left, text on the right
     // No spacing between gutter and code view so colored backgrounds are seamless
@@ -711,10 +1067,265 @@ fn build_file_row(prepared: &PreparedDiffSection, global_gutter_chars: usize) ->
     row
 }
 
+/// One pane (old or new) of the side-by-side layout.
+#[derive(Debug, Default)]
+struct SplitSide {
+    /// Width of the line-number column.
+    width: usize,
+    gutter_text: String,
+    text: String,
+    kinds: Vec<DiffLineKind>,
+    /// Changed-word spans per line (see `word_change_spans`).
+    word_spans: Vec<WordSpans>,
+}
+
+impl SplitSide {
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    fn push(
+        &mut self,
+        number: Option<i64>,
+        sym: char,
+        text: &str,
+        kind: DiffLineKind,
+        word_spans: &[(usize, usize)],
+    ) {
+        let number = number.map(|v| v.to_string()).unwrap_or_default();
+        let width = self.width;
+        self.gutter_text
+            .push_str(&format!(" {number:>width$} {sym}\n"));
+        self.text.push_str(text);
+        self.text.push('\n');
+        self.kinds.push(kind);
+        self.word_spans.push(word_spans.to_vec());
+    }
+
+    /// Blank line keeping both panes aligned where the other side has content.
+    fn push_filler(&mut self) {
+        self.push(None, ' ', "", DiffLineKind::Other, &[]);
+    }
+}
+
+/// A removed or added line waiting to be paired: line number, text and its
+/// index in the unified section.
+type PendingLine<'a> = (Option<i64>, &'a str, usize);
+
+/// Emit a run of removed/added lines side by side, padding the shorter side.
+fn flush_changes(
+    removed: &mut Vec<PendingLine>,
+    added: &mut Vec<PendingLine>,
+    word_spans: &[WordSpans],
+    old: &mut SplitSide,
+    new: &mut SplitSide,
+) {
+    let spans_at = |idx: usize| word_spans.get(idx).map(Vec::as_slice).unwrap_or(&[]);
+    for i in 0..removed.len().max(added.len()) {
+        match removed.get(i) {
+            Some(&(n, text, idx)) => old.push(n, '-', text, DiffLineKind::Remove, spans_at(idx)),
+            None => old.push_filler(),
+        }
+        match added.get(i) {
+            Some(&(n, text, idx)) => new.push(n, '+', text, DiffLineKind::Add, spans_at(idx)),
+            None => new.push_filler(),
+        }
+    }
+    removed.clear();
+    added.clear();
+}
+
+/// Split the unified diff of a section into old and new panes with the same
+/// number of lines, so hunks line up when shown next to each other.
+///
+/// Within each run of changes, removed lines are paired with added lines in
+/// order; the shorter side is padded with filler lines.
+fn build_split_sides(
+    right_text: &str,
+    kinds: &[DiffLineKind],
+    word_spans: &[WordSpans],
+    width: usize,
+) -> (SplitSide, SplitSide) {
+    let mut old = SplitSide::new(width);
+    let mut new = SplitSide::new(width);
+    let mut old_line: Option<i64> = None;
+    let mut new_line: Option<i64> = None;
+    let mut removed: Vec<PendingLine> = Vec::new();
+    let mut added: Vec<PendingLine> = Vec::new();
+
+    for (idx, (line, kind)) in right_text.lines().zip(kinds).enumerate() {
+        match kind {
+            DiffLineKind::Remove => {
+                removed.push((old_line, line, idx));
+                if let Some(v) = &mut old_line {
+                    *v += 1;
+                }
+            }
+            DiffLineKind::Add => {
+                added.push((new_line, line, idx));
+                if let Some(v) = &mut new_line {
+                    *v += 1;
+                }
+            }
+            DiffLineKind::Context => {
+                flush_changes(&mut removed, &mut added, word_spans, &mut old, &mut new);
+                old.push(old_line, ' ', line, *kind, &[]);
+                new.push(new_line, ' ', line, *kind, &[]);
+                if let Some(v) = &mut old_line {
+                    *v += 1;
+                }
+                if let Some(v) = &mut new_line {
+                    *v += 1;
+                }
+            }
+            _ => {
+                flush_changes(&mut removed, &mut added, word_spans, &mut old, &mut new);
+                if let Some((o, n)) = parse_hunk_header(line) {
+                    old_line = Some(o);
+                    new_line = Some(n);
+                }
+                old.push(None, ' ', line, *kind, &[]);
+                new.push(None, ' ', line, *kind, &[]);
+            }
+        }
+    }
+    flush_changes(&mut removed, &mut added, word_spans, &mut old, &mut new);
+
+    (old, new)
+}
+
+/// Side-by-side layout: old content on the left, new content on the right.
+///
+/// Both panes have the same number of lines and share the outer vertical
+/// scroll; their horizontal scrolling is kept in sync.
+fn build_split_file_row(
+    prepared: &PreparedDiffSection,
+    word_spans: &[WordSpans],
+    language: Option<&sv::Language>,
+    global_gutter_chars: usize,
+) -> gtk::Box {
+    // The unified gutter is " old new sym"; each pane shows one number column.
+    let width = global_gutter_chars.saturating_sub(4) / 2;
+    let (old, new) = build_split_sides(&prepared.right_text, &prepared.kinds, word_spans, width);
+
+    let row = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .homogeneous(true)
+        .hexpand(true)
+        .build();
+
+    let mut adjustments = Vec::with_capacity(2);
+    for side in [&old, &new] {
+        let gutter_view = build_gutter_view(&side.gutter_text, &side.kinds, width + 3);
+        let h_scroller = build_code_scroller(&side.text, &side.kinds, &side.word_spans, language);
+        adjustments.push(h_scroller.hadjustment());
+
+        let pane = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .hexpand(true)
+            .build();
+        pane.append(&gutter_view);
+        pane.append(&h_scroller);
+        row.append(&pane);
+    }
+    if let Some(new_pane) = row.last_child() {
+        new_pane.add_css_class("diff-split-new");
+    }
+
+    adjustments[0]
+        .bind_property("value", &adjustments[1], "value")
+        .bidirectional()
+        .build();
+
+    row
+}
+
+/// Build the "more" menu shown in a file header, with actions scoped to
+/// the file via an action group on the expander.
+fn build_file_header_menu(
+    expander: &gtk::Expander,
+    ctx: &FileHeaderContext,
+    file_path: &str,
+) -> gtk::MenuButton {
+    let group = gio::SimpleActionGroup::new();
+    // Uncommitted files have no commit to blame at.
+    let can_blame = ctx.commit_sha != git::WORKING_TREE_ID;
+
+    let blame = gio::SimpleAction::new("blame", None);
+    let ctx_for_blame = ctx.clone();
+    let path_for_blame = file_path.to_string();
+    blame.connect_activate(move |_, _| {
+        super::blame::show_blame_dialog(
+            &ctx_for_blame.ui,
+            &ctx_for_blame.state,
+            &ctx_for_blame.commit_sha,
+            &path_for_blame,
+        );
+    });
+    if can_blame {
+        group.add_action(&blame);
+    }
+
+    let history = gio::SimpleAction::new("history", None);
+    let ctx_for_history = ctx.clone();
+    let path_for_history = file_path.to_string();
+    history.connect_activate(move |_, _| {
+        super::repo::set_path_filter(
+            &ctx_for_history.ui,
+            &ctx_for_history.state,
+            Some(path_for_history.clone()),
+        );
+    });
+    group.add_action(&history);
+
+    let folder = std::path::Path::new(file_path)
+        .parent()
+        .and_then(|p| p.to_str())
+        .filter(|p| !p.is_empty())
+        .map(str::to_string);
+    if let Some(folder) = folder.clone() {
+        let folder_history = gio::SimpleAction::new("folder-history", None);
+        let ctx_for_folder = ctx.clone();
+        folder_history.connect_activate(move |_, _| {
+            super::repo::set_path_filter(
+                &ctx_for_folder.ui,
+                &ctx_for_folder.state,
+                Some(folder.clone()),
+            );
+        });
+        group.add_action(&folder_history);
+    }
+    expander.insert_action_group("file", Some(&group));
+
+    let menu = gio::Menu::new();
+    if can_blame {
+        menu.append(Some("Blame"), Some("file.blame"));
+    }
+    menu.append(Some("History of This File"), Some("file.history"));
+    if folder.is_some() {
+        menu.append(Some("History of This Folder"), Some("file.folder-history"));
+    }
+
+    let button = gtk::MenuButton::builder()
+        .icon_name("view-more-symbolic")
+        .tooltip_text("File Actions")
+        .valign(gtk::Align::Center)
+        .menu_model(&menu)
+        .build();
+    button.add_css_class("flat");
+    button.add_css_class("file-menu-btn");
+    button.set_can_focus(false);
+    button
+}
+
 fn build_file_expander_lazy(
     prepared: &PreparedDiffSection,
     expanded: bool,
     global_gutter_chars: usize,
+    header_ctx: &FileHeaderContext,
 ) -> gtk::Expander {
     let expander = gtk::Expander::builder().expanded(expanded).build();
 
@@ -726,13 +1337,18 @@ fn build_file_expander_lazy(
         Some(gtk::pango::EllipsizeMode::Middle),
     );
     filename_row.reveal_on_hover(&expander);
+    if let Some(path) = prepared.path.as_deref() {
+        let menu_button = build_file_header_menu(&expander, header_ctx, path);
+        filename_row.widget.append(&menu_button);
+    }
     let header = filename_row.with_trailing_spacer();
     header.set_halign(gtk::Align::Start);
     expander.set_label_widget(Some(&header));
 
     // If expanded initially, build the heavy child once now.
+    let split_view = header_ctx.ui.repo_view.diff_split_view.clone();
     if expanded {
-        let row = build_file_row(prepared, global_gutter_chars);
+        let row = build_file_row(prepared, global_gutter_chars, *split_view.borrow());
         expander.set_child(Some(&row));
     }
 
@@ -740,16 +1356,19 @@ fn build_file_expander_lazy(
     // This keeps the widget tree lightweight during paned/window resizing.
     let prepared_for_cb = PreparedDiffSection {
         label: prepared.label.clone(),
+        path: prepared.path.clone(),
         gutter_text: prepared.gutter_text.clone(),
         right_text: prepared.right_text.clone(),
         kinds: prepared.kinds.clone(),
         gutter_chars: prepared.gutter_chars,
+        language_path: prepared.language_path.clone(),
     };
 
     expander.connect_expanded_notify(move |exp| {
         if exp.is_expanded() {
             if exp.child().is_none() {
-                let row = build_file_row(&prepared_for_cb, global_gutter_chars);
+                let row =
+                    build_file_row(&prepared_for_cb, global_gutter_chars, *split_view.borrow());
                 exp.set_child(Some(&row));
             }
         } else {
@@ -761,84 +1380,267 @@ fn build_file_expander_lazy(
     expander
 }
 
+/// Heading shown above a group of files, e.g. "Staged changes".
+fn build_group_heading(text: &str) -> gtk::Label {
+    let label = gtk::Label::builder()
+        .label(text)
+        .halign(gtk::Align::Start)
+        .xalign(0.0)
+        .margin_start(10)
+        .margin_end(10)
+        .margin_top(8)
+        .margin_bottom(4)
+        .build();
+    label.add_css_class("heading");
+    label
+}
+
+/// Width in pixels of the +/- bar of the file with the most changed lines;
+/// other files' bars are scaled against it.
+const DIFFSTAT_BAR_WIDTH: usize = 80;
+
+/// Above this many files the diffstat starts collapsed.
+const DIFFSTAT_EXPANDED_FILES: usize = 20;
+
+/// Summary line in the style of `git diff --stat`.
+fn diffstat_summary(files: usize, insertions: usize, deletions: usize) -> String {
+    let plural = |n: usize, word: &str| {
+        if n == 1 {
+            format!("{n} {word}")
+        } else {
+            format!("{n} {word}s")
+        }
+    };
+    format!(
+        "{} changed, {}(+), {}(−)",
+        plural(files, "file"),
+        plural(insertions, "insertion"),
+        plural(deletions, "deletion")
+    )
+}
+
+/// Widths of the added and removed parts of a file's bar. A side with any
+/// lines gets at least one pixel so small changes stay visible.
+fn diffstat_bar_widths(insertions: usize, deletions: usize, max_lines: usize) -> (usize, usize) {
+    let scale = |n: usize| {
+        if n == 0 {
+            0
+        } else {
+            (n * DIFFSTAT_BAR_WIDTH / max_lines.max(1)).max(1)
+        }
+    };
+    (scale(insertions), scale(deletions))
+}
+
+fn diffstat_bar_part(width: usize, css_class: &str) -> gtk::Box {
+    let part = gtk::Box::builder()
+        .width_request(width as i32)
+        .height_request(8)
+        .valign(gtk::Align::Center)
+        .build();
+    part.add_css_class(css_class);
+    part
+}
+
+/// Collapsible summary above the files: the total size of the change, then
+/// one row per file with its line counts and a proportional +/- bar.
+///
+/// The expander is wrapped in a box so the "expand/collapse all files"
+/// buttons, which look for direct expander children, leave it alone.
+fn build_diffstat(diff: &git::CommitDiff) -> gtk::Box {
+    let counts: Vec<(usize, usize)> = diff.files.iter().map(|f| f.line_counts()).collect();
+    let insertions: usize = counts.iter().map(|(ins, _)| ins).sum();
+    let deletions: usize = counts.iter().map(|(_, del)| del).sum();
+    let max_lines = counts.iter().map(|(ins, del)| ins + del).max().unwrap_or(0);
+
+    let grid = gtk::Grid::builder()
+        .column_spacing(12)
+        .row_spacing(2)
+        .margin_start(16)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    for (row, (file, (ins, del))) in diff.files.iter().zip(&counts).enumerate() {
+        let row = row as i32;
+        let label = file_change_label(file);
+        let path_label = gtk::Label::builder()
+            .label(&label)
+            .tooltip_text(&label)
+            .xalign(0.0)
+            .hexpand(true)
+            .ellipsize(gtk::pango::EllipsizeMode::Middle)
+            .build();
+        path_label.add_css_class("monospace");
+        grid.attach(&path_label, 0, row, 1, 1);
+
+        let count_label = gtk::Label::builder().xalign(1.0).build();
+        count_label.add_css_class("monospace");
+        count_label.add_css_class("dim-label");
+        let bar = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .width_request(DIFFSTAT_BAR_WIDTH as i32)
+            .build();
+        if *ins == 0 && *del == 0 {
+            count_label.set_label(if file.binary { "binary" } else { "0" });
+        } else {
+            count_label.set_label(&format!("+{ins} −{del}"));
+            let (ins_width, del_width) = diffstat_bar_widths(*ins, *del, max_lines);
+            bar.append(&diffstat_bar_part(ins_width, "diffstat-added"));
+            bar.append(&diffstat_bar_part(del_width, "diffstat-removed"));
+        }
+        grid.attach(&count_label, 1, row, 1, 1);
+        grid.attach(&bar, 2, row, 1, 1);
+    }
+
+    let expander = gtk::Expander::builder()
+        .label(diffstat_summary(diff.files.len(), insertions, deletions))
+        .expanded(diff.files.len() <= DIFFSTAT_EXPANDED_FILES)
+        .child(&grid)
+        .build();
+
+    let container = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .margin_start(10)
+        .margin_end(10)
+        .margin_top(8)
+        .margin_bottom(4)
+        .build();
+    container.append(&expander);
+    container
+}
+
+/// Replace the contents of `diff_files_box` with one expander per file of `diff`.
+fn render_diff(
+    diff: &git::CommitDiff,
+    diff_files_box: &gtk::Box,
+    expand_button: &gtk::Button,
+    collapse_button: &gtk::Button,
+    header_ctx: &FileHeaderContext,
+) {
+    let sections = sections_from_commit_diff(diff);
+    clear_container(diff_files_box);
+
+    if let Some(preamble) = diff.preamble.as_ref().filter(|s| !s.trim().is_empty()) {
+        let preamble_label = gtk::Label::builder()
+            .label(preamble.as_str())
+            .halign(gtk::Align::Start)
+            .wrap(true)
+            .xalign(0.0)
+            .margin_start(10)
+            .margin_end(10)
+            .margin_top(8)
+            .margin_bottom(4)
+            .build();
+        preamble_label.add_css_class("dim-label");
+        diff_files_box.append(&preamble_label);
+    }
+
+    if sections.is_empty() {
+        if diff.preamble.is_none() {
+            set_placeholder(
+                diff_files_box,
+                crate::ui::placeholder::ICON_INFO,
+                "No textual changes to display",
+            );
+        }
+        update_expand_collapse_buttons(diff_files_box, expand_button, collapse_button);
+        return;
+    }
+
+    diff_files_box.append(&build_diffstat(diff));
+
+    let focus_idx = take_pending_file(diff_files_box, header_ctx).and_then(|file| {
+        sections.iter().position(|s| {
+            s.path.as_deref() == Some(file.as_str()) || s.old_path.as_deref() == Some(file.as_str())
+        })
+    });
+
+    let mut prepared_sections: Vec<PreparedDiffSection> = Vec::with_capacity(sections.len());
+    for section in sections {
+        let (gutter_text, right_text, kinds, gutter_chars) =
+            build_diff_gutter_and_text(&section.text);
+        let language_path = section.path.clone().or(section.old_path);
+        prepared_sections.push(PreparedDiffSection {
+            label: section.label,
+            path: section.path,
+            gutter_text,
+            right_text,
+            kinds,
+            gutter_chars,
+            language_path,
+        });
+    }
+
+    // Make the gutter width consistent across all files in this commit diff.
+    let global_gutter_chars = prepared_sections
+        .iter()
+        .map(|s| s.gutter_chars)
+        .max()
+        .unwrap_or(1);
+
+    for (idx, prepared) in prepared_sections.iter().enumerate() {
+        if let Some((_, heading)) = diff.group_headings.iter().find(|(at, _)| *at == idx) {
+            diff_files_box.append(&build_group_heading(heading));
+        }
+        // Expand only the first file by default to keep huge diffs responsive.
+        let expanded = idx < DEFAULT_EXPANDED_FILES || focus_idx == Some(idx);
+        let expander =
+            build_file_expander_lazy(prepared, expanded, global_gutter_chars, header_ctx);
+        let diff_files_box_for_notify = diff_files_box.clone();
+        let expand_for_notify = expand_button.clone();
+        let collapse_for_notify = collapse_button.clone();
+        expander.connect_expanded_notify(move |_| {
+            update_expand_collapse_buttons(
+                &diff_files_box_for_notify,
+                &expand_for_notify,
+                &collapse_for_notify,
+            );
+        });
+        diff_files_box.append(&expander);
+        if focus_idx == Some(idx)
+            && let Some(viewport) = diff_files_box.parent().and_downcast::<gtk::Viewport>()
+        {
+            // Applied once the files are allocated.
+            viewport.scroll_to(&expander, None);
+        }
+    }
+
+    update_expand_collapse_buttons(diff_files_box, expand_button, collapse_button);
+}
+
+/// The file a deep link asked to reveal, if this is the diff it applies to:
+/// the main diff of the requested commit (or of whatever is shown first).
+fn take_pending_file(diff_files_box: &gtk::Box, header_ctx: &FileHeaderContext) -> Option<String> {
+    if diff_files_box != &header_ctx.ui.repo_view.diff_files_box {
+        return None;
+    }
+    let mut pending = header_ctx.state.pending_diff_file.borrow_mut();
+    let applies = pending
+        .as_ref()
+        .is_some_and(|(sha, _)| sha.as_ref().is_none_or(|sha| *sha == header_ctx.commit_sha));
+    if !applies {
+        return None;
+    }
+    pending.take().map(|(_, file)| file)
+}
+
 // Helper function to poll channel and update diff UI
 fn poll_diff_result(
     rx: mpsc::Receiver<Result<git::CommitDiff, git2::Error>>,
     diff_files_box: gtk::Box,
     expand_button: gtk::Button,
     collapse_button: gtk::Button,
+    header_ctx: FileHeaderContext,
 ) {
     match rx.try_recv() {
         Ok(Ok(diff)) => {
-            let sections = sections_from_commit_diff(&diff);
-            clear_container(&diff_files_box);
-
-            if let Some(preamble) = diff.preamble.as_ref().filter(|s| !s.trim().is_empty()) {
-                let preamble_label = gtk::Label::builder()
-                    .label(preamble.as_str())
-                    .halign(gtk::Align::Start)
-                    .wrap(true)
-                    .xalign(0.0)
-                    .margin_start(10)
-                    .margin_end(10)
-                    .margin_top(8)
-                    .margin_bottom(4)
-                    .build();
-                preamble_label.add_css_class("dim-label");
-                diff_files_box.append(&preamble_label);
-            }
-
-            if sections.is_empty() {
-                if diff.preamble.is_none() {
-                    set_placeholder(
-                        &diff_files_box,
-                        crate::ui::placeholder::ICON_INFO,
-                        "No textual changes to display",
-                    );
-                }
-                update_expand_collapse_buttons(&diff_files_box, &expand_button, &collapse_button);
-                return;
-            }
-
-            let mut prepared_sections: Vec<PreparedDiffSection> =
-                Vec::with_capacity(sections.len());
-            for section in sections {
-                let (gutter_text, right_text, kinds, gutter_chars) =
-                    build_diff_gutter_and_text(&section.text);
-                prepared_sections.push(PreparedDiffSection {
-                    label: section.label,
-                    gutter_text,
-                    right_text,
-                    kinds,
-                    gutter_chars,
-                });
-            }
-
-            // Make the gutter width consistent across all files in this commit diff.
-            let global_gutter_chars = prepared_sections
-                .iter()
-                .map(|s| s.gutter_chars)
-                .max()
-                .unwrap_or(1);
-
-            for (idx, prepared) in prepared_sections.iter().enumerate() {
-                // Expand only the first file by default to keep huge diffs responsive.
-                let expanded = idx < DEFAULT_EXPANDED_FILES;
-                let expander = build_file_expander_lazy(prepared, expanded, global_gutter_chars);
-                let diff_files_box_for_notify = diff_files_box.clone();
-                let expand_for_notify = expand_button.clone();
-                let collapse_for_notify = collapse_button.clone();
-                expander.connect_expanded_notify(move |_| {
-                    update_expand_collapse_buttons(
-                        &diff_files_box_for_notify,
-                        &expand_for_notify,
-                        &collapse_for_notify,
-                    );
-                });
-                diff_files_box.append(&expander);
-            }
-
-            update_expand_collapse_buttons(&diff_files_box, &expand_button, &collapse_button);
+            render_diff(
+                &diff,
+                &diff_files_box,
+                &expand_button,
+                &collapse_button,
+                &header_ctx,
+            );
         }
         Ok(Err(e)) => {
             let error_msg = format!("Error loading diff: {}", e);
@@ -859,6 +1661,7 @@ fn poll_diff_result(
                     diff_files_box_clone,
                     expand_btn_clone,
                     collapse_btn_clone,
+                    header_ctx,
                 );
             });
         }
@@ -896,6 +1699,7 @@ fn poll_metadata_result(
     expand_label: gtk::Label,
     full_message: std::rc::Rc<std::cell::RefCell<String>>,
     is_expanded: std::rc::Rc<std::cell::RefCell<bool>>,
+    ui: WindowUi,
 ) {
     match rx.try_recv() {
         Ok(Ok(metadata)) => {
@@ -910,6 +1714,7 @@ fn poll_metadata_result(
             sha_label.set_text(&metadata.git_sha);
             *sha_copy_text.borrow_mut() = metadata.git_sha.clone();
             sha_row.set_visible(true);
+            ui.repo_view.set_trailer_people(&metadata.trailers);
 
             *full_message.borrow_mut() = metadata.commit_message.clone();
             *is_expanded.borrow_mut() = false;
@@ -944,6 +1749,7 @@ fn poll_metadata_result(
                     expand_label_clone,
                     full_message_clone,
                     is_expanded_clone,
+                    ui,
                 );
             });
         }
@@ -962,6 +1768,10 @@ fn load_range_diff(
 ) {
     if let Some(ref path) = *state.current_path.borrow() {
         ui.repo_view.set_diff_chrome_visible(true);
+        ui.repo_view
+            .set_merge_diff_modes(Vec::new(), git::MergeDiffMode::default());
+        ui.repo_view.set_signatures(&[]);
+        ui.repo_view.set_trailer_people(&[]);
         set_diff_skeleton(&ui.repo_view.diff_files_box);
         set_metadata_skeleton(
             &ui.repo_view.diff_metadata_label,
@@ -987,7 +1797,18 @@ fn load_range_diff(
             let diff_result = git::get_range_diff(path_clone.to_str().unwrap(), &oldest, &newest);
             let _ = tx.send(diff_result);
         });
-        poll_diff_result(rx, diff_files_box_clone, expand_btn, collapse_btn);
+        let header_ctx = FileHeaderContext {
+            ui: ui.clone(),
+            state: state.clone(),
+            commit_sha: newest_sha.to_string(),
+        };
+        poll_diff_result(
+            rx,
+            diff_files_box_clone,
+            expand_btn,
+            collapse_btn,
+            header_ctx,
+        );
 
         clear_metadata_skeleton(
             &ui.repo_view.diff_metadata_label,
@@ -1010,9 +1831,143 @@ fn load_range_diff(
     }
 }
 
-fn load_commit_diff(ui: &WindowUi, state: &AppState, commit_sha: &str) {
+/// Show the staged, unstaged and untracked changes of the working tree.
+fn load_working_tree_diff(ui: &WindowUi, state: &AppState) {
+    if let Some(ref path) = *state.current_path.borrow() {
+        ui.repo_view.set_diff_chrome_visible(true);
+        ui.repo_view
+            .set_merge_diff_modes(Vec::new(), git::MergeDiffMode::default());
+        ui.repo_view.set_signatures(&[]);
+        ui.repo_view.set_trailer_people(&[]);
+        set_diff_skeleton(&ui.repo_view.diff_files_box);
+        ui.repo_view.expand_label.set_visible(false);
+        *ui.repo_view.is_expanded.borrow_mut() = false;
+        update_expand_collapse_buttons(
+            &ui.repo_view.diff_files_box,
+            &ui.repo_view.diff_expand_all_button,
+            &ui.repo_view.diff_collapse_all_button,
+        );
+
+        let diff_files_box_clone = ui.repo_view.diff_files_box.clone();
+        let expand_btn = ui.repo_view.diff_expand_all_button.clone();
+        let collapse_btn = ui.repo_view.diff_collapse_all_button.clone();
+        let path_clone = path.clone();
+        let include_ignored = gio::Settings::new(crate::APP_ID).boolean("show-ignored-files");
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let diff_result =
+                git::get_working_tree_diff(path_clone.to_str().unwrap(), include_ignored);
+            let _ = tx.send(diff_result);
+        });
+        let header_ctx = FileHeaderContext {
+            ui: ui.clone(),
+            state: state.clone(),
+            commit_sha: git::WORKING_TREE_ID.to_string(),
+        };
+        poll_diff_result(
+            rx,
+            diff_files_box_clone,
+            expand_btn,
+            collapse_btn,
+            header_ctx,
+        );
+
+        clear_metadata_skeleton(
+            &ui.repo_view.diff_metadata_label,
+            &ui.repo_view.commit_message_label,
+        );
+        ui.repo_view
+            .diff_metadata_label
+            .set_text("Uncommitted changes");
+        ui.repo_view.diff_sha_row.set_visible(false);
+        *ui.repo_view.diff_sha_copy_text.borrow_mut() = String::new();
+        ui.repo_view.commit_message_label.set_text("");
+        *ui.repo_view.full_message.borrow_mut() = String::new();
+    } else {
+        ui.repo_view.reset_diff(Some("No repository loaded"));
+    }
+}
+
+/// Show the changes saved in `stash`. File menus act on the stash commit.
+fn load_stash_diff(ui: &WindowUi, state: &AppState, stash: &git::StashInfo) {
+    if let Some(ref path) = *state.current_path.borrow() {
+        ui.repo_view.set_diff_chrome_visible(true);
+        ui.repo_view
+            .set_merge_diff_modes(Vec::new(), git::MergeDiffMode::default());
+        ui.repo_view.set_signatures(&[]);
+        ui.repo_view.set_trailer_people(&[]);
+        set_diff_skeleton(&ui.repo_view.diff_files_box);
+        ui.repo_view.expand_label.set_visible(false);
+        *ui.repo_view.is_expanded.borrow_mut() = false;
+        update_expand_collapse_buttons(
+            &ui.repo_view.diff_files_box,
+            &ui.repo_view.diff_expand_all_button,
+            &ui.repo_view.diff_collapse_all_button,
+        );
+
+        let diff_files_box_clone = ui.repo_view.diff_files_box.clone();
+        let expand_btn = ui.repo_view.diff_expand_all_button.clone();
+        let collapse_btn = ui.repo_view.diff_collapse_all_button.clone();
+        let path_clone = path.clone();
+        let sha_clone = stash.sha.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let diff_result = git::get_stash_diff(path_clone.to_str().unwrap(), &sha_clone);
+            let _ = tx.send(diff_result);
+        });
+        let header_ctx = FileHeaderContext {
+            ui: ui.clone(),
+            state: state.clone(),
+            commit_sha: stash.sha.clone(),
+        };
+        poll_diff_result(
+            rx,
+            diff_files_box_clone,
+            expand_btn,
+            collapse_btn,
+            header_ctx,
+        );
+
+        clear_metadata_skeleton(
+            &ui.repo_view.diff_metadata_label,
+            &ui.repo_view.commit_message_label,
+        );
+        ui.repo_view
+            .diff_metadata_label
+            .set_text(&format!("stash@{{{}}}", stash.index));
+        ui.repo_view.diff_sha_row.set_visible(false);
+        *ui.repo_view.diff_sha_copy_text.borrow_mut() = String::new();
+        ui.repo_view.commit_message_label.set_text(&stash.message);
+        *ui.repo_view.full_message.borrow_mut() = stash.message.clone();
+    } else {
+        ui.repo_view.reset_diff(Some("No repository loaded"));
+    }
+}
+
+pub(super) fn load_commit_diff(ui: &WindowUi, state: &AppState, commit_sha: &str) {
     if let Some(ref path) = *state.current_path.borrow() {
         ui.repo_view.set_diff_chrome_visible(true);
+        // Offer the merge modes for merge commits. The picked mode is kept
+        // even when this merge can't use it (e.g. remerging an octopus).
+        // `commit_sha` need not be the selected commit (e.g. a reflog entry).
+        let parent_count = git::get_parent_count(path, commit_sha).unwrap_or(1);
+        let merge_mode = if parent_count > 1 {
+            let modes = git::MergeDiffMode::available(parent_count);
+            let picked = ui.repo_view.diff_merge_mode.get();
+            let mode = if modes.contains(&picked) {
+                picked
+            } else {
+                git::MergeDiffMode::default()
+            };
+            ui.repo_view.set_merge_diff_modes(modes, mode);
+            mode
+        } else {
+            ui.repo_view
+                .set_merge_diff_modes(Vec::new(), git::MergeDiffMode::default());
+            git::MergeDiffMode::default()
+        };
+        ui.repo_view.set_signatures(&[]);
+        ui.repo_view.set_trailer_people(&[]);
         // Show skeleton loading state
         set_diff_skeleton(&ui.repo_view.diff_files_box);
         set_metadata_skeleton(
@@ -1036,10 +1991,39 @@ fn load_commit_diff(ui: &WindowUi, state: &AppState, commit_sha: &str) {
         let sha_clone = commit_sha.to_string();
         let (tx, rx) = mpsc::channel();
         std::thread::spawn(move || {
-            let diff_result = git::get_commit_diff(path_clone.to_str().unwrap(), &sha_clone);
+            let diff_result =
+                git::get_commit_diff(path_clone.to_str().unwrap(), &sha_clone, merge_mode);
             let _ = tx.send(diff_result);
         });
-        poll_diff_result(rx, diff_files_box_clone, expand_btn, collapse_btn);
+        let header_ctx = FileHeaderContext {
+            ui: ui.clone(),
+            state: state.clone(),
+            commit_sha: commit_sha.to_string(),
+        };
+        poll_diff_result(
+            rx,
+            diff_files_box_clone,
+            expand_btn,
+            collapse_btn,
+            header_ctx,
+        );
+
+        // Check signatures in background thread; external verifiers can be slow
+        let path_clone_sig = path.clone();
+        let sha_clone_sig = commit_sha.to_string();
+        let tags_sig = ui.repo_view.commit_list.tags_of(commit_sha);
+        let verifier = crate::ui::signature_settings::verifier();
+        let (tx_sig, rx_sig) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = git::get_signatures(
+                path_clone_sig.to_str().unwrap(),
+                &sha_clone_sig,
+                &tags_sig,
+                &verifier,
+            );
+            let _ = tx_sig.send(result);
+        });
+        poll_signature_result(rx_sig, ui.clone(), commit_sha.to_string());
 
         // Load metadata in background thread
         let metadata_label_clone = ui.repo_view.diff_metadata_label.clone();
@@ -1068,41 +2052,171 @@ fn load_commit_diff(ui: &WindowUi, state: &AppState, commit_sha: &str) {
             expand_label_clone,
             full_message_clone,
             is_expanded_clone,
+            ui.clone(),
         );
     } else {
         ui.repo_view.reset_diff(Some("No repository loaded"));
     }
 }
 
+fn poll_signature_result(
+    rx: mpsc::Receiver<Result<Vec<git::SignatureCheck>, git2::Error>>,
+    ui: WindowUi,
+    commit_sha: String,
+) {
+    match rx.try_recv() {
+        Ok(result) => {
+            // Drop the result if another commit (or none) is shown by now.
+            let commit_list = &ui.repo_view.commit_list;
+            if commit_list.selected_indices().len() != 1
+                || commit_list.selected_commit_sha().as_deref() != Some(commit_sha.as_str())
+            {
+                return;
+            }
+            match result {
+                Ok(checks) => ui.repo_view.set_signatures(&checks),
+                Err(e) => crate::logger::Logger::error(&format!(
+                    "Error checking signatures of {}: {}",
+                    commit_sha, e
+                )),
+            }
+        }
+        Err(mpsc::TryRecvError::Empty) => {
+            glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+                poll_signature_result(rx, ui, commit_sha);
+            });
+        }
+        Err(mpsc::TryRecvError::Disconnected) => {}
+    }
+}
+
+/// Wire "Expand all" / "Collapse all" buttons to the file sections in
+/// `diff_files_box`.
+pub(super) fn connect_expand_collapse(
+    diff_files_box: &gtk::Box,
+    expand_button: &gtk::Button,
+    collapse_button: &gtk::Button,
+) {
+    for (button, expanded) in [(expand_button, true), (collapse_button, false)] {
+        let diff_files_box = diff_files_box.clone();
+        let expand_btn = expand_button.clone();
+        let collapse_btn = collapse_button.clone();
+        button.connect_clicked(move |_| {
+            set_all_file_expanders(&diff_files_box, expanded);
+            update_expand_collapse_buttons(&diff_files_box, &expand_btn, &collapse_btn);
+        });
+    }
+}
+
+/// Show `diff` in a diff area other than the main one (e.g. the compare
+/// dialog). File menus act on the files as of `commit_sha`.
+pub(super) fn show_diff(
+    ui: &WindowUi,
+    state: &AppState,
+    commit_sha: &str,
+    diff: &git::CommitDiff,
+    diff_files_box: &gtk::Box,
+    expand_button: &gtk::Button,
+    collapse_button: &gtk::Button,
+) {
+    let header_ctx = FileHeaderContext {
+        ui: ui.clone(),
+        state: state.clone(),
+        commit_sha: commit_sha.to_string(),
+    };
+    render_diff(
+        diff,
+        diff_files_box,
+        expand_button,
+        collapse_button,
+        &header_ctx,
+    );
+}
+
 pub fn connect(ui: &WindowUi, state: &AppState) {
     // Wire diff header controls
+    connect_expand_collapse(
+        &ui.repo_view.diff_files_box,
+        &ui.repo_view.diff_expand_all_button,
+        &ui.repo_view.diff_collapse_all_button,
+    );
+
+    // Switch between unified and side-by-side layout. Open file sections are rebuilt in the
+    // new layout: collapsing drops the child and re-expanding builds it again.
     {
         let diff_files_box = ui.repo_view.diff_files_box.clone();
-        let expand_btn = ui.repo_view.diff_expand_all_button.clone();
-        let collapse_btn = ui.repo_view.diff_collapse_all_button.clone();
+        let split_view = ui.repo_view.diff_split_view.clone();
         ui.repo_view
-            .diff_expand_all_button
-            .connect_clicked(move |_| {
-                set_all_file_expanders(&diff_files_box, true);
-                update_expand_collapse_buttons(&diff_files_box, &expand_btn, &collapse_btn);
+            .diff_split_button
+            .connect_toggled(move |button| {
+                *split_view.borrow_mut() = button.is_active();
+                let mut child = diff_files_box.first_child();
+                while let Some(w) = child {
+                    let next = w.next_sibling();
+                    if let Ok(expander) = w.downcast::<gtk::Expander>() {
+                        if expander.is_expanded() {
+                            expander.set_expanded(false);
+                            expander.set_expanded(true);
+                        }
+                    }
+                    child = next;
+                }
             });
     }
+
+    // Reload the selected merge commit when another merge mode is picked.
     {
-        let diff_files_box = ui.repo_view.diff_files_box.clone();
-        let expand_btn = ui.repo_view.diff_expand_all_button.clone();
-        let collapse_btn = ui.repo_view.diff_collapse_all_button.clone();
+        let ui_for_merge_mode = ui.clone();
+        let state_for_merge_mode = state.clone();
         ui.repo_view
-            .diff_collapse_all_button
-            .connect_clicked(move |_| {
-                set_all_file_expanders(&diff_files_box, false);
-                update_expand_collapse_buttons(&diff_files_box, &expand_btn, &collapse_btn);
+            .diff_merge_mode_dropdown
+            .connect_selected_notify(move |dropdown| {
+                let repo_view = &ui_for_merge_mode.repo_view;
+                let Some(mode) = repo_view
+                    .diff_merge_modes
+                    .borrow()
+                    .get(dropdown.selected() as usize)
+                    .copied()
+                else {
+                    return;
+                };
+                if mode == repo_view.diff_merge_mode.get() {
+                    return;
+                }
+                repo_view.diff_merge_mode.set(mode);
+                if let Some(sha) = repo_view.commit_list.selected_commit_sha() {
+                    load_commit_diff(&ui_for_merge_mode, &state_for_merge_mode, &sha);
+                }
             });
     }
 
-    // Refresh diff colors when theme changes (light/dark mode switch)
-    let diff_files_box_for_theme = ui.repo_view.diff_files_box.clone();
-    adw::StyleManager::default().connect_dark_notify(move |_| {
-        refresh_diff_colors(&diff_files_box_for_theme);
+    // Refresh diff colors when theme changes (light/dark mode switch). The
+    // style manager outlives the tab, so it only holds a weak reference and
+    // the handler goes away with the tab's widgets.
+    let style_manager = adw::StyleManager::default();
+    let diff_files_box_for_theme = ui.repo_view.diff_files_box.downgrade();
+    let theme_handler = style_manager.connect_dark_notify(move |_| {
+        if let Some(diff_files_box) = diff_files_box_for_theme.upgrade() {
+            refresh_diff_colors(&diff_files_box);
+        }
+    });
+    let theme_handler = std::cell::Cell::new(Some(theme_handler));
+    ui.repo_view.diff_files_box.connect_destroy(move |_| {
+        if let Some(handler) = theme_handler.take() {
+            style_manager.disconnect(handler);
+        }
+    });
+
+    // A stash replaces the commit selection in the diff area.
+    let ui_for_stash = ui.clone();
+    let state_for_stash = state.clone();
+    ui.repo_view.branch_panel.on_stash_selected(move |stash| {
+        ui_for_stash
+            .repo_view
+            .commit_list
+            .selection_model
+            .unselect_all();
+        load_stash_diff(&ui_for_stash, &state_for_stash, stash);
     });
 
     let ui_for_selection = ui.clone();
@@ -1114,13 +2228,21 @@ pub fn connect(ui: &WindowUi, state: &AppState) {
         .connect_selection_changed(move |_position, _n_items, _model| {
             let commit_list = &ui_for_selection.repo_view.commit_list;
             let indices = commit_list.selected_indices();
+            // Selecting commits replaces a shown stash.
+            if !indices.is_empty() {
+                ui_for_selection.repo_view.branch_panel.unselect_stash();
+            }
             if indices.is_empty() {
                 ui_for_selection
                     .repo_view
                     .reset_diff(Some("Select a commit (or a range) to view the commit diff"));
             } else if indices.len() == 1 {
                 if let Some(sha) = commit_list.selected_commit_sha() {
-                    load_commit_diff(&ui_for_selection, &state_for_selection, &sha);
+                    if sha == git::WORKING_TREE_ID {
+                        load_working_tree_diff(&ui_for_selection, &state_for_selection);
+                    } else {
+                        load_commit_diff(&ui_for_selection, &state_for_selection, &sha);
+                    }
                 }
             } else if let Some((oldest, newest, count)) = commit_list.selected_commit_range() {
                 load_range_diff(
@@ -1130,6 +2252,83 @@ pub fn connect(ui: &WindowUi, state: &AppState) {
                     &newest,
                     count,
                 );
+            } else {
+                // Uncommitted changes plus one commit: not a commit range.
+                ui_for_selection
+                    .repo_view
+                    .reset_diff(Some("Uncommitted changes can't be part of a commit range"));
             }
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffstat_summarizes_and_scales_bars_to_the_largest_file() {
+        assert_eq!(
+            diffstat_summary(1, 1, 0),
+            "1 file changed, 1 insertion(+), 0 deletions(−)"
+        );
+        assert_eq!(
+            diffstat_summary(3, 12, 4),
+            "3 files changed, 12 insertions(+), 4 deletions(−)"
+        );
+
+        assert_eq!(diffstat_bar_widths(60, 20, 80), (60, 20));
+        assert_eq!(diffstat_bar_widths(150, 50, 400), (30, 10));
+        // A single line in a huge diff still shows up.
+        assert_eq!(diffstat_bar_widths(1, 0, 10_000), (1, 0));
+    }
+
+    #[test]
+    fn split_sides_pair_changes_and_pad_the_shorter_side() {
+        let patch = "@@ -1,3 +1,4 @@\n keep\n-old a\n-old b\n+new a\n+new b\n+new c\n tail\n";
+        let (_, right_text, kinds, _) = build_diff_gutter_and_text(patch);
+        let word_spans = word_change_spans(&right_text, &kinds);
+        let (old, new) = build_split_sides(&right_text, &kinds, &word_spans, 1);
+
+        assert_eq!(old.kinds.len(), new.kinds.len());
+        assert_eq!(
+            old.text.lines().collect::<Vec<_>>(),
+            vec!["@@ -1,3 +1,4 @@", "keep", "old a", "old b", "", "tail"]
+        );
+        assert_eq!(
+            new.text.lines().collect::<Vec<_>>(),
+            vec!["@@ -1,3 +1,4 @@", "keep", "new a", "new b", "new c", "tail"]
+        );
+        assert_eq!(
+            old.gutter_text.lines().collect::<Vec<_>>(),
+            vec!["    ", " 1  ", " 2 -", " 3 -", "    ", " 4  "]
+        );
+        assert_eq!(
+            new.gutter_text.lines().collect::<Vec<_>>(),
+            vec!["    ", " 1  ", " 2 +", " 3 +", " 4 +", " 5  "]
+        );
+    }
+
+    #[test]
+    fn changed_word_spans_mark_only_the_edited_words() {
+        let (old, new) = changed_word_spans("let count = 10;", "let total = 12;");
+        assert_eq!(old, vec![(4, 9), (12, 14)]);
+        assert_eq!(new, vec![(4, 9), (12, 14)]);
+
+        // Nothing in common but whitespace: leave it to the line color.
+        let (old, new) = changed_word_spans("foo bar", "baz qux");
+        assert!(old.is_empty() && new.is_empty());
+    }
+
+    #[test]
+    fn word_change_spans_pair_removed_and_added_lines_in_order() {
+        let patch = "@@ -1,3 +1,3 @@\n-a = 1\n-b = 2\n+a = 3\n context\n+c = 4\n";
+        let (_, right_text, kinds, _) = build_diff_gutter_and_text(patch);
+        let spans = word_change_spans(&right_text, &kinds);
+
+        assert_eq!(spans[1], vec![(4, 5)]);
+        assert_eq!(spans[3], vec![(4, 5)]);
+        // Unpaired removed line and added line after context get no spans.
+        assert!(spans[2].is_empty());
+        assert!(spans[5].is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! "Browse files" mode of the diff area: the file tree of the selected commit
+//! and a read-only viewer for its files.
+//!
+//! Files are read on a background thread and polled from the main loop, like
+//! the diff loaders.
+
+use adw::prelude::*;
+use gtk::{gio, glib};
+use sourceview5 as sv;
+use std::cell::Cell;
+use std::rc::Rc;
+use sv::prelude::*;
+
+use crate::git;
+use crate::logger::Logger;
+
+use super::common::{loading_spinner, run_in_background, short_sha, show_placeholder};
+use super::state::AppState;
+use super::ui::WindowUi;
+
+/// Files larger than this are not loaded into the viewer.
+const MAX_VIEWABLE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Show the file tree of the selected commit, or a placeholder when no
+/// single commit is selected. A range browses its newest commit.
+fn sync_file_tree(ui: &WindowUi, state: &AppState) {
+    let file_tree = &ui.repo_view.file_tree;
+    let Some(repo_path) = state.current_path.borrow().clone() else {
+        file_tree.clear("No repository loaded");
+        return;
+    };
+    let commit_list = &ui.repo_view.commit_list;
+    let sha = match commit_list.selected_indices().len() {
+        0 => None,
+        1 => commit_list.selected_commit_sha(),
+        _ => commit_list
+            .selected_commit_range()
+            .map(|(_, newest, _)| newest),
+    };
+    match sha {
+        Some(sha) if sha != git::WORKING_TREE_ID => file_tree.show_commit(repo_path, &sha),
+        _ => file_tree.clear("Select a commit to browse its files"),
+    }
+}
+
+/// Open a read-only viewer for `file_path` as it exists at `commit_sha`.
+fn show_file_dialog(ui: &WindowUi, state: &AppState, commit_sha: &str, file_path: &str) {
+    let Some(repo_path) = state.current_path.borrow().clone() else {
+        return;
+    };
+
+    let title = adw::WindowTitle::new(file_path, &format!("At {}", short_sha(commit_sha)));
+    let header = adw::HeaderBar::new();
+    header.set_title_widget(Some(&title));
+
+    let history_button = gtk::Button::builder()
+        .label("History")
+        .tooltip_text("History of This File")
+        .build();
+    let blame_button = gtk::Button::builder()
+        .label("Blame")
+        .tooltip_text("Blame this file at this commit")
+        .build();
+    header.pack_start(&history_button);
+    header.pack_start(&blame_button);
+
+    let stack = gtk::Stack::new();
+    stack.add_named(&loading_spinner(), Some("loading"));
+
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.set_content(Some(&stack));
+
+    let dialog = adw::Dialog::builder()
+        .content_width(960)
+        .content_height(640)
+        .child(&toolbar)
+        .build();
+
+    let closed = Rc::new(Cell::new(false));
+    let closed_on_close = closed.clone();
+    dialog.connect_closed(move |_| closed_on_close.set(true));
+
+    let dialog_for_history = dialog.clone();
+    let ui_for_history = ui.clone();
+    let state_for_history = state.clone();
+    let path_for_history = file_path.to_string();
+    history_button.connect_clicked(move |_| {
+        dialog_for_history.close();
+        super::repo::set_path_filter(
+            &ui_for_history,
+            &state_for_history,
+            Some(path_for_history.clone()),
+        );
+    });
+
+    let dialog_for_blame = dialog.clone();
+    let ui_for_blame = ui.clone();
+    let state_for_blame = state.clone();
+    let sha_for_blame = commit_sha.to_string();
+    let path_for_blame = file_path.to_string();
+    blame_button.connect_clicked(move |_| {
+        dialog_for_blame.close();
+        super::blame::show_blame_dialog(
+            &ui_for_blame,
+            &state_for_blame,
+            &sha_for_blame,
+            &path_for_blame,
+        );
+    });
+
+    let sha = commit_sha.to_string();
+    let path = file_path.to_string();
+    let file_path = file_path.to_string();
+    run_in_background(
+        move || git::get_file_content(repo_path.to_str().unwrap(), &sha, &path),
+        move |result| {
+            if !closed.get() {
+                show_file_content(result, &stack, &blame_button, &file_path);
+            }
+        },
+    );
+    dialog.present(Some(&ui.repo_view.widget));
+}
+
+/// Show a loaded file in the viewer's `stack`. Blame is disabled for files
+/// it can't handle.
+fn show_file_content(
+    result: Result<git::FileContent, git2::Error>,
+    stack: &gtk::Stack,
+    blame_button: &gtk::Button,
+    file_path: &str,
+) {
+    let content = match result {
+        Ok(content) => content,
+        Err(e) => {
+            Logger::error(&format!("Error loading {}: {}", file_path, e));
+            blame_button.set_sensitive(false);
+            show_placeholder(
+                stack,
+                "error",
+                crate::ui::placeholder::ICON_ERROR,
+                &format!("Error loading file: {}", e.message()),
+            );
+            return;
+        }
+    };
+    let view = match content.text {
+        Some(_) if content.size > MAX_VIEWABLE_SIZE => {
+            blame_button.set_sensitive(false);
+            crate::ui::placeholder::centered(
+                crate::ui::placeholder::ICON_INFO,
+                &format!(
+                    "File too large to display ({})",
+                    glib::format_size(content.size as u64)
+                ),
+            )
+        }
+        Some(text) => build_file_view(file_path, &text),
+        None => {
+            // Blame refuses binary files.
+            blame_button.set_sensitive(false);
+            crate::ui::placeholder::centered(
+                crate::ui::placeholder::ICON_INFO,
+                &format!("Binary file ({})", glib::format_size(content.size as u64)),
+            )
+        }
+    };
+    stack.add_named(&view, Some("file"));
+    stack.set_visible_child_name("file");
+}
+
+fn build_file_view(file_path: &str, text: &str) -> gtk::Widget {
+    let sample: String = text.lines().take(64).collect::<Vec<_>>().join("\n");
+    let (content_type, _uncertain) = gio::content_type_guess(Some(file_path), sample.as_bytes());
+    let language =
+        sv::LanguageManager::default().guess_language(Some(file_path), Some(content_type.as_str()));
+
+    let buffer = sv::Buffer::new(None);
+    buffer.set_language(language.as_ref());
+    buffer.set_highlight_syntax(language.is_some());
+    buffer.set_style_scheme(super::diff::diff_style_scheme().as_ref());
+    buffer.set_text(text);
+
+    let view = sv::View::with_buffer(&buffer);
+    view.set_editable(false);
+    view.set_cursor_visible(false);
+    view.set_monospace(true);
+    view.set_show_line_numbers(true);
+    view.set_left_margin(6);
+    view.add_css_class("diff-view");
+
+    gtk::ScrolledWindow::builder()
+        .vexpand(true)
+        .hexpand(true)
+        .child(&view)
+        .build()
+        .upcast()
+}
+
+pub fn connect(ui: &WindowUi, state: &AppState) {
+    let ui_for_toggle = ui.clone();
+    let state_for_toggle = state.clone();
+    ui.repo_view
+        .diff_browse_button
+        .connect_toggled(move |button| {
+            let repo_view = &ui_for_toggle.repo_view;
+            let browsing = button.is_active();
+            repo_view
+                .diff_stack
+                .set_visible_child_name(if browsing { "files" } else { "changes" });
+            // Expanding, the split layout and merge modes only apply to the changes.
+            repo_view.diff_expand_all_button.set_visible(!browsing);
+            repo_view.diff_collapse_all_button.set_visible(!browsing);
+            repo_view.diff_split_button.set_visible(!browsing);
+            repo_view
+                .diff_merge_mode_dropdown
+                .set_visible(!browsing && !repo_view.diff_merge_modes.borrow().is_empty());
+            if browsing {
+                sync_file_tree(&ui_for_toggle, &state_for_toggle);
+            }
+        });
+
+    let ui_for_selection = ui.clone();
+    let state_for_selection = state.clone();
+    ui.repo_view
+        .commit_list
+        .selection_model
+        .connect_selection_changed(move |_, _, _| {
+            if ui_for_selection.repo_view.diff_browse_button.is_active() {
+                sync_file_tree(&ui_for_selection, &state_for_selection);
+            }
+        });
+
+    let ui_for_open = ui.clone();
+    let state_for_open = state.clone();
+    ui.repo_view.file_tree.on_file_activated(move |path| {
+        let Some(sha) = ui_for_open.repo_view.file_tree.commit_sha() else {
+            return;
+        };
+        show_file_dialog(&ui_for_open, &state_for_open, &sha, path);
+    });
+}
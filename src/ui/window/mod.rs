@@ -1,13 +1,24 @@
 use gtk::{gio, glib, prelude::*};
 
 use crate::APP_ID;
+use crate::launch::LaunchTarget;
 mod actions;
+mod blame;
+mod common;
+mod compare;
 mod diff;
+mod files;
+mod patches;
+mod preferences;
 pub mod recent_repos;
+mod reflog;
+mod releases;
 mod repo;
 mod search;
 mod state;
+mod tabs;
 mod ui;
+mod watcher;
 
 pub use actions::{setup_app_action, setup_shortcuts};
 
@@ -16,9 +27,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 
 const APP_NAME: &str = "GitY";
 
-fn setup_window(
-    application: &adw::Application,
-) -> (gtk::ApplicationWindow, ui::WindowUi, state::AppState) {
+fn setup_window(application: &adw::Application) -> (gtk::ApplicationWindow, tabs::Tabs) {
     // Load GSettings
     let settings = gio::Settings::new(APP_ID);
 
@@ -41,13 +50,139 @@ fn setup_window(
     if is_maximized {
         window.maximize();
     }
-    // Build UI widgets (layout + stack views). Persistence wiring stays in this file.
-    let ui = ui::WindowUi::build(window.clone(), APP_NAME);
-    let app_state = state::AppState::new();
-    (window, ui, app_state)
+    // Build the header bar and tab view. Tabs are added by the callers.
+    let chrome = ui::WindowChrome::build(window.clone(), APP_NAME);
+    let tabs = tabs::Tabs::new(&window, chrome);
+    (window, tabs)
 }
 
-fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &state::AppState) {
+/// Open a new tab on the welcome screen with its handlers connected.
+fn add_tab(window: &gtk::ApplicationWindow, tabs: &tabs::Tabs) -> tabs::Tab {
+    let tab = tabs.add_tab(|ui, state| wire_tab(window, tabs, ui, state));
+    tab.ui.set_repo_controls_visible(false);
+    tab.ui.show_welcome();
+    tab
+}
+
+/// Open the repository at `path` in `tab`, or report why it can't be opened.
+/// `ref_name` and `select_sha` pick the ref to view and the commit to select
+/// instead of the defaults.
+fn open_repo_in_tab(
+    window: &gtk::ApplicationWindow,
+    tab: &tabs::Tab,
+    path: &std::path::Path,
+    ref_name: Option<String>,
+    select_sha: Option<String>,
+    log_label: &str,
+) -> bool {
+    if let Err(e) = crate::git::validate_repository(path) {
+        repo::show_repo_error(window, &path.to_path_buf(), &e.to_string());
+        return false;
+    }
+
+    recent_repos::add_recent_repo(path, path);
+    load_repo_in_tab(tab, path, ref_name, select_sha, log_label);
+    true
+}
+
+/// Load the already validated repository at `path` into `tab`, without
+/// recording it as recently opened.
+fn load_repo_in_tab(
+    tab: &tabs::Tab,
+    path: &std::path::Path,
+    ref_name: Option<String>,
+    select_sha: Option<String>,
+    log_label: &str,
+) {
+    use std::time::Instant;
+
+    let started_at = Instant::now();
+    tab.ui
+        .repo_view
+        .commit_paging_state
+        .borrow_mut()
+        .pending_first_page_log = Some((started_at, path.to_path_buf(), log_label.to_string()));
+
+    tab.ui.set_repo_controls_visible(true);
+    tab.ui.show_main();
+    repo::load_repo_with_selection(
+        &tab.ui,
+        &tab.state,
+        APP_NAME,
+        path.to_path_buf(),
+        ref_name,
+        select_sha,
+    );
+}
+
+/// Show `target` in this window: the tab already showing its repository, or
+/// a new one. A ref, commit or file in the target reloads that tab at the
+/// ref with the commit selected and the file revealed in its diff.
+fn open_target(window: &gtk::ApplicationWindow, tabs: &tabs::Tabs, target: &LaunchTarget) {
+    let path =
+        crate::git::discover_repository_root(&target.repo).unwrap_or_else(|| target.repo.clone());
+    let select_sha = target
+        .commit
+        .as_deref()
+        .and_then(|spec| crate::git::resolve_commit(&path, spec));
+    if target.commit.is_some() && select_sha.is_none() {
+        crate::logger::Logger::error(&format!(
+            "Commit {} not found in {}",
+            target.commit.as_deref().unwrap_or_default(),
+            path.display()
+        ));
+    }
+
+    let existing = tabs.select_repo(&path).then(|| tabs.current()).flatten();
+    if existing.is_some() && !target.has_selection() {
+        return;
+    }
+    let tab = match existing {
+        Some(tab) => {
+            repo::reset_for_repo_switch(&tab.ui, &tab.state);
+            tab
+        }
+        None => add_tab(window, tabs),
+    };
+    *tab.state.pending_diff_file.borrow_mut() =
+        target.file.clone().map(|file| (select_sha.clone(), file));
+    let opened = open_repo_in_tab(
+        window,
+        &tab,
+        &path,
+        target.reference.clone(),
+        select_sha,
+        "Open deep link -> rendered on screen",
+    );
+    if !opened && tabs.all().len() > 1 {
+        tabs.chrome.tab_view.close_page(&tab.ui.page);
+    }
+}
+
+/// Open a deep link or repository given on the command line (or forwarded
+/// from another `gity` invocation): in the focused window, or a new one when
+/// none is open yet.
+pub fn open_launch_target(application: &adw::Application, target: &LaunchTarget) {
+    match application.active_window() {
+        Some(window) => {
+            window.present();
+            let _ = gtk::prelude::WidgetExt::activate_action(
+                &window,
+                "win.open-link",
+                Some(&target.to_uri().to_variant()),
+            );
+        }
+        None => build_ui(application, Some(target)),
+    }
+}
+
+/// Connect the widgets of one tab.
+fn wire_tab(
+    window: &gtk::ApplicationWindow,
+    tabs: &tabs::Tabs,
+    ui: &ui::WindowUi,
+    app_state: &state::AppState,
+) {
     // Ref selection: switch branch or tag within the same repo (reuses tags for commit display).
     let ui_for_ref_select = ui.clone();
     let state_for_ref_select = app_state.clone();
@@ -71,9 +206,6 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
             );
         });
 
-    // Wire actions + button handlers.
-    actions::install(&window, &ui, &app_state);
-
     // Hook up mouse back button to go to welcome screen
     let window_for_back_button = window.clone();
     let gesture = gtk::GestureClick::new();
@@ -90,9 +222,17 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
     // Wire recent repository click handler
     let ui_for_recent = ui.clone();
     let state_for_recent = app_state.clone();
+    let tabs_for_recent = tabs.clone();
     ui.on_recent_repo_clicked(move |sandbox_path, real_path| {
         use std::time::Instant;
 
+        // Switch to the tab that already shows this repository, if any.
+        if state_for_recent.current_path.borrow().as_ref() != Some(&sandbox_path)
+            && tabs_for_recent.select_repo(&sandbox_path)
+        {
+            return;
+        }
+
         // If a repository is already open, reset UI state first
         if state_for_recent.current_path.borrow().is_some() {
             repo::reset_for_repo_switch(&ui_for_recent, &state_for_recent);
@@ -131,7 +271,8 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
     ui.refresh_recent_repos();
 
     // Hook diff loader to commit selection changes
-    diff::connect(&ui, &app_state);
+    diff::connect(ui, app_state);
+    files::connect(ui, app_state);
 
     // Create paned widget to allow resizing between commits list and diff view
     let main_content_paned = ui.repo_view.main_content_paned.clone();
@@ -141,12 +282,30 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
     let paned_position = settings.int("diff-paned-position");
     main_content_paned.set_position(paned_position);
 
-    // Save paned position when it changes
+    // Save paned position when it changes. The key is shared by all tabs, so
+    // only the selected one writes it.
     let settings_for_paned = settings.clone();
+    let page_for_paned = ui.page.downgrade();
     main_content_paned.connect_position_notify(move |paned| {
-        let _ = settings_for_paned.set_int("diff-paned-position", paned.position());
+        if page_for_paned
+            .upgrade()
+            .is_some_and(|page| page.is_selected())
+        {
+            let _ = settings_for_paned.set_int("diff-paned-position", paned.position());
+        }
     });
 
+    // Restore the diff layout (unified or side-by-side) and save it when toggled
+    ui.repo_view
+        .diff_split_button
+        .set_active(settings.boolean("diff-split-view"));
+    let settings_for_split = settings.clone();
+    ui.repo_view
+        .diff_split_button
+        .connect_toggled(move |button| {
+            let _ = settings_for_split.set_boolean("diff-split-view", button.is_active());
+        });
+
     // Create horizontal paned to hold side panel and main content (allows resizing)
     let horizontal_paned = ui.repo_view.horizontal_paned.clone();
 
@@ -154,13 +313,24 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
     let branch_panel_width = settings.int("branch-panel-width");
     horizontal_paned.set_position(branch_panel_width);
 
-    // Save branch panel width when it changes
+    // Save branch panel width when it changes, from the selected tab only
     let settings_for_branch_panel = settings.clone();
+    let page_for_branch_panel = ui.page.downgrade();
     horizontal_paned.connect_position_notify(move |paned| {
-        let _ = settings_for_branch_panel.set_int("branch-panel-width", paned.position());
+        if page_for_branch_panel
+            .upgrade()
+            .is_some_and(|page| page.is_selected())
+        {
+            let _ = settings_for_branch_panel.set_int("branch-panel-width", paned.position());
+        }
     });
+}
+
+fn wire_window(window: &gtk::ApplicationWindow, tabs: &tabs::Tabs) {
+    // Wire actions + button handlers.
+    actions::install(window, tabs);
 
-    // Main/welcome views are added by ui::WindowUi::build().
+    let settings = gio::Settings::new(APP_ID);
 
     // Save window state to GSettings when window is resized
     // Use an atomic bool to track if we're currently restoring to avoid saving during restore
@@ -183,17 +353,27 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
     // Save state when window is closed
     let settings_close = settings_for_save.clone();
     let restoring_close = restoring.clone();
+    let tabs_close = tabs.clone();
     window.connect_close_request(move |win| {
         save_state(win, &settings_close, &restoring_close);
+        if win.is_active() {
+            tabs_close.save_open_repos(&settings_close);
+        }
         glib::Propagation::Proceed
     });
 
-    // Save state periodically using a recurring timeout (every 500ms)
+    // Save state periodically using a recurring timeout (every 500ms). The
+    // open tabs are saved from the focused window only, so with several
+    // windows the last one used is restored.
     let settings_timeout = settings_for_save.clone();
     let window_timeout = window.clone();
     let restoring_timeout = restoring.clone();
+    let tabs_timeout = tabs.clone();
     glib::timeout_add_local(std::time::Duration::from_millis(500), move || {
         save_state(&window_timeout, &settings_timeout, &restoring_timeout);
+        if window_timeout.is_active() && !restoring_timeout.load(Ordering::Relaxed) {
+            tabs_timeout.save_open_repos(&settings_timeout);
+        }
         glib::ControlFlow::Continue
     });
 
@@ -206,6 +386,15 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
         }
     });
 
+    // Repository watcher callbacks hold the tab state; drop them with the
+    // window so the two don't keep each other alive.
+    let tabs_for_destroy = tabs.clone();
+    window.connect_destroy(move |_| {
+        for tab in tabs_for_destroy.all() {
+            tab.state.repo_watcher.borrow_mut().take();
+        }
+    });
+
     // Mark that we're done restoring after the window is shown
     let restoring_realize = restoring.clone();
     window.connect_realize(move |_| {
@@ -213,52 +402,77 @@ fn wire_window(window: &gtk::ApplicationWindow, ui: &ui::WindowUi, app_state: &s
     });
 }
 
-pub fn build_ui(application: &adw::Application, repo_path_from_args: Option<&std::path::PathBuf>) {
-    let (window, ui, app_state) = setup_window(application);
-    wire_window(&window, &ui, &app_state);
-
-    // If a repo path was provided via command-line args, load it
-    if let Some(path) = repo_path_from_args {
-        if path.exists() {
-            use std::time::Instant;
-            if let Err(e) = crate::git::validate_repository(path) {
-                repo::show_repo_error(&window, path, &e.to_string());
-                ui.set_repo_controls_visible(false);
-                ui.show_welcome();
-            } else {
-                let started_at = Instant::now();
-                ui.repo_view
-                    .commit_paging_state
-                    .borrow_mut()
-                    .pending_first_page_log = Some((
-                    started_at,
-                    path.clone(),
-                    "Open repo from CLI arg -> rendered on screen".to_string(),
-                ));
-
-                recent_repos::add_recent_repo(path, path);
-                ui.set_repo_controls_visible(true);
-                ui.show_main();
-                repo::load_repo(&ui, &app_state, APP_NAME, path.clone(), None);
+pub fn build_ui(application: &adw::Application, launch_target: Option<&LaunchTarget>) {
+    // Tabs from the last session are only restored in the first window.
+    let is_first_window = application.windows().is_empty();
+    let (window, tabs) = setup_window(application);
+    wire_window(&window, &tabs);
+
+    if is_first_window {
+        let settings = gio::Settings::new(APP_ID);
+        let (paths, selected) = tabs::saved_open_repos(&settings);
+        let mut restored = Vec::new();
+        // Repositories that can no longer be opened are dropped quietly.
+        // Restoring is not opening, so the recent list is left alone.
+        for path in paths {
+            if crate::git::validate_repository(&path).is_err() {
+                continue;
             }
-            window.present();
-            return;
+            let tab = add_tab(&window, &tabs);
+            load_repo_in_tab(&tab, &path, None, None, "Restore tab -> rendered on screen");
+            restored.push((path, tab));
+        }
+        let selected_tab = restored
+            .iter()
+            .find(|(path, _)| Some(path) == selected.as_ref())
+            .or(restored.first());
+        if let Some((_, tab)) = selected_tab {
+            tabs.chrome.tab_view.set_selected_page(&tab.ui.page);
         }
     }
 
-    // Auto-open repo from CWD if applicable (only for initial launch, not new windows)
-    let _loaded = repo::maybe_load_repo_from_cwd(&window, &ui, &app_state, APP_NAME);
+    // A repository from the command line, or else the working directory when
+    // launched from a terminal, opens in its own tab (or selects the restored
+    // tab already showing it).
+    match launch_target.filter(|target| target.repo.exists()) {
+        Some(target) => open_target(&window, &tabs, target),
+        None => {
+            let cwd_repo = std::env::current_dir()
+                .ok()
+                .and_then(|cwd| crate::git::discover_repository_root(&cwd))
+                .filter(|root| root.exists());
+            if let Some(path) = cwd_repo
+                && !tabs.select_repo(&path)
+            {
+                let tab = add_tab(&window, &tabs);
+                let opened = open_repo_in_tab(
+                    &window,
+                    &tab,
+                    &path,
+                    None,
+                    None,
+                    "Auto-open repo from CWD -> rendered on screen",
+                );
+                if !opened && tabs.all().len() > 1 {
+                    tabs.chrome.tab_view.close_page(&tab.ui.page);
+                }
+            }
+        }
+    }
+
+    if tabs.all().is_empty() {
+        add_tab(&window, &tabs);
+    }
 
     window.present();
 }
 
 pub fn build_ui_for_new_window(application: &adw::Application) {
-    let (window, ui, app_state) = setup_window(application);
-    wire_window(&window, &ui, &app_state);
+    let (window, tabs) = setup_window(application);
+    wire_window(&window, &tabs);
 
     // New windows always start on welcome screen (skip CWD auto-open)
-    ui.set_repo_controls_visible(false);
-    ui.show_welcome();
+    add_tab(&window, &tabs);
 
     window.present();
 }
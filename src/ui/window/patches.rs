@@ -0,0 +1,207 @@
+//! Selected commits as patches, for mailing lists: "Copy as Patch" puts them
+//! on the clipboard, "Export as Patches…" writes a `git format-patch` style
+//! series (one file per commit) or a single mbox.
+//!
+//! Patches are formatted (and written) on a background thread and polled
+//! from the main loop, like the diff loaders. The outcome is shown in a
+//! toast.
+
+use adw::prelude::*;
+use gtk::gio;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::logger::Logger;
+use crate::ui::copy_on_hover;
+
+use super::common::run_in_background;
+use super::state::AppState;
+use super::ui::WindowUi;
+
+/// Where an export goes.
+enum ExportTarget {
+    /// One `NNNN-subject.patch` file per commit in this folder.
+    Series(PathBuf),
+    /// All patches concatenated into this file.
+    Mbox(PathBuf),
+}
+
+impl ExportTarget {
+    fn path(&self) -> &Path {
+        match self {
+            ExportTarget::Series(path) | ExportTarget::Mbox(path) => path,
+        }
+    }
+}
+
+/// "1 patch", "3 patches".
+fn patch_count(count: usize) -> String {
+    match count {
+        1 => "1 patch".to_string(),
+        n => format!("{n} patches"),
+    }
+}
+
+/// Repository and selected commits (oldest first, the order of a series),
+/// or `None` when no commit is selected.
+fn selection(ui: &WindowUi, state: &AppState) -> Option<(PathBuf, Vec<String>)> {
+    let repo_path = state.current_path.borrow().clone()?;
+    let mut shas = ui.repo_view.commit_list.selected_commit_shas();
+    if shas.is_empty() {
+        return None;
+    }
+    shas.reverse();
+    Some((repo_path, shas))
+}
+
+/// Copy the selected commits to the clipboard as an mbox-style patch series.
+pub fn copy_as_patch(ui: &WindowUi, state: &AppState) {
+    let Some((repo_path, shas)) = selection(ui, state) else {
+        return;
+    };
+    let ui = ui.clone();
+    run_in_background(
+        move || git::format_patches(&repo_path, &shas),
+        move |result| match result {
+            Ok(patches) if !patches.is_empty() => {
+                let text: String = patches.iter().map(|p| p.text.as_str()).collect();
+                copy_on_hover::copy_text_to_clipboard(&text);
+                ui.show_toast(&format!(
+                    "Copied {} to the clipboard",
+                    patch_count(patches.len())
+                ));
+            }
+            Ok(_) => {
+                Logger::info("Only merge commits selected; nothing to copy as patch");
+                ui.show_toast("Merge commits cannot be copied as patches");
+            }
+            Err(e) => {
+                Logger::error(&format!("Error formatting patches: {}", e));
+                ui.show_toast(&format!("Could not copy patches: {}", e.message()));
+            }
+        },
+    );
+}
+
+/// Ask whether to write a patch series or a single mbox, then where, and
+/// export the selected commits there.
+pub fn export_patches(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState) {
+    let Some((repo_path, shas)) = selection(ui, state) else {
+        return;
+    };
+
+    let heading = match shas.len() {
+        1 => "Export Commit as Patch".to_string(),
+        n => format!("Export {n} Commits as Patches"),
+    };
+    let dialog = adw::AlertDialog::builder()
+        .heading(heading)
+        .body(
+            "Write one .patch file per commit into a folder, or all of them into a single \
+             mailbox file. Merge commits are skipped.",
+        )
+        .build();
+    dialog.add_responses(&[
+        ("cancel", "Cancel"),
+        ("mbox", "Single Mailbox"),
+        ("series", "Patch Files"),
+    ]);
+    dialog.set_response_appearance("series", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("series"));
+    dialog.set_close_response("cancel");
+
+    let window_for_response = window.clone();
+    let ui_for_response = ui.clone();
+    dialog.connect_response(None, move |_, response| {
+        let ui = ui_for_response.clone();
+        let repo_path = repo_path.clone();
+        let shas = shas.clone();
+        match response {
+            "series" => {
+                let chooser = gtk::FileDialog::builder()
+                    .title("Export Patches To")
+                    .build();
+                chooser.select_folder(
+                    Some(&window_for_response),
+                    gio::Cancellable::NONE,
+                    move |result| {
+                        if let Some(folder) = result.ok().and_then(|f| f.path()) {
+                            export(&ui, repo_path, shas, ExportTarget::Series(folder));
+                        }
+                    },
+                );
+            }
+            "mbox" => {
+                let name = repo_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "patches".to_string());
+                let chooser = gtk::FileDialog::builder()
+                    .title("Export Mailbox")
+                    .initial_name(format!("{name}.mbox"))
+                    .build();
+                chooser.save(
+                    Some(&window_for_response),
+                    gio::Cancellable::NONE,
+                    move |result| {
+                        if let Some(file) = result.ok().and_then(|f| f.path()) {
+                            export(&ui, repo_path, shas, ExportTarget::Mbox(file));
+                        }
+                    },
+                );
+            }
+            _ => {}
+        }
+    });
+    dialog.present(Some(window));
+}
+
+fn export(ui: &WindowUi, repo_path: PathBuf, shas: Vec<String>, target: ExportTarget) {
+    let ui = ui.clone();
+    let destination = target.path().to_path_buf();
+    run_in_background(
+        move || write_patches(&repo_path, &shas, &target),
+        move |result| match result {
+            Ok(count) => {
+                Logger::info(&format!("Exported {count} patches"));
+                ui.show_toast(&format!(
+                    "Exported {} to {}",
+                    patch_count(count),
+                    destination.display()
+                ));
+            }
+            Err(message) => {
+                Logger::error(&format!("Error exporting patches: {}", message));
+                ui.show_toast(&format!("Could not export patches: {}", message));
+            }
+        },
+    );
+}
+
+/// Format `shas` and write them to `target`. Returns the number of patches.
+fn write_patches(
+    repo_path: &Path,
+    shas: &[String],
+    target: &ExportTarget,
+) -> Result<usize, String> {
+    let patches = git::format_patches(repo_path, shas).map_err(|e| e.message().to_string())?;
+    if patches.is_empty() {
+        return Err(
+            "Only merge commits are selected, and they cannot be exported as patches.".to_string(),
+        );
+    }
+    match target {
+        ExportTarget::Series(folder) => {
+            for patch in &patches {
+                let path = folder.join(&patch.file_name);
+                std::fs::write(&path, &patch.text)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+        }
+        ExportTarget::Mbox(file) => {
+            let text: String = patches.iter().map(|p| p.text.as_str()).collect();
+            std::fs::write(file, text).map_err(|e| format!("{}: {}", file.display(), e))?;
+        }
+    }
+    Ok(patches.len())
+}
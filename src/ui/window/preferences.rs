@@ -0,0 +1,74 @@
+use adw::prelude::*;
+use gtk::gio;
+
+use crate::APP_ID;
+
+/// Show the preferences dialog. Rows are bound directly to GSettings, so
+/// changes apply immediately.
+pub fn show_preferences(window: &gtk::ApplicationWindow) {
+    let settings = gio::Settings::new(APP_ID);
+
+    let auto_refresh_row = adw::SwitchRow::builder()
+        .title("Refresh Automatically")
+        .subtitle("Reload when commits, branches or the index change outside GitY")
+        .build();
+    settings
+        .bind("auto-refresh", &auto_refresh_row, "active")
+        .build();
+
+    let changes_column_row = adw::SwitchRow::builder()
+        .title("Show Changes Column")
+        .subtitle("Lines added and removed by each commit in the history")
+        .build();
+    settings
+        .bind("show-changes-column", &changes_column_row, "active")
+        .build();
+
+    let signature_column_row = adw::SwitchRow::builder()
+        .title("Show Signature Column")
+        .subtitle("Whether each commit's signature verifies, in the history")
+        .build();
+    settings
+        .bind("show-signature-column", &signature_column_row, "active")
+        .build();
+
+    let ignored_files_row = adw::SwitchRow::builder()
+        .title("Show Ignored Files")
+        .subtitle("List files ignored by .gitignore with the uncommitted changes")
+        .build();
+    settings
+        .bind("show-ignored-files", &ignored_files_row, "active")
+        .build();
+
+    let repository_group = adw::PreferencesGroup::builder().title("Repository").build();
+    repository_group.add(&auto_refresh_row);
+    repository_group.add(&changes_column_row);
+    repository_group.add(&signature_column_row);
+    repository_group.add(&ignored_files_row);
+
+    let gpg_home_row = adw::EntryRow::builder()
+        .title("GnuPG Home (empty for the default keyring)")
+        .build();
+    settings.bind("gpg-home", &gpg_home_row, "text").build();
+    let allowed_signers_row = adw::EntryRow::builder()
+        .title("SSH Allowed Signers File (empty for global git config)")
+        .build();
+    settings
+        .bind("ssh-allowed-signers", &allowed_signers_row, "text")
+        .build();
+
+    let signatures_group = adw::PreferencesGroup::builder()
+        .title("Signatures")
+        .description("Keys that commit and tag signatures are verified against")
+        .build();
+    signatures_group.add(&gpg_home_row);
+    signatures_group.add(&allowed_signers_row);
+
+    let page = adw::PreferencesPage::new();
+    page.add(&repository_group);
+    page.add(&signatures_group);
+
+    let dialog = adw::PreferencesDialog::new();
+    dialog.add(&page);
+    dialog.present(Some(window));
+}
@@ -0,0 +1,244 @@
+//! "Reflog" dialog: where `HEAD` or a local branch pointed over time.
+//! Activating an entry shows the commit it moved to in the main diff view, so
+//! commits that no ref reaches any more (e.g. after a bad rebase or reset)
+//! can be inspected this way.
+//!
+//! The reflog is read on a background thread and polled from the main loop,
+//! like the diff loaders.
+
+use adw::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::git;
+use crate::logger::Logger;
+use crate::ui::RefType;
+
+use super::common::{bump, loading_spinner, run_in_background, short_sha, show_placeholder};
+use super::state::AppState;
+use super::ui::WindowUi;
+
+/// Widgets and state shared by the dialog's callbacks.
+#[derive(Clone)]
+struct ReflogView {
+    ui: WindowUi,
+    state: AppState,
+    repo_path: PathBuf,
+    list_stack: gtk::Stack,
+    list: gtk::ListBox,
+    entries: Rc<RefCell<Vec<git::ReflogEntry>>>,
+    /// Bumped per reflog load, and on close, so a superseded result is dropped.
+    generation: Rc<Cell<u64>>,
+}
+
+/// Open the reflog dialog for `name` (`HEAD` or a local branch). The other
+/// reflogs can be picked in the header.
+pub fn show_reflog_dialog(
+    window: &gtk::ApplicationWindow,
+    ui: &WindowUi,
+    state: &AppState,
+    name: &str,
+) {
+    let Some(repo_path) = state.current_path.borrow().clone() else {
+        return;
+    };
+
+    let mut names = vec!["HEAD".to_string()];
+    names.extend(
+        ui.repo_view
+            .branch_panel
+            .ref_names()
+            .into_iter()
+            .filter(|(_, ty)| *ty == RefType::Branch)
+            .map(|(name, _)| name),
+    );
+    if !names.iter().any(|n| n == name) {
+        names.push(name.to_string());
+    }
+    let labels: Vec<&str> = names.iter().map(String::as_str).collect();
+    let ref_dropdown = gtk::DropDown::from_strings(&labels);
+    ref_dropdown.set_tooltip_text(Some("Reflog to show"));
+    let position = names.iter().position(|n| n == name).unwrap_or(0);
+    ref_dropdown.set_selected(position as u32);
+
+    let header = adw::HeaderBar::new();
+    header.set_title_widget(Some(&ref_dropdown));
+
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::Single)
+        .build();
+    list.add_css_class("navigation-sidebar");
+    let list_scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .child(&list)
+        .build();
+    let list_stack = gtk::Stack::new();
+    list_stack.add_named(&loading_spinner(), Some("loading"));
+    list_stack.add_named(&list_scrolled, Some("list"));
+
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.set_content(Some(&list_stack));
+
+    let dialog = adw::Dialog::builder()
+        .title("Reflog")
+        .content_width(560)
+        .content_height(640)
+        .child(&toolbar)
+        .build();
+
+    let view = ReflogView {
+        ui: ui.clone(),
+        state: state.clone(),
+        repo_path,
+        list_stack,
+        list,
+        entries: Rc::new(RefCell::new(Vec::new())),
+        generation: Rc::new(Cell::new(0)),
+    };
+
+    let view_on_close = view.clone();
+    dialog.connect_closed(move |_| {
+        bump(&view_on_close.generation);
+    });
+
+    // The commit replaces the commit selection in the main diff view, like a
+    // selected stash.
+    let view_for_activate = view.clone();
+    let dialog_for_activate = dialog.clone();
+    view.list.connect_row_activated(move |_, row| {
+        let Some(sha) = usize::try_from(row.index())
+            .ok()
+            .and_then(|idx| view_for_activate.entries.borrow().get(idx).cloned())
+            .map(|entry| entry.new_sha)
+        else {
+            return;
+        };
+        dialog_for_activate.close();
+        let ui = &view_for_activate.ui;
+        ui.repo_view.commit_list.selection_model.unselect_all();
+        super::diff::load_commit_diff(ui, &view_for_activate.state, &sha);
+    });
+
+    let view_for_dropdown = view.clone();
+    ref_dropdown.connect_selected_notify(move |dropdown| {
+        if let Some(name) = names.get(dropdown.selected() as usize) {
+            load_reflog(&view_for_dropdown, name);
+        }
+    });
+
+    load_reflog(&view, name);
+    dialog.present(Some(window));
+}
+
+/// Whether `sha` is the all-zero id a reflog uses for "no commit" (the ref
+/// was created or deleted).
+fn is_zero_sha(sha: &str) -> bool {
+    sha.bytes().all(|b| b == b'0')
+}
+
+fn entry_row(name: &str, index: usize, entry: &git::ReflogEntry) -> gtk::ListBoxRow {
+    let selector_label = gtk::Label::builder()
+        .label(format!("{name}@{{{index}}}"))
+        .build();
+    selector_label.add_css_class("monospace");
+    selector_label.add_css_class("dim-label");
+
+    let message_label = gtk::Label::builder()
+        .label(&entry.message)
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .tooltip_text(&entry.message)
+        .build();
+
+    let top = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    top.append(&selector_label);
+    top.append(&message_label);
+
+    let mut details = format!(
+        "{} → {} · {} · {}",
+        short_sha(&entry.old_sha),
+        short_sha(&entry.new_sha),
+        entry.committer,
+        entry.date
+    );
+    if entry.summary.is_none() && !is_zero_sha(&entry.new_sha) {
+        details.push_str(" · commit missing");
+    }
+    let details_label = gtk::Label::builder()
+        .label(details)
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+    details_label.add_css_class("caption");
+    details_label.add_css_class("dim-label");
+
+    let row_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(2)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    row_box.append(&top);
+    row_box.append(&details_label);
+    if let Some(summary) = &entry.summary {
+        row_box.set_tooltip_text(Some(summary));
+    }
+
+    // Only entries whose commit still exists can be shown.
+    gtk::ListBoxRow::builder()
+        .child(&row_box)
+        .activatable(entry.summary.is_some())
+        .build()
+}
+
+fn load_reflog(view: &ReflogView, name: &str) {
+    bump(&view.generation);
+    view.list.remove_all();
+    view.entries.borrow_mut().clear();
+    view.list_stack.set_visible_child_name("loading");
+
+    let repo_path = view.repo_path.clone();
+    let name_for_thread = name.to_string();
+    let name = name.to_string();
+    let view = view.clone();
+    let expected_generation = view.generation.get();
+    run_in_background(
+        move || git::get_reflog(&repo_path, &name_for_thread),
+        move |result| {
+            if view.generation.get() != expected_generation {
+                return;
+            }
+            match result {
+                Ok(entries) if entries.is_empty() => show_placeholder(
+                    &view.list_stack,
+                    "message",
+                    crate::ui::placeholder::ICON_INFO,
+                    &format!("No reflog entries for {name}"),
+                ),
+                Ok(entries) => {
+                    for (index, entry) in entries.iter().enumerate() {
+                        view.list.append(&entry_row(&name, index, entry));
+                    }
+                    *view.entries.borrow_mut() = entries;
+                    view.list_stack.set_visible_child_name("list");
+                }
+                Err(e) => {
+                    Logger::error(&format!("Error reading reflog of {}: {}", name, e));
+                    show_placeholder(
+                        &view.list_stack,
+                        "message",
+                        crate::ui::placeholder::ICON_ERROR,
+                        &format!("Cannot read the reflog of {}: {}", name, e.message()),
+                    );
+                }
+            }
+        },
+    );
+}
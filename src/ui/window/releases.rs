@@ -0,0 +1,383 @@
+//! "Releases" dialog: version tags in version order, each with its tag
+//! message, tagger and the commits since the previous version, plus a
+//! Markdown changelog export grouped by Conventional Commit type.
+//!
+//! The releases are read on a background thread and polled from the main
+//! loop, like the diff loaders.
+
+use adw::prelude::*;
+use gtk::gio;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::git;
+use crate::logger::Logger;
+use crate::releases;
+
+use super::common::{bump, loading_spinner, run_in_background, short_sha, show_placeholder};
+use super::state::AppState;
+use super::ui::WindowUi;
+
+/// Widgets and state shared by the dialog's callbacks.
+#[derive(Clone)]
+struct ReleasesView {
+    dialog: adw::Dialog,
+    list_stack: gtk::Stack,
+    list: gtk::ListBox,
+    releases: Rc<RefCell<Vec<git::Release>>>,
+    detail_stack: gtk::Stack,
+    detail_box: gtk::Box,
+    export_button: gtk::Button,
+    /// Bumped on close, so a late result is dropped.
+    generation: Rc<Cell<u64>>,
+}
+
+/// Open the releases dialog for the current repository.
+pub fn show_releases_dialog(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState) {
+    let Some(repo_path) = state.current_path.borrow().clone() else {
+        return;
+    };
+
+    let export_button = gtk::Button::builder()
+        .icon_name("document-save-symbolic")
+        .tooltip_text("Export Changelog…")
+        .sensitive(false)
+        .build();
+    let header = adw::HeaderBar::new();
+    header.pack_end(&export_button);
+
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::Single)
+        .build();
+    list.add_css_class("navigation-sidebar");
+    let list_scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .child(&list)
+        .build();
+    let list_stack = gtk::Stack::new();
+    list_stack.add_named(&loading_spinner(), Some("loading"));
+    list_stack.add_named(&list_scrolled, Some("list"));
+
+    let detail_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    let detail_scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .hexpand(true)
+        .child(&detail_box)
+        .build();
+    let detail_stack = gtk::Stack::new();
+    detail_stack.add_named(
+        &crate::ui::placeholder::centered(
+            crate::ui::placeholder::ICON_INFO,
+            "Select a release to see what went into it",
+        ),
+        Some("empty"),
+    );
+    detail_stack.add_named(&detail_scrolled, Some("detail"));
+
+    let paned = gtk::Paned::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .start_child(&list_stack)
+        .end_child(&detail_stack)
+        .resize_start_child(false)
+        .shrink_start_child(false)
+        .position(320)
+        .build();
+
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.set_content(Some(&paned));
+
+    let dialog = adw::Dialog::builder()
+        .title("Releases")
+        .content_width(1000)
+        .content_height(720)
+        .child(&toolbar)
+        .build();
+
+    let view = ReleasesView {
+        dialog: dialog.clone(),
+        list_stack,
+        list,
+        releases: Rc::new(RefCell::new(Vec::new())),
+        detail_stack,
+        detail_box,
+        export_button,
+        generation: Rc::new(Cell::new(0)),
+    };
+
+    let view_on_close = view.clone();
+    dialog.connect_closed(move |_| bump(&view_on_close.generation));
+
+    let view_for_select = view.clone();
+    let ui_for_select = ui.clone();
+    view.list.connect_row_selected(move |_, row| {
+        let index = row.and_then(|row| usize::try_from(row.index()).ok());
+        match index {
+            Some(index) if index < view_for_select.releases.borrow().len() => {
+                show_release(&view_for_select, &ui_for_select, index);
+            }
+            _ => view_for_select.detail_stack.set_visible_child_name("empty"),
+        }
+    });
+
+    let view_for_export = view.clone();
+    let window_for_export = window.clone();
+    let repo_name = repo_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    view.export_button.connect_clicked(move |_| {
+        export_changelog(&window_for_export, &view_for_export, &repo_name);
+    });
+
+    load_releases(&view, repo_path);
+    dialog.present(Some(window));
+}
+
+fn commit_count(release: &git::Release) -> String {
+    let count = release.commits.len();
+    let more = if release.truncated { "+" } else { "" };
+    match count {
+        1 => "1 commit".to_string(),
+        _ => format!("{count}{more} commits"),
+    }
+}
+
+fn release_row(release: &git::Release) -> gtk::ListBoxRow {
+    let tag_label = gtk::Label::builder()
+        .label(&release.tag)
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+    tag_label.add_css_class("heading");
+
+    let details_label = gtk::Label::builder()
+        .label(format!(
+            "{} · {}",
+            release.time.format("%Y-%m-%d"),
+            commit_count(release)
+        ))
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+    details_label.add_css_class("caption");
+    details_label.add_css_class("dim-label");
+
+    let row_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(2)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    row_box.append(&tag_label);
+    row_box.append(&details_label);
+
+    gtk::ListBoxRow::builder().child(&row_box).build()
+}
+
+fn load_releases(view: &ReleasesView, repo_path: PathBuf) {
+    bump(&view.generation);
+    view.list_stack.set_visible_child_name("loading");
+
+    let view = view.clone();
+    let expected_generation = view.generation.get();
+    run_in_background(
+        move || releases::load_releases(&repo_path),
+        move |result| {
+            if view.generation.get() != expected_generation {
+                return;
+            }
+            match result {
+                Ok(releases) if releases.is_empty() => show_placeholder(
+                    &view.list_stack,
+                    "message",
+                    crate::ui::placeholder::ICON_INFO,
+                    "No version tags such as v1.2.0 in this repository",
+                ),
+                Ok(releases) => {
+                    for release in &releases {
+                        view.list.append(&release_row(release));
+                    }
+                    *view.releases.borrow_mut() = releases;
+                    view.export_button.set_sensitive(true);
+                    view.list_stack.set_visible_child_name("list");
+                }
+                Err(e) => {
+                    Logger::error(&format!("Error reading releases: {}", e));
+                    show_placeholder(
+                        &view.list_stack,
+                        "message",
+                        crate::ui::placeholder::ICON_ERROR,
+                        &format!("Cannot read the tags: {}", e.message()),
+                    );
+                }
+            }
+        },
+    );
+}
+
+/// Fill the detail pane with the release at `index`. Activating one of its
+/// commits selects it in the commit list.
+fn show_release(view: &ReleasesView, ui: &WindowUi, index: usize) {
+    while let Some(child) = view.detail_box.first_child() {
+        view.detail_box.remove(&child);
+    }
+    let releases = view.releases.borrow();
+    let release = &releases[index];
+
+    let title = gtk::Label::builder()
+        .label(&release.tag)
+        .xalign(0.0)
+        .selectable(true)
+        .build();
+    title.add_css_class("title-2");
+    view.detail_box.append(&title);
+
+    let meta = match &release.tagger {
+        Some(tagger) => format!(
+            "Tagged by {} on {} · {}",
+            tagger,
+            release.time.format("%Y-%m-%d %H:%M"),
+            short_sha(&release.sha)
+        ),
+        None => format!(
+            "Lightweight tag on {} · {}",
+            short_sha(&release.sha),
+            release.time.format("%Y-%m-%d %H:%M")
+        ),
+    };
+    let meta_label = gtk::Label::builder()
+        .label(meta)
+        .xalign(0.0)
+        .wrap(true)
+        .selectable(true)
+        .build();
+    meta_label.add_css_class("dim-label");
+    view.detail_box.append(&meta_label);
+
+    if let Some(message) = &release.message {
+        let message_label = gtk::Label::builder()
+            .label(message)
+            .xalign(0.0)
+            .wrap(true)
+            .selectable(true)
+            .build();
+        view.detail_box.append(&message_label);
+    }
+
+    let since = match &release.previous_tag {
+        Some(previous) => format!("{} since {}", commit_count(release), previous),
+        None => commit_count(release),
+    };
+    let since_label = gtk::Label::builder().label(since).xalign(0.0).build();
+    since_label.add_css_class("heading");
+    view.detail_box.append(&since_label);
+
+    if !release.commits.is_empty() {
+        let commit_list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .build();
+        commit_list.add_css_class("boxed-list");
+        for commit in &release.commits {
+            commit_list.append(&commit_row(commit));
+        }
+        let shas: Vec<String> = release.commits.iter().map(|c| c.id.clone()).collect();
+        let ui_for_activate = ui.clone();
+        let dialog_for_activate = view.dialog.clone();
+        commit_list.connect_row_activated(move |_, row| {
+            if let Some(sha) = usize::try_from(row.index())
+                .ok()
+                .and_then(|idx| shas.get(idx))
+            {
+                ui_for_activate.repo_view.commit_list.select_commit(sha);
+                dialog_for_activate.close();
+            }
+        });
+        view.detail_box.append(&commit_list);
+    }
+    if release.truncated {
+        let truncated_label = gtk::Label::builder()
+            .label(format!(
+                "Only the newest {} commits are listed.",
+                git::RELEASE_COMMIT_LIMIT
+            ))
+            .xalign(0.0)
+            .build();
+        truncated_label.add_css_class("dim-label");
+        view.detail_box.append(&truncated_label);
+    }
+
+    view.detail_stack.set_visible_child_name("detail");
+}
+
+fn commit_row(commit: &git::GitCommit) -> gtk::ListBoxRow {
+    let summary = commit.message.lines().next().unwrap_or("");
+    let sha_label = gtk::Label::new(Some(short_sha(&commit.id)));
+    sha_label.add_css_class("monospace");
+    sha_label.add_css_class("dim-label");
+
+    let summary_label = gtk::Label::builder()
+        .label(summary)
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .tooltip_text(commit.message.trim())
+        .build();
+
+    let author_label = gtk::Label::new(Some(&commit.author));
+    author_label.add_css_class("dim-label");
+
+    let row_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(8)
+        .margin_top(6)
+        .margin_bottom(6)
+        .margin_start(8)
+        .margin_end(8)
+        .build();
+    row_box.append(&sha_label);
+    row_box.append(&summary_label);
+    row_box.append(&author_label);
+
+    gtk::ListBoxRow::builder()
+        .child(&row_box)
+        .activatable(true)
+        .build()
+}
+
+/// Save the changelog of all releases as Markdown.
+fn export_changelog(window: &gtk::ApplicationWindow, view: &ReleasesView, repo_name: &str) {
+    let markdown = releases::changelog_markdown(&view.releases.borrow());
+    let initial_name = match repo_name {
+        "" => "CHANGELOG.md".to_string(),
+        name => format!("{name}-CHANGELOG.md"),
+    };
+    let chooser = gtk::FileDialog::builder()
+        .title("Export Changelog")
+        .initial_name(initial_name)
+        .build();
+    let window_for_save = window.clone();
+    chooser.save(Some(window), gio::Cancellable::NONE, move |result| {
+        let Some(path) = result.ok().and_then(|f| f.path()) else {
+            return;
+        };
+        if let Err(e) = std::fs::write(&path, &markdown) {
+            Logger::error(&format!("Error writing {}: {}", path.display(), e));
+            gtk::AlertDialog::builder()
+                .message("Could not export the changelog")
+                .detail(format!("{}: {}", path.display(), e))
+                .build()
+                .show(Some(&window_for_save));
+        }
+    });
+}
@@ -1,5 +1,6 @@
-use gtk::{gio, glib, prelude::*};
-use std::path::PathBuf;
+use adw::prelude::*;
+use gtk::{gio, glib};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -10,6 +11,7 @@ use crate::ui::RefType;
 use super::recent_repos;
 use super::state::AppState;
 use super::ui::WindowUi;
+use super::watcher::RepoWatcher;
 
 fn ref_type_from_classification(c: RefClassification) -> RefType {
     match c {
@@ -54,11 +56,12 @@ pub fn load_repo_with_selection(
 ) {
     if let Err(e) = git::validate_repository(&path) {
         Logger::error(&format!("Failed to open repository: {}", e));
-        close_repo(ui, state, app_name);
+        close_repo(ui, state);
         return;
     }
 
     *state.current_path.borrow_mut() = Some(path.clone());
+    watch_repo(ui, state, app_name, &path);
 
     let checked_out_branch = git::checked_out_branch_name(&path);
     let mut effective_ref = ref_name.unwrap_or_else(|| git::default_branch_ref(&path));
@@ -154,6 +157,16 @@ pub fn load_repo_with_selection(
         checked_out_branch.as_deref(),
         Some(&effective_ref),
     );
+    ui.repo_view.branch_panel.load_divergence(path.clone());
+
+    let stashes = match git::get_stashes(&path) {
+        Ok(s) => s,
+        Err(e) => {
+            Logger::error(&format!("Error reading stashes: {}", e));
+            Vec::new()
+        }
+    };
+    ui.repo_view.branch_panel.update_stashes(&stashes);
     let _ = ui.repo_view.branch_panel.select_ref(&effective_ref);
 }
 
@@ -191,6 +204,76 @@ pub fn switch_ref(ui: &WindowUi, state: &AppState, ref_name: &str, ref_type: Ref
         });
 }
 
+/// Limit the commit list to commits touching `path` (a file or folder,
+/// relative to the repository root), or show full history again for `None`.
+///
+/// The current ref is reloaded in place, keeping the selected commit if it is
+/// part of the filtered history.
+pub fn set_path_filter(ui: &WindowUi, state: &AppState, path: Option<String>) {
+    let Some(repo_path) = state.current_path.borrow().clone() else {
+        return;
+    };
+    let Some(ref_name) = state.current_ref.borrow().clone() else {
+        return;
+    };
+
+    let path = path
+        .map(|p| p.trim().trim_matches('/').to_string())
+        .filter(|p| !p.is_empty());
+    if *state.path_filter.lock().unwrap() == path {
+        return;
+    }
+
+    *state.path_filter.lock().unwrap() = path.clone();
+    ui.repo_view.commit_list.set_path_filter(path.clone());
+    ui.repo_view.set_path_filter(path.as_deref());
+
+    // Search indices refer to the old list; start over.
+    ui.repo_view.search_entry.set_text("");
+
+    let selected_sha = ui.repo_view.commit_list.selected_commit_sha();
+    ui.repo_view
+        .commit_list
+        .load_commits(repo_path, ref_name, selected_sha, {
+            let current_ref = state.current_ref.clone();
+            move |ref_name| {
+                *current_ref.borrow_mut() = Some(ref_name);
+            }
+        });
+}
+
+/// Ask for a path and show its history.
+pub fn show_path_filter_dialog(window: &gtk::ApplicationWindow, ui: &WindowUi, state: &AppState) {
+    if !state.is_repo_loaded() {
+        return;
+    }
+
+    let entry = gtk::Entry::builder()
+        .placeholder_text("src/main.rs")
+        .activates_default(true)
+        .build();
+    if let Some(path) = state.path_filter.lock().unwrap().as_deref() {
+        entry.set_text(path);
+    }
+
+    let dialog = adw::AlertDialog::new(
+        Some("Show History of Path"),
+        Some("Only commits touching this file or folder will be listed."),
+    );
+    dialog.add_responses(&[("cancel", "_Cancel"), ("filter", "_Show History")]);
+    dialog.set_response_appearance("filter", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("filter"));
+    dialog.set_close_response("cancel");
+    dialog.set_extra_child(Some(&entry));
+
+    let ui = ui.clone();
+    let state = state.clone();
+    dialog.connect_response(Some("filter"), move |_, _| {
+        set_path_filter(&ui, &state, Some(entry.text().to_string()));
+    });
+    dialog.present(Some(window));
+}
+
 pub fn open_repo_dialog(
     window: &gtk::ApplicationWindow,
     ui: &WindowUi,
@@ -438,14 +521,42 @@ pub fn refresh_repo(ui: &WindowUi, state: &AppState, app_name: &str) {
     }
 }
 
-pub fn close_repo(ui: &WindowUi, state: &AppState, app_name: &str) {
+/// Refresh automatically when the repository changes outside the app, unless
+/// turned off in preferences. Keeps the existing watcher on a plain refresh.
+fn watch_repo(ui: &WindowUi, state: &AppState, app_name: &str, path: &Path) {
+    let mut watcher = state.repo_watcher.borrow_mut();
+    if watcher.as_ref().is_some_and(|w| w.repo_path() == path) {
+        return;
+    }
+
+    let settings = gio::Settings::new(crate::APP_ID);
+    let ui = ui.clone();
+    let state_for_change = state.clone();
+    let app_name = app_name.to_string();
+    let watched_path = path.to_path_buf();
+    *watcher = RepoWatcher::new(path, move || {
+        let state = &state_for_change;
+        if !settings.boolean("auto-refresh")
+            || *state.file_portal_active.borrow()
+            || state.current_path.borrow().as_deref() != Some(watched_path.as_path())
+        {
+            return;
+        }
+        Logger::info("Repository changed on disk, refreshing");
+        refresh_repo(&ui, state, &app_name);
+    });
+}
+
+pub fn close_repo(ui: &WindowUi, state: &AppState) {
     state.clear_repo();
-    ui.reset_title(app_name);
+    ui.reset_title();
 
     ui.repo_view.commit_list.clear();
+    ui.repo_view.set_path_filter(None);
     ui.repo_view
         .branch_panel
         .update_refs(&[], &[], &[], None, None);
+    ui.repo_view.branch_panel.update_stashes(&[]);
 
     // Reset search UI
     ui.repo_view.search_bar.set_search_mode(false);
@@ -467,9 +578,11 @@ pub fn reset_for_repo_switch(ui: &WindowUi, state: &AppState) {
 
     // Clear panels while the new repo loads.
     ui.repo_view.commit_list.clear();
+    ui.repo_view.set_path_filter(None);
     ui.repo_view
         .branch_panel
         .update_refs(&[], &[], &[], None, None);
+    ui.repo_view.branch_panel.update_stashes(&[]);
 
     // Reset search UI.
     ui.repo_view.search_bar.set_search_mode(false);
@@ -479,47 +592,3 @@ pub fn reset_for_repo_switch(ui: &WindowUi, state: &AppState) {
 
     ui.repo_view.reset_diff(None);
 }
-
-pub fn maybe_load_repo_from_cwd(
-    window: &gtk::ApplicationWindow,
-    ui: &WindowUi,
-    state: &AppState,
-    app_name: &str,
-) -> bool {
-    // If launched from a terminal, auto-open the current working directory if it
-    // is inside a Git repository (worktree root preferred).
-    if let Ok(cwd) = std::env::current_dir() {
-        if let Some(repo_root) = git::discover_repository_root(&cwd) {
-            if repo_root.exists() {
-                if let Err(e) = git::validate_repository(&repo_root) {
-                    show_repo_error(window, &repo_root, &e.to_string());
-                    ui.set_repo_controls_visible(false);
-                    ui.show_welcome();
-                    return false;
-                }
-
-                let started_at = Instant::now();
-                ui.repo_view
-                    .commit_paging_state
-                    .borrow_mut()
-                    .pending_first_page_log = Some((
-                    started_at,
-                    repo_root.clone(),
-                    "Auto-open repo from CWD -> rendered on screen".to_string(),
-                ));
-
-                let branch = git::checked_out_branch_name(&repo_root);
-                // For non-portal access, real_path and sandbox_path are the same
-                recent_repos::add_recent_repo(&repo_root, &repo_root);
-                ui.set_repo_controls_visible(true);
-                ui.show_main();
-                load_repo(ui, state, app_name, repo_root, branch);
-                return true;
-            }
-        }
-    }
-
-    ui.set_repo_controls_visible(false);
-    ui.show_welcome();
-    false
-}
@@ -4,7 +4,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 
 use crate::search::SearchHandler;
-use crate::ui::{CommitLoadRequest, CommitPagingState};
+use crate::ui::{CommitLoadRequest, CommitPagingState, commit_row};
 
 use super::state::AppState;
 use super::ui::WindowUi;
@@ -29,13 +29,10 @@ fn ensure_loaded_then_select(
         return;
     }
 
-    if store.n_items() > target_index {
-        selection_model.select_item(target_index, true);
-        crate::search::SearchHandler::scroll_to_item(
-            &scrolled_window,
-            &selection_model,
-            target_index,
-        );
+    let row = commit_row(&store, target_index);
+    if store.n_items() > row {
+        selection_model.select_item(row, true);
+        crate::search::SearchHandler::scroll_to_item(&scrolled_window, &selection_model, row);
         return;
     }
 
@@ -72,7 +69,7 @@ pub struct SearchController {
 
 impl SearchController {
     pub fn connect(ui: &WindowUi, state: &AppState) -> Self {
-        let handler = SearchHandler::new();
+        let handler = SearchHandler::with_path_filter(state.path_filter.clone());
         let debounce_source: std::rc::Rc<std::cell::RefCell<Option<glib::SourceId>>> =
             std::rc::Rc::new(std::cell::RefCell::new(None));
         let current_cancel: std::rc::Rc<std::cell::RefCell<Option<Arc<AtomicBool>>>> =
@@ -269,133 +266,75 @@ impl SearchController {
         Self { handler }
     }
 
-    pub fn action_show_search(&self, ui: &WindowUi) -> gio::ActionEntry<gtk::ApplicationWindow> {
-        let search_bar = ui.repo_view.search_bar.clone();
-        let search_entry = ui.repo_view.search_entry.clone();
-        let search_status_label = ui.repo_view.search_status_label.clone();
-        let last_search_status = ui.repo_view.last_search_status.clone();
-        gio::ActionEntry::builder("show-search")
-            .activate(move |_, _, _| {
-                search_bar.set_search_mode(true);
-                search_entry.grab_focus();
-
-                let text = search_entry.text();
-                if !text.is_empty() {
-                    let status = last_search_status.borrow().clone();
-                    if !status.is_empty() {
-                        search_status_label.set_text(&status);
-                    }
-                }
-            })
-            .build()
+    /// Show the search bar of the tab `ui` and focus its entry.
+    pub fn show_search(&self, ui: &WindowUi) {
+        let repo_view = &ui.repo_view;
+        repo_view.search_bar.set_search_mode(true);
+        repo_view.search_entry.grab_focus();
+
+        let text = repo_view.search_entry.text();
+        if !text.is_empty() {
+            let status = repo_view.last_search_status.borrow().clone();
+            if !status.is_empty() {
+                repo_view.search_status_label.set_text(&status);
+            }
+        }
     }
 
-    pub fn action_hide_search(&self, ui: &WindowUi) -> gio::ActionEntry<gtk::ApplicationWindow> {
-        let search_bar = ui.repo_view.search_bar.clone();
-        gio::ActionEntry::builder("hide-search")
-            .activate(move |_, _, _| {
-                if search_bar.is_search_mode() {
-                    search_bar.set_search_mode(false);
-                }
-            })
-            .build()
+    pub fn hide_search(&self, ui: &WindowUi) {
+        if ui.repo_view.search_bar.is_search_mode() {
+            ui.repo_view.search_bar.set_search_mode(false);
+        }
     }
 
-    pub fn action_find_next(
-        &self,
-        ui: &WindowUi,
-        state: &AppState,
-    ) -> gio::ActionEntry<gtk::ApplicationWindow> {
-        let search_entry = ui.repo_view.search_entry.clone();
-        let store = ui.repo_view.commit_list.store.clone();
-        let selection_model = ui.repo_view.commit_list.selection_model.clone();
-        let scrolled_window = ui.repo_view.commit_list.widget.clone();
-        let commit_paging_state = ui.repo_view.commit_paging_state.clone();
-        let handler = self.handler.clone();
-        let state = state.clone();
-        gio::ActionEntry::builder("find-next")
-            .activate(move |_, _, _| {
-                let text = search_entry.text();
-                if text.is_empty() {
-                    return;
-                }
-                let query = clamp_search_query(text.as_str());
-                if query.to_lowercase().is_empty() {
-                    return;
-                }
-
-                let path_opt = state.current_path.borrow().clone();
-                let ref_opt = state.current_ref.borrow().clone();
-                if path_opt.is_none() {
-                    return;
-                }
-                let path = path_opt.unwrap();
-                let ref_name = ref_opt.as_deref().unwrap_or("HEAD");
-
-                if let Some(target_index) = handler.compute_next_match_index(query, &path, ref_name)
-                {
-                    let expected_generation = commit_paging_state.borrow().generation;
-                    ensure_loaded_then_select(
-                        target_index,
-                        expected_generation,
-                        store.clone(),
-                        selection_model.clone(),
-                        scrolled_window.clone(),
-                        commit_paging_state.clone(),
-                        0,
-                    );
-                }
-            })
-            .build()
+    /// Select the next match of the current query, wrapping around.
+    pub fn find_next(&self, ui: &WindowUi, state: &AppState) {
+        self.find(ui, state, true);
     }
 
-    pub fn action_find_previous(
-        &self,
-        ui: &WindowUi,
-        state: &AppState,
-    ) -> gio::ActionEntry<gtk::ApplicationWindow> {
-        let search_entry = ui.repo_view.search_entry.clone();
-        let store = ui.repo_view.commit_list.store.clone();
-        let selection_model = ui.repo_view.commit_list.selection_model.clone();
-        let scrolled_window = ui.repo_view.commit_list.widget.clone();
-        let commit_paging_state = ui.repo_view.commit_paging_state.clone();
-        let handler = self.handler.clone();
-        let state = state.clone();
-        gio::ActionEntry::builder("find-previous")
-            .activate(move |_, _, _| {
-                let text = search_entry.text();
-                if text.is_empty() {
-                    return;
-                }
-                let query = clamp_search_query(text.as_str());
-                if query.to_lowercase().is_empty() {
-                    return;
-                }
+    /// Select the previous match of the current query, wrapping around.
+    pub fn find_previous(&self, ui: &WindowUi, state: &AppState) {
+        self.find(ui, state, false);
+    }
 
-                let path_opt = state.current_path.borrow().clone();
-                let ref_opt = state.current_ref.borrow().clone();
-                if path_opt.is_none() {
-                    return;
-                }
-                let path = path_opt.unwrap();
-                let ref_name = ref_opt.as_deref().unwrap_or("HEAD");
+    fn find(&self, ui: &WindowUi, state: &AppState, forward: bool) {
+        let text = ui.repo_view.search_entry.text();
+        if text.is_empty() {
+            return;
+        }
+        let query = clamp_search_query(text.as_str());
+        if query.to_lowercase().is_empty() {
+            return;
+        }
 
-                if let Some(target_index) =
-                    handler.compute_previous_match_index(query, &path, ref_name)
-                {
-                    let expected_generation = commit_paging_state.borrow().generation;
-                    ensure_loaded_then_select(
-                        target_index,
-                        expected_generation,
-                        store.clone(),
-                        selection_model.clone(),
-                        scrolled_window.clone(),
-                        commit_paging_state.clone(),
-                        0,
-                    );
-                }
-            })
-            .build()
+        let path_opt = state.current_path.borrow().clone();
+        let ref_opt = state.current_ref.borrow().clone();
+        if path_opt.is_none() {
+            return;
+        }
+        let path = path_opt.unwrap();
+        let ref_name = ref_opt.as_deref().unwrap_or("HEAD");
+
+        let target_index = if forward {
+            self.handler
+                .compute_next_match_index(query, &path, ref_name)
+        } else {
+            self.handler
+                .compute_previous_match_index(query, &path, ref_name)
+        };
+        if let Some(target_index) = target_index {
+            let commit_paging_state = ui.repo_view.commit_paging_state.clone();
+            let expected_generation = commit_paging_state.borrow().generation;
+            ensure_loaded_then_select(
+                target_index,
+                expected_generation,
+                ui.repo_view.commit_list.store.clone(),
+                ui.repo_view.commit_list.selection_model.clone(),
+                ui.repo_view.commit_list.widget.clone(),
+                commit_paging_state,
+                0,
+            );
+        }
     }
 }
 
@@ -438,8 +377,8 @@ fn poll_search_result(
         // Ensure the first match is present in the paged store before processing the result.
         // Search matches are global indices (revwalk order), while the UI list is loaded lazily.
         if result.error.is_none() && !result.matching_indices.is_empty() {
-            let first_index = result.matching_indices[0];
-            if store.n_items() <= first_index {
+            let first_row = commit_row(&store, result.matching_indices[0]);
+            if store.n_items() <= first_row {
                 let mut st = commit_paging_state.borrow_mut();
                 let can_page = !st.done && st.request_tx.is_some();
                 if can_page && attempt < 200 {
@@ -486,6 +425,9 @@ fn poll_search_result(
         let search_status_label_clone = search_status_label.clone();
         let last_search_status_clone = last_search_status.clone();
         let search_spinner_clone = search_spinner.clone();
+        // Query syntax errors (bad regex, date, ...) are shown in full as a tooltip.
+        let error = result.error.clone();
+        search_status_label.set_tooltip_text(error.as_deref());
         search_handler.process_search_result_async(
             result,
             store,
@@ -494,6 +436,7 @@ fn poll_search_result(
             move |match_count| {
                 let status_text = match match_count {
                     Some(count) => format_match_count(count),
+                    None if error.is_some() => "Invalid query".to_string(),
                     None => String::new(),
                 };
                 search_status_label_clone.set_text(&status_text);
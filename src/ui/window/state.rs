@@ -1,9 +1,10 @@
 use std::cell::RefCell;
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
 
+use super::watcher::RepoWatcher;
 use crate::ui::RefType;
 
 #[derive(Clone)]
@@ -13,22 +14,32 @@ pub struct AppState {
     pub current_ref: Rc<RefCell<Option<String>>>,
     /// The type of the currently viewed ref
     pub current_ref_type: Rc<RefCell<Option<RefType>>>,
+    /// Path the commit list is limited to (file or folder history), if any.
+    /// Shared with the search worker so match indices follow the filtered list.
+    pub path_filter: Arc<Mutex<Option<String>>>,
     pub file_portal_active: Rc<RefCell<bool>>,
+    /// File to expand and scroll to once a diff is shown, with the commit
+    /// whose diff that must be (`None` for the first diff shown). Set when
+    /// opening a deep link.
+    pub pending_diff_file: Rc<RefCell<Option<(Option<String>, String)>>>,
+    /// Watches the open repository for changes made outside the app.
+    pub repo_watcher: Rc<RefCell<Option<RepoWatcher>>>,
     pub tokio_runtime: Arc<Runtime>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
-        // Create a shared Tokio runtime for file portal operations
-        // This ensures DBus connections are properly managed and reused
-        let runtime = Runtime::new().expect("Failed to create Tokio runtime for file portal");
-
+    /// `tokio_runtime` is shared by all tabs of a window for file portal
+    /// operations.
+    pub fn new(tokio_runtime: Arc<Runtime>) -> Self {
         Self {
             current_path: Rc::new(RefCell::new(None)),
             current_ref: Rc::new(RefCell::new(None)),
             current_ref_type: Rc::new(RefCell::new(None)),
+            path_filter: Arc::new(Mutex::new(None)),
             file_portal_active: Rc::new(RefCell::new(false)),
-            tokio_runtime: Arc::new(runtime),
+            pending_diff_file: Rc::new(RefCell::new(None)),
+            repo_watcher: Rc::new(RefCell::new(None)),
+            tokio_runtime,
         }
     }
 
@@ -36,6 +47,9 @@ impl AppState {
         *self.current_path.borrow_mut() = None;
         *self.current_ref.borrow_mut() = None;
         *self.current_ref_type.borrow_mut() = None;
+        *self.path_filter.lock().unwrap() = None;
+        *self.pending_diff_file.borrow_mut() = None;
+        self.repo_watcher.borrow_mut().take();
     }
 
     pub fn is_repo_loaded(&self) -> bool {
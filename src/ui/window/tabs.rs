@@ -0,0 +1,183 @@
+//! Tabs within a window. Each tab shows one repository (or the welcome
+//! screen) with its own `AppState`, `RepoView` and search state; window
+//! actions act on the selected tab.
+
+use gtk::{gio, glib, prelude::*};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+use super::search::SearchController;
+use super::state::AppState;
+use super::ui::{WindowChrome, WindowUi};
+
+#[derive(Clone)]
+pub struct Tab {
+    pub ui: WindowUi,
+    pub state: AppState,
+    pub search: SearchController,
+}
+
+#[derive(Clone)]
+pub struct Tabs {
+    pub chrome: WindowChrome,
+    tabs: Rc<RefCell<Vec<Tab>>>,
+    // Shared by all tabs for file portal operations
+    tokio_runtime: Arc<Runtime>,
+}
+
+impl Tabs {
+    pub fn new(window: &gtk::ApplicationWindow, chrome: WindowChrome) -> Self {
+        // Create a shared Tokio runtime for file portal operations
+        // This ensures DBus connections are properly managed and reused
+        let runtime = Runtime::new().expect("Failed to create Tokio runtime for file portal");
+
+        let tabs = Self {
+            chrome,
+            tabs: Rc::new(RefCell::new(Vec::new())),
+            tokio_runtime: Arc::new(runtime),
+        };
+
+        // Header title and controls follow the selected tab.
+        let tabs_for_select = tabs.clone();
+        tabs.chrome.tab_view.connect_selected_page_notify(move |_| {
+            if let Some(tab) = tabs_for_select.current() {
+                tab.ui.apply_to_window();
+            }
+        });
+
+        // Stop the closed tab's background work before its widgets go away.
+        let tabs_for_close = tabs.clone();
+        tabs.chrome.tab_view.connect_close_page(move |_, page| {
+            let mut open = tabs_for_close.tabs.borrow_mut();
+            if let Some(idx) = open.iter().position(|t| &t.ui.page == page) {
+                let tab = open.remove(idx);
+                tab.state.clear_repo();
+                tab.ui.repo_view.commit_list.clear();
+            }
+            glib::Propagation::Proceed
+        });
+
+        // Closing the last tab closes the window.
+        let window_for_empty = window.clone();
+        tabs.chrome.tab_view.connect_n_pages_notify(move |view| {
+            if view.n_pages() == 0 {
+                window_for_empty.close();
+            }
+        });
+
+        tabs
+    }
+
+    /// Open a new tab on the welcome screen and select it. `wire` connects
+    /// the tab's widgets before it is shown.
+    pub fn add_tab(&self, wire: impl FnOnce(&WindowUi, &AppState)) -> Tab {
+        let ui = WindowUi::build(&self.chrome);
+        let state = AppState::new(self.tokio_runtime.clone());
+        wire(&ui, &state);
+        let search = SearchController::connect(&ui, &state);
+
+        let tab = Tab { ui, state, search };
+        self.tabs.borrow_mut().push(tab.clone());
+        self.chrome.tab_view.set_selected_page(&tab.ui.page);
+        // Selecting an already selected page does not notify.
+        tab.ui.apply_to_window();
+        tab
+    }
+
+    /// The selected tab.
+    pub fn current(&self) -> Option<Tab> {
+        let page = self.chrome.tab_view.selected_page()?;
+        self.tabs
+            .borrow()
+            .iter()
+            .find(|t| t.ui.page == page)
+            .cloned()
+    }
+
+    pub fn all(&self) -> Vec<Tab> {
+        self.tabs.borrow().clone()
+    }
+
+    /// Select the tab showing the repository at `path`, if any.
+    pub fn select_repo(&self, path: &Path) -> bool {
+        let found = self
+            .tabs
+            .borrow()
+            .iter()
+            .find(|t| t.state.current_path.borrow().as_deref() == Some(path))
+            .map(|t| t.ui.page.clone());
+        match found {
+            Some(page) => {
+                self.chrome.tab_view.set_selected_page(&page);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close the selected tab (and the window with the last one).
+    pub fn close_current(&self) {
+        if let Some(page) = self.chrome.tab_view.selected_page() {
+            self.chrome.tab_view.close_page(&page);
+        }
+    }
+
+    /// Repository paths open in tabs, in tab order, and the index of the
+    /// selected one among them.
+    fn open_repos(&self) -> (Vec<String>, i32) {
+        let view = &self.chrome.tab_view;
+        let selected = view.selected_page();
+        let tabs = self.tabs.borrow();
+        let mut paths = Vec::new();
+        let mut selected_idx = 0;
+        for position in 0..view.n_pages() {
+            let page = view.nth_page(position);
+            let Some(tab) = tabs.iter().find(|t| t.ui.page == page) else {
+                continue;
+            };
+            let Some(path) = tab.state.current_path.borrow().clone() else {
+                continue;
+            };
+            if selected.as_ref() == Some(&page) {
+                selected_idx = paths.len() as i32;
+            }
+            paths.push(path.to_string_lossy().into_owned());
+        }
+        (paths, selected_idx)
+    }
+
+    /// Remember the open repositories so they are restored at the next start.
+    /// Only writes when something changed.
+    pub fn save_open_repos(&self, settings: &gio::Settings) {
+        let (paths, selected) = self.open_repos();
+        let saved: Vec<String> = settings
+            .strv("open-tabs")
+            .iter()
+            .map(|s| s.as_str().to_string())
+            .collect();
+        if saved != paths {
+            let _ = settings.set_strv("open-tabs", paths.as_slice());
+        }
+        if settings.int("selected-tab") != selected {
+            let _ = settings.set_int("selected-tab", selected);
+        }
+    }
+}
+
+/// Repositories that were open in tabs when the app last ran, and the one
+/// that was selected. Paths that no longer exist are skipped.
+pub fn saved_open_repos(settings: &gio::Settings) -> (Vec<PathBuf>, Option<PathBuf>) {
+    let paths: Vec<PathBuf> = settings
+        .strv("open-tabs")
+        .iter()
+        .map(|s| PathBuf::from(s.as_str()))
+        .collect();
+    let selected = usize::try_from(settings.int("selected-tab"))
+        .ok()
+        .and_then(|i| paths.get(i).cloned());
+    let existing = paths.into_iter().filter(|p| p.exists()).collect();
+    (existing, selected)
+}
@@ -1,13 +1,17 @@
-use gtk::{gio, prelude::*};
+use gtk::{gio, glib, prelude::*};
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::path::PathBuf;
 use std::rc::Rc;
 
 use crate::ui::{RepoView, WelcomeView};
 
+/// Tab title while a tab shows the welcome screen.
+const NEW_TAB_TITLE: &str = "New Tab";
+
+/// Window-level widgets shared by all tabs: the header bar and the tab view.
 #[derive(Clone)]
-pub struct WindowUi {
+pub struct WindowChrome {
     // The top-level window (used to keep the OS window title in sync).
     window: gtk::ApplicationWindow,
     // The application name, used when composing the OS window title.
@@ -19,20 +23,17 @@ pub struct WindowUi {
     pub open_button: gtk::Button,
     pub search_button: gtk::Button,
 
-    // Root navigation stack
-    pub stack: gtk::Stack,
-
-    // Repository screen (search + panels + diff)
-    pub repo_view: RepoView,
+    // One page per open repository (or welcome screen)
+    pub tab_view: adw::TabView,
 
-    // Welcome screen (new component-based approach)
-    welcome_view: WelcomeView,
+    // Transient notifications shown over all tabs
+    toast_overlay: adw::ToastOverlay,
 
     // Action that requires a repository to be loaded
     refresh_action: Rc<RefCell<Option<gio::SimpleAction>>>,
 }
 
-impl WindowUi {
+impl WindowChrome {
     pub fn build(window: gtk::ApplicationWindow, app_name: &str) -> Self {
         // App-wide CSS tweaks used by multiple screens.
         crate::ui::styles::install();
@@ -71,8 +72,20 @@ impl WindowUi {
             .tooltip_text("Menu")
             .build();
         let menu = gio::Menu::new();
+        let tab_section = gio::Menu::new();
+        tab_section.append(Some("New Tab"), Some("win.new-tab"));
+        menu.append_section(None, &tab_section);
         let menu_section = gio::Menu::new();
         menu_section.append(Some("Reload Repository"), Some("win.refresh"));
+        menu_section.append(Some("Show History of Path…"), Some("win.filter-path"));
+        menu_section.append(Some("Compare…"), Some("win.compare"));
+        menu_section.append(Some("Reflog…"), Some("win.reflog::HEAD"));
+        menu_section.append(Some("Releases…"), Some("win.releases"));
+        menu_section.append(
+            Some("Export Selected as Patches…"),
+            Some("win.export-patches"),
+        );
+        menu_section.append(Some("Preferences"), Some("win.preferences"));
         menu_section.append(Some("Keyboard Shortcuts"), Some("win.show-help-overlay"));
         menu_section.append(Some(&format!("About {}", app_name)), Some("win.about"));
         menu.append_section(None, &menu_section);
@@ -99,35 +112,112 @@ impl WindowUi {
 
         window.set_titlebar(Some(&header_bar));
 
+        // Tabs: the bar only shows up once there is more than one tab.
+        let tab_view = adw::TabView::builder().hexpand(true).vexpand(true).build();
+        let tab_bar = adw::TabBar::builder()
+            .view(&tab_view)
+            .autohide(true)
+            .build();
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        content.append(&tab_bar);
+        content.append(&tab_view);
+        let toast_overlay = adw::ToastOverlay::builder().child(&content).build();
+        window.set_child(Some(&toast_overlay));
+
+        Self {
+            window,
+            app_name: app_name.to_string(),
+            title,
+            close_repo_button,
+            open_button,
+            search_button,
+            tab_view,
+            toast_overlay,
+            refresh_action: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Store the refresh action so we can enable/disable it based on repo state.
+    pub fn set_refresh_action(&self, action: gio::SimpleAction) {
+        *self.refresh_action.borrow_mut() = Some(action);
+    }
+}
+
+/// One tab: the welcome screen and repository view for a single repository.
+/// Header title and controls follow the selected tab.
+#[derive(Clone)]
+pub struct WindowUi {
+    chrome: WindowChrome,
+
+    // Root navigation stack of this tab
+    pub stack: gtk::Stack,
+    pub page: adw::TabPage,
+
+    // Repository screen (search + panels + diff)
+    pub repo_view: RepoView,
+
+    // Welcome screen (new component-based approach)
+    welcome_view: WelcomeView,
+
+    // Repository name and subtitle shown while this tab is selected
+    repo_title: Rc<RefCell<Option<(String, String)>>>,
+    repo_controls_visible: Rc<Cell<bool>>,
+}
+
+impl WindowUi {
+    /// Build a new tab and append it to the window's tab view.
+    pub fn build(chrome: &WindowChrome) -> Self {
         // Root stack holds either welcome or main UI
         let stack = gtk::Stack::builder().hexpand(true).vexpand(true).build();
-        window.set_child(Some(&stack));
 
         // Repository view (search + panels + diff)
-        let repo_view = RepoView::new(&window);
+        let repo_view = RepoView::new(&chrome.window);
         stack.add_named(&repo_view.widget, Some("main"));
 
         // Welcome view (using the new component)
-        let welcome_view = WelcomeView::new(&window);
+        let welcome_view = WelcomeView::new(&chrome.window);
         stack.add_named(&welcome_view.widget, Some("welcome"));
 
+        let page = chrome.tab_view.append(&stack);
+        page.set_title(NEW_TAB_TITLE);
+
         Self {
-            window,
-            app_name: app_name.to_string(),
-            title,
-            close_repo_button,
-            open_button,
-            search_button,
+            chrome: chrome.clone(),
             stack,
+            page,
             repo_view,
             welcome_view,
-            refresh_action: Rc::new(RefCell::new(None)),
+            repo_title: Rc::new(RefCell::new(None)),
+            repo_controls_visible: Rc::new(Cell::new(false)),
         }
     }
 
-    /// Store the refresh action so we can enable/disable it based on repo state.
-    pub fn set_refresh_action(&self, action: gio::SimpleAction) {
-        *self.refresh_action.borrow_mut() = Some(action);
+    fn is_selected(&self) -> bool {
+        self.chrome.tab_view.selected_page().as_ref() == Some(&self.page)
+    }
+
+    /// Show `text` in a toast over the window. It is plain text, not markup.
+    pub fn show_toast(&self, text: &str) {
+        let toast = adw::Toast::builder().title(text).use_markup(false).build();
+        self.chrome.toast_overlay.add_toast(toast);
+    }
+
+    /// Show this tab's title and repository controls in the window header.
+    /// Called when the tab becomes the selected one.
+    pub fn apply_to_window(&self) {
+        self.apply_repo_controls();
+        match self.repo_title.borrow().as_ref() {
+            Some((repo_name, subtitle)) => {
+                self.chrome.title.set_title(repo_name);
+                self.chrome.title.set_subtitle(subtitle);
+                self.update_os_title(repo_name, subtitle);
+            }
+            None => {
+                self.chrome.title.set_title(&self.chrome.app_name);
+                self.chrome.title.set_subtitle("");
+                self.chrome.window.set_title(Some(&self.chrome.app_name));
+            }
+        }
     }
 
     /// Set a callback for when a recent repository card is clicked.
@@ -147,49 +237,71 @@ impl WindowUi {
     }
 
     pub fn set_repo_controls_visible(&self, visible: bool) {
-        self.search_button.set_visible(visible);
-        self.close_repo_button.set_visible(visible);
+        self.repo_controls_visible.set(visible);
+        if self.is_selected() {
+            self.apply_repo_controls();
+        }
+    }
+
+    fn apply_repo_controls(&self) {
+        let visible = self.repo_controls_visible.get();
+        self.chrome.search_button.set_visible(visible);
+        self.chrome.close_repo_button.set_visible(visible);
 
         // Enable/disable the refresh action based on whether a repo is loaded
-        if let Some(ref action) = *self.refresh_action.borrow() {
+        if let Some(ref action) = *self.chrome.refresh_action.borrow() {
             action.set_enabled(visible);
         }
     }
 
-    /// Set the header title to the repository name with a subtitle (typically
-    /// the current branch/ref). Also updates the OS window title (taskbar,
-    /// alt-tab, overview) to "repo - branch - AppName".
+    /// Set the tab title to the repository name and, while this tab is
+    /// selected, the header title to the repository name with a subtitle
+    /// (typically the current branch/ref). Also updates the OS window title
+    /// (taskbar, alt-tab, overview) to "repo - branch - AppName".
     pub fn set_repo_title(&self, repo_name: &str, subtitle: &str) {
-        self.title.set_title(repo_name);
-        self.title.set_subtitle(subtitle);
-        self.update_os_title(repo_name, subtitle);
+        *self.repo_title.borrow_mut() = Some((repo_name.to_string(), subtitle.to_string()));
+        self.page.set_title(repo_name);
+        self.page.set_tooltip(&glib::markup_escape_text(&format!(
+            "{repo_name} - {subtitle}"
+        )));
+        if self.is_selected() {
+            self.apply_to_window();
+        }
     }
 
-    /// Update only the header subtitle (e.g. after switching branch or tag).
+    /// Update only the subtitle (e.g. after switching branch or tag).
     /// Keeps the OS window title in sync with the new branch/ref.
     pub fn set_repo_subtitle(&self, subtitle: &str) {
-        self.title.set_subtitle(subtitle);
-        let repo_name = self.title.title();
-        self.update_os_title(repo_name.as_str(), subtitle);
+        let repo_name = self
+            .repo_title
+            .borrow()
+            .as_ref()
+            .map(|(name, _)| name.clone());
+        if let Some(repo_name) = repo_name {
+            self.set_repo_title(&repo_name, subtitle);
+        }
     }
 
-    /// Reset the header and OS window titles back to the application name with
-    /// no subtitle.
-    pub fn reset_title(&self, app_name: &str) {
-        self.title.set_title(app_name);
-        self.title.set_subtitle("");
-        self.window.set_title(Some(app_name));
+    /// Reset the tab, header and OS window titles back to the application
+    /// name with no subtitle.
+    pub fn reset_title(&self) {
+        *self.repo_title.borrow_mut() = None;
+        self.page.set_title(NEW_TAB_TITLE);
+        self.page.set_tooltip("");
+        if self.is_selected() {
+            self.apply_to_window();
+        }
     }
 
     /// Compose and apply the OS window title as "repo - branch - AppName"
     /// (omitting the branch when it is empty).
     fn update_os_title(&self, repo_name: &str, branch: &str) {
         let os_title = if branch.is_empty() {
-            format!("{} - {}", repo_name, self.app_name)
+            format!("{} - {}", repo_name, self.chrome.app_name)
         } else {
-            format!("{} - {} - {}", repo_name, branch, self.app_name)
+            format!("{} - {} - {}", repo_name, branch, self.chrome.app_name)
         };
-        self.window.set_title(Some(&os_title));
+        self.chrome.window.set_title(Some(&os_title));
     }
 
     pub fn show_main(&self) {
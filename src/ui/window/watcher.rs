@@ -0,0 +1,163 @@
+//! Watches a repository's git directory so commits, fetches and rebases made
+//! outside the app (e.g. in a terminal) trigger a refresh.
+//!
+//! `HEAD`, `index`, `packed-refs` and every directory under `refs/` are
+//! monitored. Bursts of events (a rebase touches many refs) are debounced
+//! into a single callback.
+
+use gtk::{gio, glib, prelude::*};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use crate::git;
+use crate::logger::Logger;
+
+/// Quiet period after the last change before the callback runs.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Files directly inside the git directory whose changes matter.
+const WATCHED_FILES: &[&str] = &["HEAD", "index", "packed-refs"];
+
+pub struct RepoWatcher {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    repo_path: PathBuf,
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+    monitors: RefCell<Vec<gio::FileMonitor>>,
+    pending: RefCell<Option<glib::SourceId>>,
+    on_change: Box<dyn Fn()>,
+}
+
+impl RepoWatcher {
+    /// Start watching the repository at `repo_path`. Returns `None` if it is
+    /// not a repository.
+    pub fn new(repo_path: &Path, on_change: impl Fn() + 'static) -> Option<Self> {
+        let (git_dir, common_dir) = git::git_dirs(repo_path)?;
+        let inner = Rc::new(Inner {
+            repo_path: repo_path.to_path_buf(),
+            git_dir,
+            common_dir,
+            monitors: RefCell::new(Vec::new()),
+            pending: RefCell::new(None),
+            on_change: Box::new(on_change),
+        });
+        Inner::watch(&inner);
+        Some(Self { inner })
+    }
+
+    pub fn repo_path(&self) -> &Path {
+        &self.inner.repo_path
+    }
+}
+
+impl Inner {
+    /// (Re)create the monitors. Called again after each change so ref
+    /// directories created since the last scan (e.g. a new remote) are
+    /// watched too.
+    fn watch(this: &Rc<Self>) {
+        // (directory, whether only `WATCHED_FILES` in it matter)
+        let mut dirs = vec![(this.git_dir.clone(), true)];
+        if this.common_dir != this.git_dir {
+            dirs.push((this.common_dir.clone(), true));
+        }
+        collect_dirs(&this.common_dir.join("refs"), &mut dirs);
+
+        let mut monitors = Vec::with_capacity(dirs.len());
+        for (dir, files_only) in dirs {
+            let monitor = match gio::File::for_path(&dir)
+                .monitor_directory(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE)
+            {
+                Ok(m) => m,
+                Err(e) => {
+                    Logger::error(&format!("Cannot watch {}: {}", dir.display(), e));
+                    continue;
+                }
+            };
+            let weak = Rc::downgrade(this);
+            monitor.connect_changed(move |_, file, _, event| {
+                if !is_relevant_event(event) {
+                    return;
+                }
+                let Some(name) = file.basename() else {
+                    return;
+                };
+                let name = name.to_string_lossy();
+                // Git writes `<file>.lock` and renames it into place.
+                let relevant = if files_only {
+                    WATCHED_FILES.contains(&name.as_ref())
+                } else {
+                    !name.ends_with(".lock")
+                };
+                if relevant {
+                    Inner::schedule(&weak);
+                }
+            });
+            monitors.push(monitor);
+        }
+
+        for old in this.monitors.replace(monitors) {
+            old.cancel();
+        }
+    }
+
+    /// Restart the debounce timer.
+    fn schedule(weak: &Weak<Self>) {
+        let Some(this) = weak.upgrade() else {
+            return;
+        };
+        if let Some(source) = this.pending.borrow_mut().take() {
+            source.remove();
+        }
+        let weak = weak.clone();
+        let source = glib::timeout_add_local_once(DEBOUNCE, move || {
+            let Some(this) = weak.upgrade() else {
+                return;
+            };
+            this.pending.borrow_mut().take();
+            Inner::watch(&this);
+            (this.on_change)();
+        });
+        *this.pending.borrow_mut() = Some(source);
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        for monitor in self.monitors.borrow().iter() {
+            monitor.cancel();
+        }
+        if let Some(source) = self.pending.borrow_mut().take() {
+            source.remove();
+        }
+    }
+}
+
+fn is_relevant_event(event: gio::FileMonitorEvent) -> bool {
+    matches!(
+        event,
+        gio::FileMonitorEvent::Changed
+            | gio::FileMonitorEvent::Created
+            | gio::FileMonitorEvent::Deleted
+            | gio::FileMonitorEvent::Renamed
+            | gio::FileMonitorEvent::MovedIn
+            | gio::FileMonitorEvent::MovedOut
+    )
+}
+
+/// Add `dir` and all directories below it, marked as ref directories.
+fn collect_dirs(dir: &Path, out: &mut Vec<(PathBuf, bool)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    out.push((dir.to_path_buf(), false));
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect_dirs(&entry.path(), out);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Small helpers shared by the git, release and UI code.
+
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Natural comparison for strings (e.g., "branch-2" < "branch-10").
+pub fn natural_compare(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        let (a_next, b_next) = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&a), Some(&b)) => (a, b),
+        };
+
+        if a_next.is_ascii_digit() && b_next.is_ascii_digit() {
+            // Both start with digits - compare numerically
+            match take_number(&mut a_chars).cmp(&take_number(&mut b_chars)) {
+                Ordering::Equal => continue,
+                other => return other,
+            }
+        }
+
+        // At least one is not a digit - compare lexicographically,
+        // case-insensitively first
+        a_chars.next();
+        b_chars.next();
+        match a_next
+            .to_ascii_lowercase()
+            .cmp(&b_next.to_ascii_lowercase())
+            .then(a_next.cmp(&b_next))
+        {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+}
+
+/// Consume the run of ASCII digits at the front of `chars` (leaving the
+/// character after it) and return its value.
+fn take_number(chars: &mut Peekable<Chars>) -> u64 {
+    let mut value: u64 = 0;
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+        value = value.saturating_mul(10).saturating_add(u64::from(digit));
+        chars.next();
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_compare_orders_numbers_by_value() {
+        assert_eq!(natural_compare("branch-2", "branch-10"), Ordering::Less);
+        assert_eq!(natural_compare("v1.10", "v1.9"), Ordering::Greater);
+        assert_eq!(natural_compare("rc.02", "rc.2"), Ordering::Equal);
+        assert_eq!(natural_compare("a", "a1"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_compare_keeps_the_character_after_a_number() {
+        assert_eq!(natural_compare("1.0.0-rc1a", "1.0.0-rc1b"), Ordering::Less);
+        assert_eq!(natural_compare("rc1b", "rc1a"), Ordering::Greater);
+        assert_eq!(natural_compare("item2x", "item2"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_compare_ignores_case_before_breaking_ties_by_it() {
+        assert_eq!(natural_compare("Alpha", "beta"), Ordering::Less);
+        assert_eq!(natural_compare("Main", "main"), Ordering::Less);
+    }
+}